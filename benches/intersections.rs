@@ -0,0 +1,83 @@
+use std::f64::consts::PI;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use raytracer::{
+    draw::io::obj::ObjFileParser,
+    math::{point::Point, ray::Ray, transformation::Transformable, tuple::Tuple, vector::Vector},
+    render::{camera::Camera, intersections::Intersections, object::Object, world::World},
+};
+
+// Baselines from `cargo bench` on the development machine, in rays/sec. They are
+// only a rough guide for spotting large regressions:
+//
+//   sphere           ~19M
+//   cube             ~19M
+//   cylinder         ~14M
+//   teddy bear mesh  ~7K
+//   default world    ~3.3M (World::color_at)
+//
+// world::test::default_world_color_at_throughput guards the last one in debug builds.
+
+const SIZE: usize = 32;
+
+// A fixed 32x32 fan of rays from z = -5 aimed at the origin
+fn rays() -> Vec<Ray> {
+    let camera = Camera::new(SIZE, SIZE, PI / 9.0).view_transform(
+        &Point::new(0.0, 0.0, -5.0),
+        &Point::new(0.0, 0.0, 0.0),
+        &Vector::new(0.0, 1.0, 0.0),
+    );
+    (0..SIZE)
+        .flat_map(|y| (0..SIZE).map(move |x| (x, y)))
+        .map(|(x, y)| camera.ray_for_pixel(x, y))
+        .collect()
+}
+
+fn bench_object(c: &mut Criterion, name: &str, object: &Object) {
+    let rays = rays();
+    let mut group = c.benchmark_group("intersect");
+    group.throughput(Throughput::Elements(rays.len() as u64));
+    group.bench_function(name, |b| {
+        b.iter(|| {
+            let mut count = 0;
+            for ray in &rays {
+                let mut ints = Intersections::new();
+                object.intersect(ray, &mut ints);
+                count += ints.len();
+            }
+            count
+        })
+    });
+    group.finish();
+}
+
+fn primitives(c: &mut Criterion) {
+    bench_object(c, "sphere", &Object::new_sphere());
+    bench_object(c, "cube", &Object::new_cube());
+    bench_object(c, "cylinder", &Object::new_cylinder(-1.0, 1.0, true));
+}
+
+fn mesh(c: &mut Criterion) {
+    let bear = ObjFileParser::new_file("./assets/obj/teddy_bear.obj")
+        .expect("teddy_bear.obj is part of the repo")
+        .build()
+        .scale(0.1, 0.1, 0.1);
+    bench_object(c, "teddy bear mesh", &bear);
+}
+
+fn default_world(c: &mut Criterion) {
+    let world = World::default();
+    let rays = rays();
+    let mut group = c.benchmark_group("world");
+    group.throughput(Throughput::Elements(rays.len() as u64));
+    group.bench_function("color_at", |b| {
+        b.iter(|| {
+            rays.iter()
+                .fold(0.0, |acc, ray| acc + world.color_at(ray, 5).as_tuple().0)
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, primitives, mesh, default_world);
+criterion_main!(benches);
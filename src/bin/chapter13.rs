@@ -26,8 +26,8 @@ fn create_floor() -> Object {
     Object::new_plane()
         .with_pattern(
             Pattern::new_checker(dark, light)
-                .scale(0.25, 0.25, 0.25)
-                .rotate_y(0.3),
+                .rotate_y(0.3)
+                .tile_size(0.25),
         )
         .with_ambient(0.2)
         .with_diffuse(0.9)
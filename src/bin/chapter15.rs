@@ -2,6 +2,7 @@ use std::f64::consts::PI;
 
 use raytracer::{
     draw::{color::Color, io::obj::ObjFileParser},
+    error::Error,
     math::{point::Point, transformation::Transformable, tuple::Tuple, vector::Vector},
     render::{
         camera::Camera,
@@ -37,7 +38,7 @@ fn light() -> Light {
     ))
 }
 
-fn main() -> std::io::Result<()> {
+fn main() -> Result<(), Error> {
     let width = 100;
     let height = 50;
     let fov = PI / 3.0;
@@ -47,7 +48,7 @@ fn main() -> std::io::Result<()> {
         &Vector::new(0.0, 1.0, 0.0),
     );
 
-    let cow = ObjFileParser::new_file("./assets/obj/pumpkin.obj")
+    let cow = ObjFileParser::new_file("./assets/obj/pumpkin.obj")?
         .build()
         .scale(0.1, 0.1, 0.1)
         .translate(0.0, 2.5, -2.0);
@@ -57,5 +58,6 @@ fn main() -> std::io::Result<()> {
     world.add_object(floor());
     world.add_object(cow);
 
-    world.render(&camera).save("./", "chapter15")
+    world.render(&camera).save("./", "chapter15")?;
+    Ok(())
 }
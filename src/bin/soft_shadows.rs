@@ -0,0 +1,57 @@
+/**
+ *
+ * Soft shadows from an area light
+ *
+ */
+use raytracer::{
+    draw::color::Color,
+    math::{point::Point, transformation::Transformable, tuple::Tuple, vector::Vector},
+    render::{
+        camera::Camera, light::Light, lights::area_light::AreaLight, material::Materialable,
+        object::Object, pattern::Pattern, world::World,
+    },
+};
+
+// Fixed so the penumbra comes out the same on every run
+const SEED: u64 = 7;
+
+fn create_light() -> Light {
+    Light::Area(
+        AreaLight::new(
+            Point::new(-3.0, 4.0, -2.0),
+            Vector::new(2.0, 0.0, 0.0),
+            8,
+            Vector::new(0.0, 2.0, 0.0),
+            8,
+            Color::white(),
+        )
+        .with_jitter(SEED),
+    )
+}
+
+fn create_floor() -> Object {
+    Object::new_plane().with_specular(0.0)
+}
+
+fn create_sphere() -> Object {
+    Object::new_sphere()
+        .translate(0.0, 1.0, 0.0)
+        .with_pattern(Pattern::new_solid(Color::new(1.0, 0.2, 0.2)))
+}
+
+fn main() -> std::io::Result<()> {
+    let width: usize = 800;
+    let height: usize = 400;
+    let mut world = World::new();
+    world.add_light(create_light());
+    world.add_object(create_floor());
+    world.add_object(create_sphere());
+
+    let camera = Camera::new(width, height, std::f64::consts::PI / 3.0).view_transform(
+        &Point::new(0.0, 3.0, -6.0),
+        &Point::new(0.5, 0.5, 0.0),
+        &Vector::new(0.0, 1.0, 0.0),
+    );
+
+    world.render(&camera).save("./", "soft_shadows")
+}
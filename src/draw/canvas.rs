@@ -1,33 +1,46 @@
 use std::{fmt::Display, fs::File, io::Write, path::Path};
 
-use super::color::Color;
+use crate::error::Error;
+
+use super::{color::Color, io::ppm};
 
 pub type Position = (usize, usize);
 
-#[derive(Debug)]
+// Longest line the PPM format allows
+const PPM_LINE_LENGTH: usize = 70;
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Canvas {
     width: usize,
     height: usize,
     data: Vec<u8>,
+    alpha: Option<Vec<u8>>, // Coverage per pixel, only tracked when enabled
+    srgb: bool,             // Bytes hold sRGB encoded colors rather than linear ones
 }
 
 impl Display for Canvas {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str("P3\n")?;
-        write!(f, "{} {}\n", self.width, self.height)?;
+        writeln!(f, "{} {}", self.width, self.height)?;
         f.write_str("255\n")?;
 
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let index = self.to_index(&(x, y));
-                let r = self.data.get(index).unwrap_or(&0);
-                let g = self.data.get(index + 1).unwrap_or(&0);
-                let b = self.data.get(index + 2).unwrap_or(&0);
-                write!(f, "{} {} {} ", r, g, b)?;
-                if x > 1 && x % 4 == 0 {
-                    f.write_str("\n")?;
+        // Every row starts a new line, and long rows wrap before a value would push
+        // the line past the limit
+        let mut line = String::new();
+        for row in self.data.chunks_exact((self.width * 3).max(1)) {
+            for value in row {
+                let token = value.to_string();
+                if !line.is_empty() && line.len() + 1 + token.len() > PPM_LINE_LENGTH {
+                    writeln!(f, "{}", line)?;
+                    line.clear();
                 }
+                if !line.is_empty() {
+                    line.push(' ');
+                }
+                line.push_str(&token);
             }
+            writeln!(f, "{}", line)?;
+            line.clear();
         }
         Ok(())
     }
@@ -39,11 +52,89 @@ impl Canvas {
             width,
             height,
             data: vec![0; width * height * 3],
+            alpha: None,
+            srgb: false,
+        }
+    }
+
+    // Tracks an alpha channel alongside the colors, starting fully transparent
+    pub fn with_alpha(mut self) -> Self {
+        self.alpha = Some(vec![0; self.width * self.height]);
+        self
+    }
+
+    // Stores colors sRGB encoded, the way image viewers expect them. Colors are still
+    // set and read back as linear values.
+    pub fn with_srgb(mut self) -> Self {
+        self.srgb = true;
+        self
+    }
+
+    pub fn is_srgb(&self) -> bool {
+        self.srgb
+    }
+
+    // How colors set on this canvas are turned into bytes
+    pub(crate) fn encoder(&self) -> fn(&Color) -> (u8, u8, u8) {
+        if self.srgb {
+            Color::scale_srgb
+        } else {
+            Color::scale
+        }
+    }
+
+    pub fn has_alpha(&self) -> bool {
+        self.alpha.is_some()
+    }
+
+    pub fn set_alpha(&mut self, (x, y): Position, alpha: f64) {
+        let index = (y * self.width) + x;
+        if let Some(value) = self.alpha.as_mut().and_then(|a| a.get_mut(index)) {
+            *value = (alpha.clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+    }
+
+    pub fn alpha_at(&self, (x, y): Position) -> Option<f64> {
+        let index = (y * self.width) + x;
+        self.alpha
+            .as_ref()
+            .and_then(|a| a.get(index))
+            .map(|value| (*value as f64) / 255.0)
+    }
+
+    // RGBA bytes row by row from the top left, opaque everywhere when no alpha is tracked
+    pub fn rgba_bytes(&self) -> Vec<u8> {
+        self.data
+            .chunks_exact(3)
+            .enumerate()
+            .flat_map(|(i, rgb)| {
+                let a = self.alpha.as_ref().map_or(255, |a| a[i]);
+                [rgb[0], rgb[1], rgb[2], a]
+            })
+            .collect()
+    }
+
+    // Loads a plain or binary PPM image
+    pub fn from_ppm(path: &str) -> Result<Self, Error> {
+        ppm::parse(&std::fs::read(path)?)
+    }
+
+    pub fn filled(width: usize, height: usize, color: Color) -> Self {
+        let mut canvas = Self::new(width, height);
+        canvas.clear(color);
+        canvas
+    }
+
+    // Sets every pixel to the given color
+    pub fn clear(&mut self, color: Color) {
+        let (r, g, b) = self.encoder()(&color);
+        for pixel in self.data.chunks_exact_mut(3) {
+            pixel.copy_from_slice(&[r, g, b]);
         }
     }
 
     pub fn set_pixel(&mut self, pos: Position, color: &Color) {
-        let (r, g, b) = color.scale();
+        let (r, g, b) = self.encoder()(color);
 
         let index = self.to_index(&pos);
         if index + 2 < self.data.len() {
@@ -59,11 +150,27 @@ impl Canvas {
             let r: f64 = (self.data[index] as f64) / 255.0;
             let g: f64 = (self.data[index + 1] as f64) / 255.0;
             let b: f64 = (self.data[index + 2] as f64) / 255.0;
+            if self.srgb {
+                return Some(Color::srgb(r, g, b));
+            }
             return Some(Color::new(r, g, b));
         }
         None
     }
 
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    // Tightly packed RGB bytes, row by row from the top left
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub(crate) fn as_bytes_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+
     pub fn save(&self, dir: &str, name: &str) -> std::io::Result<()> {
         let file_name = [dir, "/", name, ".ppm"].concat();
         let file_path = Path::new(file_name.as_str());
@@ -72,11 +179,103 @@ impl Canvas {
         Ok(())
     }
 
+    // Binary PPM, the header followed by the raw RGB bytes
+    pub fn save_binary(&self, dir: &str, name: &str) -> std::io::Result<()> {
+        let file_name = [dir, "/", name, ".ppm"].concat();
+        let mut file = File::create(Path::new(file_name.as_str()))?;
+        write!(file, "P6\n{} {}\n255\n", self.width, self.height)?;
+        file.write_all(&self.data)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "png")]
+    pub fn save_png(&self, dir: &str, name: &str) -> std::io::Result<()> {
+        let file_name = [dir, "/", name, ".png"].concat();
+        let file = File::create(Path::new(file_name.as_str()))?;
+        let mut encoder = png::Encoder::new(
+            std::io::BufWriter::new(file),
+            self.width as u32,
+            self.height as u32,
+        );
+        encoder.set_depth(png::BitDepth::Eight);
+        // Canvases tracking coverage keep it as the PNG's alpha channel
+        if self.has_alpha() {
+            encoder.set_color(png::ColorType::Rgba);
+            encoder
+                .write_header()?
+                .write_image_data(&self.rgba_bytes())?;
+        } else {
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.write_header()?.write_image_data(&self.data)?;
+        }
+        Ok(())
+    }
+
+    pub fn flip_vertical(&mut self) {
+        flip_vertical(&mut self.data, self.width, self.height, 3);
+        if let Some(alpha) = &mut self.alpha {
+            flip_vertical(alpha, self.width, self.height, 1);
+        }
+    }
+
+    pub fn flip_horizontal(&mut self) {
+        flip_horizontal(&mut self.data, self.width, self.height, 3);
+        if let Some(alpha) = &mut self.alpha {
+            flip_horizontal(alpha, self.width, self.height, 1);
+        }
+    }
+
+    // Rotates the image 90 degrees clockwise, swapping width and height
+    pub fn rotate_90(&mut self) {
+        self.data = rotate_90(&self.data, self.width, self.height, 3);
+        if let Some(alpha) = &self.alpha {
+            self.alpha = Some(rotate_90(alpha, self.width, self.height, 1));
+        }
+        std::mem::swap(&mut self.width, &mut self.height);
+    }
+
     fn to_index(&self, (x, y): &Position) -> usize {
         ((y * self.width) + x) * 3
     }
 }
 
+// Image operations over a buffer with `channels` bytes per pixel, shared by the color
+// and alpha buffers
+
+fn flip_vertical(buf: &mut [u8], width: usize, height: usize, channels: usize) {
+    let row_len = width * channels;
+    for y in 0..(height / 2) {
+        let (top, bottom) = buf.split_at_mut((height - 1 - y) * row_len);
+        top[(y * row_len)..((y + 1) * row_len)].swap_with_slice(&mut bottom[..row_len]);
+    }
+}
+
+fn flip_horizontal(buf: &mut [u8], width: usize, height: usize, channels: usize) {
+    for y in 0..height {
+        for x in 0..(width / 2) {
+            let left = ((y * width) + x) * channels;
+            let right = ((y * width) + (width - 1 - x)) * channels;
+            for channel in 0..channels {
+                buf.swap(left + channel, right + channel);
+            }
+        }
+    }
+}
+
+fn rotate_90(buf: &[u8], width: usize, height: usize, channels: usize) -> Vec<u8> {
+    let mut rotated = vec![0; buf.len()];
+    let new_width = height;
+
+    for y in 0..height {
+        for x in 0..width {
+            let from = ((y * width) + x) * channels;
+            let to = ((x * new_width) + (new_width - 1 - y)) * channels;
+            rotated[to..(to + channels)].copy_from_slice(&buf[from..(from + channels)]);
+        }
+    }
+    rotated
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -93,6 +292,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn canvas_can_be_created_filled_with_a_color() {
+        let c = Canvas::filled(2, 2, Color::red());
+        for pos in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            assert_eq!(c.pixel_at(pos).unwrap(), Color::red());
+        }
+    }
+
+    #[test]
+    fn canvas_clear_overwrites_every_pixel() {
+        let mut c = Canvas::new(3, 2);
+        c.set_pixel((1, 1), &Color::green());
+        c.clear(Color::blue());
+        for y in 0..2 {
+            for x in 0..3 {
+                assert_eq!(c.pixel_at((x, y)).unwrap(), Color::blue());
+            }
+        }
+    }
+
     #[test]
     fn canvas_can_write_to_canvas() {
         let mut c = Canvas::new(10, 20);
@@ -135,11 +354,11 @@ mod tests {
         let mut line_count = 0;
         for line in c.to_string().lines() {
             if line_count == 3 {
-                assert_eq!(line, "255 0 0 0 0 0 0 0 0 0 0 0 0 0 0 ")
+                assert_eq!(line, "255 0 0 0 0 0 0 0 0 0 0 0 0 0 0")
             } else if line_count == 4 {
-                assert_eq!(line, "0 0 0 0 0 0 0 127 0 0 0 0 0 0 0 ")
+                assert_eq!(line, "0 0 0 0 0 0 0 127 0 0 0 0 0 0 0")
             } else if line_count == 5 {
-                assert_eq!(line, "0 0 0 0 0 0 0 0 0 0 0 0 0 0 255 ")
+                assert_eq!(line, "0 0 0 0 0 0 0 0 0 0 0 0 0 0 255")
             } else if line_count > 6 {
                 break;
             }
@@ -161,24 +380,138 @@ mod tests {
 
         let mut line_count = 0;
         for line in c.to_string().lines() {
-            if line_count == 3 {
-                assert_eq!(
-                    line,
-                    "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 "
-                )
-            } else if line_count == 4 {
-                assert_eq!(line, "255 204 153 255 204 153 255 204 153 255 204 153 ")
-            } else if line_count == 5 {
+            if line_count == 3 || line_count == 5 {
                 assert_eq!(
                     line,
-                    "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 "
+                    "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204"
                 )
-            } else if line_count == 6 {
-                assert_eq!(line, "255 204 153 255 204 153 255 204 153 255 204 153 ")
+            } else if line_count == 4 || line_count == 6 {
+                assert_eq!(line, "153 255 204 153 255 204 153 255 204 153 255 204 153")
             } else if line_count > 7 {
                 break;
             }
             line_count += 1;
         }
     }
+
+    #[test]
+    fn canvas_flip_vertical_swaps_top_and_bottom_rows() {
+        let mut c = Canvas::new(2, 3);
+        c.set_pixel((0, 0), &Color::red());
+        c.set_pixel((1, 2), &Color::blue());
+        c.flip_vertical();
+
+        assert_eq!(c.pixel_at((0, 2)).unwrap(), Color::red());
+        assert_eq!(c.pixel_at((1, 0)).unwrap(), Color::blue());
+        assert_eq!(c.pixel_at((0, 0)).unwrap(), Color::black());
+        assert_eq!(c.pixel_at((1, 2)).unwrap(), Color::black());
+    }
+
+    #[test]
+    fn canvas_flip_horizontal_swaps_left_and_right_columns() {
+        let mut c = Canvas::new(3, 2);
+        c.set_pixel((0, 1), &Color::green());
+        c.flip_horizontal();
+
+        assert_eq!(c.pixel_at((2, 1)).unwrap(), Color::green());
+        assert_eq!(c.pixel_at((0, 1)).unwrap(), Color::black());
+    }
+
+    #[test]
+    fn canvas_rotate_90_transposes_dimensions() {
+        let mut c = Canvas::new(4, 2);
+        c.set_pixel((0, 0), &Color::red());
+        c.set_pixel((3, 1), &Color::blue());
+        c.rotate_90();
+
+        assert_eq!(c.width, 2);
+        assert_eq!(c.height, 4);
+        assert_eq!(c.pixel_at((1, 0)).unwrap(), Color::red());
+        assert_eq!(c.pixel_at((0, 3)).unwrap(), Color::blue());
+    }
+
+    #[test]
+    fn canvas_exposes_raw_rgb_bytes() {
+        let mut c = Canvas::new(4, 3);
+        c.set_pixel((2, 1), &Color::new(1.0, 0.5, 0.0));
+
+        assert_eq!(c.dimensions(), (4, 3));
+        assert_eq!(c.as_bytes().len(), 4 * 3 * 3);
+
+        let offset = (1 * 4 + 2) * 3;
+        assert_eq!(&c.as_bytes()[offset..(offset + 3)], &[255, 127, 0]);
+    }
+
+    #[test]
+    fn canvas_alpha_follows_rotations_and_rgba_output() {
+        let mut c = Canvas::new(2, 1).with_alpha();
+        c.set_pixel((1, 0), &Color::red());
+        c.set_alpha((1, 0), 1.0);
+        assert_eq!(c.alpha_at((0, 0)), Some(0.0));
+        assert_eq!(c.rgba_bytes(), vec![0, 0, 0, 0, 255, 0, 0, 255]);
+
+        c.rotate_90();
+        assert_eq!(c.alpha_at((0, 1)), Some(1.0));
+        assert_eq!(c.alpha_at((0, 0)), Some(0.0));
+
+        let opaque = Canvas::new(1, 1);
+        assert!(!opaque.has_alpha());
+        assert_eq!(opaque.rgba_bytes(), vec![0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn srgb_canvases_store_encoded_bytes() {
+        let mut c = Canvas::new(2, 1).with_srgb();
+        c.set_pixel((0, 0), &Color::new(0.5, 0.5, 0.5));
+        c.set_pixel((1, 0), &Color::srgb(0.2, 0.4, 0.6));
+        assert_eq!(c.as_bytes(), [188, 188, 188, 51, 102, 153]);
+        assert_eq!(c.pixel_at((1, 0)), Some(Color::srgb(0.2, 0.4, 0.6)));
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn canvas_can_save_a_png() {
+        let c = Canvas::filled(4, 3, Color::new(1.0, 0.5, 0.0));
+        let dir = std::env::temp_dir();
+        let dir = dir.to_str().unwrap();
+        c.save_png(dir, "canvas_save_png_test").unwrap();
+
+        let path = [dir, "/canvas_save_png_test.png"].concat();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(bytes[..4], [0x89, b'P', b'N', b'G']);
+
+        let mut reader = png::Decoder::new(bytes.as_slice()).read_info().unwrap();
+        let mut decoded = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut decoded).unwrap();
+        assert_eq!(decoded, c.as_bytes());
+    }
+
+    #[test]
+    fn canvas_can_save_a_binary_ppm() {
+        let mut c = Canvas::new(5, 3);
+        c.set_pixel((0, 0), &Color::new(1.0, 0.0, 0.0));
+        let dir = std::env::temp_dir();
+        let dir = dir.to_str().unwrap();
+        c.save_binary(dir, "canvas_save_binary_test").unwrap();
+
+        let path = [dir, "/canvas_save_binary_test.ppm"].concat();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let header = b"P6\n5 3\n255\n";
+        assert_eq!(&bytes[..header.len()], header);
+        assert_eq!(bytes[header.len()..header.len() + 3], [255, 0, 0]);
+        assert_eq!(bytes.len(), header.len() + 5 * 3 * 3);
+    }
+
+    #[test]
+    fn canvas_ppm_lines_never_exceed_70_characters() {
+        let c = Canvas::filled(20, 2, Color::new(1.0, 1.0, 1.0));
+        let ppm = c.to_string();
+        assert!(ppm.ends_with('\n'));
+        for line in ppm.lines() {
+            assert!(line.len() <= 70, "{} characters: {}", line.len(), line);
+            assert!(!line.ends_with(' '));
+        }
+    }
 }
@@ -15,6 +15,16 @@ impl Color {
         Self { r, g, b }
     }
 
+    // Builds a linear color from display (sRGB encoded) values, e.g. ones picked in
+    // an image editor
+    pub fn srgb(r: f64, g: f64, b: f64) -> Self {
+        Self {
+            r: srgb_to_linear(r),
+            g: srgb_to_linear(g),
+            b: srgb_to_linear(b),
+        }
+    }
+
     pub fn scale(&self) -> (u8, u8, u8) {
         let red = ((self.r * 255.0) as u8).max(0).min(255);
         let green = ((self.g * 255.0) as u8).max(0).min(255);
@@ -22,10 +32,22 @@ impl Color {
         (red, green, blue)
     }
 
+    // Encodes the linear color to sRGB display bytes. Anything that blends colors, like
+    // averaging anti-aliasing samples, has to happen before this step.
+    pub fn scale_srgb(&self) -> (u8, u8, u8) {
+        let encode = |c: f64| (linear_to_srgb(c.clamp(0.0, 1.0)) * 255.0).round() as u8;
+        (encode(self.r), encode(self.g), encode(self.b))
+    }
+
     pub fn as_tuple(&self) -> (f64, f64, f64) {
         (self.r, self.g, self.b)
     }
 
+    // Relative luminance using the Rec. 709 primaries
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
     pub fn black() -> Color {
         Color {
             r: 0.0,
@@ -67,6 +89,22 @@ impl Color {
     }
 }
 
+fn srgb_to_linear(c: f64) -> f64 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f64) -> f64 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
 impl Display for Color {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(format!("({}, {}, {})", self.r, self.g, self.b).as_str())
@@ -185,4 +223,24 @@ mod tests {
         let got = c1 * c2;
         assert_eq!(got, want);
     }
+
+    #[test]
+    fn srgb_colors_are_decoded_to_linear() {
+        let c = Color::srgb(0.5, 0.5, 0.5);
+        assert!(c.r.approx_eq(0.21404));
+        assert!(c.g.approx_eq(0.21404));
+        assert!(c.b.approx_eq(0.21404));
+
+        assert_eq!(
+            Color::srgb(0.0, 1.0, 0.02),
+            Color::new(0.0, 1.0, 0.02 / 12.92)
+        );
+    }
+
+    #[test]
+    fn linear_colors_are_encoded_to_srgb_bytes() {
+        assert_eq!(Color::black().scale_srgb(), (0, 0, 0));
+        assert_eq!(Color::white().scale_srgb(), (255, 255, 255));
+        assert_eq!(Color::srgb(0.2, 0.4, 0.6).scale_srgb(), (51, 102, 153));
+    }
 }
@@ -0,0 +1,19 @@
+// An image ready to hand to a GUI or texture upload: tightly packed 8 bit RGBA, row by
+// row from the top left. Alpha is straight (not premultiplied), 255 where a ray hit an
+// object and 0 where it missed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub width: usize,
+    pub height: usize,
+    pub rgba: Vec<u8>,
+}
+
+impl Frame {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            rgba: vec![0; width * height * 4],
+        }
+    }
+}
@@ -1,16 +1,24 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
 use crate::{
+    draw::color::Color,
+    error::Error,
     math::{point::Point, tuple::Tuple, vector::Vector},
     render::{
         material::{Material, Materialable},
         object::Object,
+        pattern::Pattern,
+        shapes::indexed_mesh::{IndexedMesh, MeshFace},
     },
 };
 
 #[derive(Debug, Clone, PartialEq)]
 struct FaceVertex {
     vertex: usize,
+    texcoord: Option<usize>,
     normal: Option<usize>,
 }
 
@@ -18,6 +26,7 @@ struct FaceVertex {
 struct Face {
     vertices: Vec<FaceVertex>,
     group: Option<String>,
+    material: Option<String>, // Name of the MTL material active when the face was read
 }
 
 impl Default for Face {
@@ -25,33 +34,59 @@ impl Default for Face {
         Self {
             vertices: vec![],
             group: None,
+            material: None,
         }
     }
 }
 
+// How faces with more than 3 vertices are split into triangles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Triangulation {
+    // Fan out from the first vertex of the face
+    #[default]
+    Fan,
+    // Split quads along their shorter diagonal, larger polygons still fan
+    ShortestDiagonal,
+}
+
 #[derive(Debug)]
 pub struct ObjFileParser {
     input: String,
     lines_ignored: usize,
     vertices: Vec<Point>,
     normals: Vec<Vector>,
+    texcoords: Vec<(f64, f64)>,
     faces: Vec<Face>,
     current_group: Option<String>,
     material: Material,
+    mtl_dir: PathBuf, // mtllib paths are relative to the OBJ file
+    materials: HashMap<String, Material>,
+    current_material: Option<String>,
+    triangulation: Triangulation,
+    flip_normals: bool,
+    auto_orient: bool,
 }
 
 impl ObjFileParser {
-    pub fn new_file(path: &str) -> Self {
-        let err_message = format!("Error reading OBJ file: {}", path);
-        Self {
-            input: std::fs::read_to_string(path).expect(&err_message),
+    pub fn new_file(path: &str) -> Result<Self, Error> {
+        Ok(Self {
+            input: std::fs::read_to_string(path)?,
             lines_ignored: 0,
             vertices: vec![],
             normals: vec![],
+            texcoords: vec![],
             faces: vec![],
             current_group: None,
             material: Material::default(),
-        }
+            mtl_dir: Path::new(path)
+                .parent()
+                .map_or_else(PathBuf::new, Path::to_path_buf),
+            materials: HashMap::new(),
+            current_material: None,
+            triangulation: Triangulation::default(),
+            flip_normals: false,
+            auto_orient: false,
+        })
     }
 
     pub fn new_input(input: String) -> Self {
@@ -60,35 +95,50 @@ impl ObjFileParser {
             lines_ignored: 0,
             vertices: vec![],
             normals: vec![],
+            texcoords: vec![],
             faces: vec![],
             current_group: None,
             material: Material::default(),
+            mtl_dir: PathBuf::new(),
+            materials: HashMap::new(),
+            current_material: None,
+            triangulation: Triangulation::default(),
+            flip_normals: false,
+            auto_orient: false,
         }
     }
 
     pub fn build(&mut self) -> Object {
         self.parse();
 
+        if self.flip_normals || (self.auto_orient && self.mostly_inward()) {
+            self.flip_faces();
+        }
+
         let mut root_children = Vec::<Object>::new();
-        let mut group_hash = HashMap::<String, Vec<Object>>::new();
+        // Groups are kept in the order they first appear in the file so builds are repeatable
+        let mut group_index = HashMap::<String, usize>::new();
+        let mut group_tris = Vec::<Vec<Object>>::new();
 
         for face in &self.faces {
-            let mut tris = self.fan_triangulation(&face.vertices);
+            let material = self.face_material(face);
+            let mut tris: Vec<Object> = self
+                .triangulate(&face.vertices)
+                .iter()
+                .map(|tri| self.triangle_object(tri).with_material(material.clone()))
+                .collect();
             if let Some(grp) = &face.group {
-                group_hash.insert(grp.clone(), tris);
+                let index = *group_index.entry(grp.clone()).or_insert_with(|| {
+                    group_tris.push(vec![]);
+                    group_tris.len() - 1
+                });
+                group_tris[index].append(&mut tris);
             } else {
                 root_children.append(&mut tris);
             }
         }
 
-        let mut groups: Vec<Object> = group_hash
-            .keys()
-            .into_iter()
-            .filter_map(|k| match group_hash.get(k) {
-                Some(tris) => Some(Object::new_group(tris.clone())),
-                None => None,
-            })
-            .collect();
+        let mut groups: Vec<Object> = group_tris.into_iter().map(Object::new_group).collect();
 
         if groups.len() == 1 {
             match groups.first() {
@@ -101,17 +151,103 @@ impl ObjFileParser {
         }
     }
 
+    // Builds every face into one IndexedMesh sharing the file's vertex and normal
+    // buffers. Groups are flattened, the whole file becomes a single shape with a single
+    // material, so MTL materials are ignored.
+    pub fn build_mesh(&mut self) -> Object {
+        self.parse();
+
+        if self.flip_normals || (self.auto_orient && self.mostly_inward()) {
+            self.flip_faces();
+        }
+
+        let faces = self
+            .faces
+            .iter()
+            .flat_map(|face| self.triangulate(&face.vertices))
+            .map(|[v1, v2, v3]| {
+                let face = MeshFace::new([
+                    self.vertex_slot(v1.vertex),
+                    self.vertex_slot(v2.vertex),
+                    self.vertex_slot(v3.vertex),
+                ]);
+                match (v1.normal, v2.normal, v3.normal) {
+                    (Some(n1), Some(n2), Some(n3)) => face.with_normals([
+                        self.normal_slot(n1),
+                        self.normal_slot(n2),
+                        self.normal_slot(n3),
+                    ]),
+                    _ => face,
+                }
+            })
+            .collect();
+
+        let mesh = IndexedMesh::new(self.vertices.clone(), self.normals.clone(), faces);
+        Object::new_mesh(mesh).with_material(self.material.clone())
+    }
+
+    pub fn with_triangulation(self, triangulation: Triangulation) -> Self {
+        Self {
+            triangulation,
+            ..self
+        }
+    }
+
+    // Reverses the winding of every face (and any vertex normals) for files that
+    // wind their faces clockwise
+    pub fn with_flip_normals(self, flip_normals: bool) -> Self {
+        Self {
+            flip_normals,
+            ..self
+        }
+    }
+
+    // Flips the mesh when most of its faces point towards the mesh's centroid
+    pub fn with_auto_orient(self, auto_orient: bool) -> Self {
+        Self {
+            auto_orient,
+            ..self
+        }
+    }
+
     pub fn build_with_material(&mut self, mat: Material) -> Object {
         self.material = mat;
         self.build()
     }
 
+    // Like build, but a malformed vertex or face line is an error instead of being skipped
+    pub fn try_build(&mut self) -> Result<Object, Error> {
+        self.check()?;
+        Ok(self.build())
+    }
+
+    pub fn try_build_with_material(&mut self, mat: Material) -> Result<Object, Error> {
+        self.check()?;
+        Ok(self.build_with_material(mat))
+    }
+
     fn get_vertex(&self, index: usize) -> Point {
-        self.vertices[(index - 1).max(0).min(self.vertices.len() - 1)]
+        self.vertices[self.vertex_slot(index)]
     }
 
     fn get_normal(&self, index: usize) -> Vector {
-        self.normals[(index - 1).max(0).min(self.normals.len() - 1)]
+        self.normals[self.normal_slot(index)]
+    }
+
+    fn get_texcoord(&self, index: usize) -> Option<(f64, f64)> {
+        index
+            .checked_sub(1)
+            .and_then(|slot| self.texcoords.get(slot))
+            .copied()
+    }
+
+    // Position of a 1-based OBJ index in the vertex buffer
+    fn vertex_slot(&self, index: usize) -> usize {
+        (index - 1).max(0).min(self.vertices.len() - 1)
+    }
+
+    fn normal_slot(&self, index: usize) -> usize {
+        (index - 1).max(0).min(self.normals.len() - 1)
     }
 
     fn parse(&mut self) {
@@ -124,21 +260,36 @@ impl ObjFileParser {
             let lead = cols[0];
             match lead {
                 "v" => match self.parse_vertex_line(line) {
-                    Some(v) => self.vertices.push(v),
-                    _ => self.lines_ignored += 1,
+                    Ok(v) => self.vertices.push(v),
+                    Err(_) => self.lines_ignored += 1,
+                },
+                "vt" => match self.parse_texcoord_line(line) {
+                    Some(uv) => self.texcoords.push(uv),
+                    None => self.lines_ignored += 1,
                 },
                 "vn" => match self.parse_normal_line(line) {
                     Some(v) => self.normals.push(v),
                     None => self.lines_ignored += 1,
                 },
                 "f" => match self.parse_face_line(line) {
-                    Some(f) => self.faces.push(f),
-                    None => self.lines_ignored += 1,
+                    Ok(f) => self.faces.push(f),
+                    Err(_) => self.lines_ignored += 1,
                 },
                 "g" => {
                     let name: String = line.split(" ").skip(1).take(1).collect();
                     self.current_group = Some(name);
                 }
+                "mtllib" => {
+                    for file in line.split_whitespace().skip(1) {
+                        match std::fs::read_to_string(self.mtl_dir.join(file)) {
+                            Ok(mtl) => self.materials.extend(parse_mtl(&mtl, &self.material)),
+                            Err(_) => self.lines_ignored += 1,
+                        }
+                    }
+                }
+                "usemtl" => {
+                    self.current_material = line.split_whitespace().nth(1).map(String::from);
+                }
 
                 _ => {
                     self.lines_ignored += 1;
@@ -147,45 +298,85 @@ impl ObjFileParser {
         }
     }
 
-    fn parse_vertex_line(&self, line: &str) -> Option<Point> {
+    fn parse_vertex_line(&self, line: &str) -> Result<Point, String> {
         let p_str: Vec<&str> = line.split(" ").skip(1).collect();
         if p_str.len() < 3 {
-            None
-        } else {
-            let x = p_str[0].parse::<f64>().unwrap_or(0.0);
-            let y = p_str[1].parse::<f64>().unwrap_or(0.0);
-            let z = p_str[2].parse::<f64>().unwrap_or(0.0);
+            return Err(format!("vertex needs 3 coordinates, found {}", p_str.len()));
+        }
+        let coord = |s: &str| {
+            s.parse::<f64>()
+                .map_err(|_| format!("invalid vertex coordinate '{}'", s))
+        };
+        let x = coord(p_str[0])?;
+        let y = coord(p_str[1])?;
+        let z = coord(p_str[2])?;
 
-            Some(Point::new(x, y, z))
+        // Optional homogeneous w component
+        match p_str.get(3).and_then(|w| w.parse::<f64>().ok()) {
+            Some(w) if w != 0.0 => Ok(Point::new(x / w, y / w, z / w)),
+            _ => Ok(Point::new(x, y, z)),
         }
     }
 
-    fn parse_face_line(&self, line: &str) -> Option<Face> {
+    fn parse_face_line(&self, line: &str) -> Result<Face, String> {
         let t_str: Vec<&str> = line.split(" ").skip(1).collect();
         if t_str.len() < 3 {
-            None
-        } else {
-            let vertices: Vec<FaceVertex> = t_str
-                .iter()
-                .map(|s| self.parse_face_entry(*s))
-                .map(|(vertex, normal)| FaceVertex { vertex, normal })
-                .collect();
-            Some(Face {
-                vertices,
-                group: self.current_group.clone(),
+            return Err(format!("face needs 3 vertices, found {}", t_str.len()));
+        }
+        let vertices = t_str
+            .iter()
+            .map(|s| match self.parse_face_entry(s) {
+                (0, _, _) => Err(format!("invalid face vertex '{}'", s)),
+                (vertex, texcoord, normal) => Ok(FaceVertex {
+                    vertex,
+                    texcoord,
+                    normal,
+                }),
             })
+            .collect::<Result<Vec<FaceVertex>, String>>()?;
+        Ok(Face {
+            vertices,
+            group: self.current_group.clone(),
+            material: self.current_material.clone(),
+        })
+    }
+
+    // Finds the first v or f line that can't be read, by its 1-based line number
+    fn check(&self) -> Result<(), Error> {
+        for (index, line) in self.input.lines().enumerate() {
+            let reason = match line.split(" ").next() {
+                Some("v") => self.parse_vertex_line(line).err(),
+                Some("f") => self.parse_face_line(line).err(),
+                _ => None,
+            };
+            if let Some(reason) = reason {
+                return Err(Error::ObjParse {
+                    line: index + 1,
+                    reason,
+                });
+            }
         }
+        Ok(())
     }
 
-    fn parse_face_entry(&self, entry: &str) -> (usize, Option<usize>) {
+    // Entries are v, v/vt, v//vn or v/vt/vn
+    fn parse_face_entry(&self, entry: &str) -> (usize, Option<usize>, Option<usize>) {
         let items: Vec<&str> = entry.split('/').collect();
-        if items.len() == 1 {
-            return (entry.parse::<usize>().unwrap_or(0), None);
-        }
+        let index = |i: usize| items.get(i).and_then(|item| item.parse::<usize>().ok());
+        (index(0).unwrap_or(0), index(1), index(2))
+    }
 
-        let vertex = items[0].parse::<usize>().unwrap_or(0);
-        let normal = items[2].parse::<usize>().ok();
-        (vertex, normal)
+    fn parse_texcoord_line(&self, line: &str) -> Option<(f64, f64)> {
+        let uv: Vec<f64> = line
+            .split_whitespace()
+            .skip(1)
+            .map(|c| c.parse::<f64>().unwrap_or(0.0))
+            .collect();
+        match uv.as_slice() {
+            [] => None,
+            [u] => Some((*u, 0.0)),
+            [u, v, ..] => Some((*u, *v)),
+        }
     }
 
     fn parse_normal_line(&self, line: &str) -> Option<Vector> {
@@ -201,43 +392,174 @@ impl ObjFileParser {
         }
     }
 
-    fn fan_triangulation(&self, vertices: &Vec<FaceVertex>) -> Vec<Object> {
-        let mut tris = Vec::<Object>::new();
-
-        for index in 1..(vertices.len() - 1) {
-            let v1 = &vertices[0];
-            let v2 = &vertices[index];
-            let v3 = &vertices[index + 1];
-            if let (Some(n1), Some(n2), Some(n3)) = (v1.normal, v2.normal, v3.normal) {
-                let p1 = self.get_vertex(v1.vertex);
-                let p2 = self.get_vertex(v2.vertex);
-                let p3 = self.get_vertex(v3.vertex);
-                let n1 = self.get_normal(n1);
-                let n2 = self.get_normal(n2);
-                let n3 = self.get_normal(n3);
-                let tri =
-                    Object::new_smooth_tri(p1, p2, p3, n1, n2, n3).with_material(self.material);
-                tris.push(tri);
-            } else {
-                let tri = Object::new_tri(
-                    self.get_vertex(v1.vertex),
-                    self.get_vertex(v2.vertex),
-                    self.get_vertex(v3.vertex),
-                )
-                .with_material(self.material);
-                tris.push(tri);
+    fn face_normal(&self, face: &Face) -> Vector {
+        let p1 = self.get_vertex(face.vertices[0].vertex);
+        let p2 = self.get_vertex(face.vertices[1].vertex);
+        let p3 = self.get_vertex(face.vertices[2].vertex);
+        (p3 - p1).cross(&(p2 - p1))
+    }
+
+    fn mostly_inward(&self) -> bool {
+        if self.vertices.is_empty() {
+            return false;
+        }
+        let centroid = |points: &mut dyn Iterator<Item = Point>, count: usize| {
+            let sum = points.fold(Vector::new(0.0, 0.0, 0.0), |acc, p| {
+                acc + (p - Point::new(0.0, 0.0, 0.0))
+            });
+            Point::new(0.0, 0.0, 0.0) + sum / count as f64
+        };
+        let mesh_centroid = centroid(&mut self.vertices.iter().copied(), self.vertices.len());
+
+        let inward = self
+            .faces
+            .iter()
+            .filter(|face| {
+                let face_centroid = centroid(
+                    &mut face.vertices.iter().map(|v| self.get_vertex(v.vertex)),
+                    face.vertices.len(),
+                );
+                self.face_normal(face) * (face_centroid - mesh_centroid) < 0.0
+            })
+            .count();
+
+        inward * 2 > self.faces.len()
+    }
+
+    fn flip_faces(&mut self) {
+        for face in self.faces.iter_mut() {
+            face.vertices.reverse();
+        }
+        for normal in self.normals.iter_mut() {
+            *normal = -*normal;
+        }
+    }
+
+    fn triangulate(&self, vertices: &Vec<FaceVertex>) -> Vec<[FaceVertex; 3]> {
+        if vertices.len() == 4 && self.triangulation == Triangulation::ShortestDiagonal {
+            let p = |i: usize| self.get_vertex(vertices[i].vertex);
+            let diagonal_02 = (p(2) - p(0)).magnitude();
+            let diagonal_13 = (p(3) - p(1)).magnitude();
+            if diagonal_13 < diagonal_02 {
+                let rotated = vertices.iter().cycle().skip(1).take(4).cloned().collect();
+                return self.fan_triangulation(&rotated);
             }
         }
+        self.fan_triangulation(vertices)
+    }
+
+    fn fan_triangulation(&self, vertices: &Vec<FaceVertex>) -> Vec<[FaceVertex; 3]> {
+        (1..(vertices.len() - 1))
+            .map(|index| {
+                [
+                    vertices[0].clone(),
+                    vertices[index].clone(),
+                    vertices[index + 1].clone(),
+                ]
+            })
+            .collect()
+    }
+
+    // The face's MTL material, or the parser's own when it has none or names an unknown one
+    fn face_material(&self, face: &Face) -> &Material {
+        face.material
+            .as_ref()
+            .and_then(|name| self.materials.get(name))
+            .unwrap_or(&self.material)
+    }
+
+    // Triangles get texture coordinates when all three vertices name one
+    fn triangle_object(&self, tri: &[FaceVertex; 3]) -> Object {
+        let uvs = tri
+            .iter()
+            .map(|v| v.texcoord.and_then(|index| self.get_texcoord(index)))
+            .collect::<Option<Vec<_>>>();
+        match uvs.as_deref() {
+            Some(&[uv1, uv2, uv3]) => self.untextured_triangle(tri).with_uvs([uv1, uv2, uv3]),
+            _ => self.untextured_triangle(tri),
+        }
+    }
 
-        tris
+    fn untextured_triangle(&self, [v1, v2, v3]: &[FaceVertex; 3]) -> Object {
+        if let (Some(n1), Some(n2), Some(n3)) = (v1.normal, v2.normal, v3.normal) {
+            let p1 = self.get_vertex(v1.vertex);
+            let p2 = self.get_vertex(v2.vertex);
+            let p3 = self.get_vertex(v3.vertex);
+            let n1 = self.get_normal(n1);
+            let n2 = self.get_normal(n2);
+            let n3 = self.get_normal(n3);
+            Object::new_smooth_tri(p1, p2, p3, n1, n2, n3)
+        } else {
+            Object::new_tri(
+                self.get_vertex(v1.vertex),
+                self.get_vertex(v2.vertex),
+                self.get_vertex(v3.vertex),
+            )
+        }
     }
 }
 
+// Reads the materials of an MTL file, each starting from base. Kd sets the color, Ka the
+// ambient term (the average of its channels), Ns the shininess and d or Tr the transparency.
+fn parse_mtl(input: &str, base: &Material) -> HashMap<String, Material> {
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, Material)> = None;
+
+    for line in input.lines() {
+        let cols: Vec<&str> = line.split_whitespace().collect();
+        let numbers: Vec<f64> = cols.iter().skip(1).filter_map(|c| c.parse().ok()).collect();
+        if let ["newmtl", name, ..] = cols.as_slice() {
+            materials.extend(current.take());
+            current = Some((name.to_string(), base.clone()));
+            continue;
+        }
+        let Some((_, material)) = current.as_mut() else {
+            continue;
+        };
+        match (cols.first(), numbers.as_slice()) {
+            (Some(&"Kd"), [r, g, b, ..]) => {
+                material.pattern = Pattern::new_solid(Color::new(*r, *g, *b))
+            }
+            (Some(&"Ka"), [r, g, b, ..]) => material.ambient = (r + g + b) / 3.0,
+            (Some(&"Ns"), [shininess, ..]) => material.shininess = *shininess,
+            (Some(&"d"), [dissolve, ..]) => material.transparency = 1.0 - dissolve,
+            (Some(&"Tr"), [transparency, ..]) => material.transparency = *transparency,
+            _ => {}
+        }
+    }
+    materials.extend(current);
+    materials
+}
+
 #[cfg(test)]
 mod test {
-    use super::{Face, FaceVertex, ObjFileParser};
+    use super::{parse_mtl, Face, FaceVertex, ObjFileParser, Triangulation};
 
-    use crate::math::{point::Point, tuple::Tuple, vector::Vector};
+    use crate::{
+        draw::color::Color,
+        error::Error,
+        math::{epsilon::ApproxEq, point::Point, ray::Ray, tuple::Tuple, vector::Vector},
+        render::{
+            intersections::Intersections,
+            material::{Material, Materialable},
+            object::Object,
+        },
+    };
+
+    fn total_area(group: &Object) -> f64 {
+        group
+            .children()
+            .unwrap()
+            .iter()
+            .map(|child| {
+                let tri = child.get_shape().as_triangle().unwrap();
+                (tri.p2() - tri.p1())
+                    .cross(&(tri.p3() - tri.p1()))
+                    .magnitude()
+                    / 2.0
+            })
+            .sum()
+    }
 
     #[test]
     fn ignore_unrecognized_lines() {
@@ -270,6 +592,19 @@ v 1 1 0";
         assert_eq!(parser.vertices[3], Point::new(1.0, 1.0, 0.0));
     }
 
+    #[test]
+    fn vertex_records_with_a_w_component() {
+        let input = "
+v 2 4 6 2
+v 1 2 3 0
+";
+        let mut parser = ObjFileParser::new_input(String::from(input));
+        parser.parse();
+
+        assert_eq!(parser.vertices[0], Point::new(1.0, 2.0, 3.0));
+        assert_eq!(parser.vertices[1], Point::new(1.0, 2.0, 3.0));
+    }
+
     #[test]
     fn parsing_triangle_faces() {
         let input = "
@@ -333,6 +668,128 @@ f 1 2 3 4 5
         assert_eq!(t3.p3(), parser.get_vertex(5));
     }
 
+    #[test]
+    fn triangulating_a_non_planar_quad_along_the_shorter_diagonal() {
+        let input = "
+v -2 0 0
+v 0 -1 1
+v 2 0 0
+v 0 1 1
+
+f 1 2 3 4
+";
+        let fan = ObjFileParser::new_input(String::from(input)).build();
+
+        let mut parser = ObjFileParser::new_input(String::from(input))
+            .with_triangulation(Triangulation::ShortestDiagonal);
+        let group = parser.build();
+        let children = group.children().unwrap();
+        let t1 = children[0].get_shape().as_triangle().unwrap();
+        let t2 = children[1].get_shape().as_triangle().unwrap();
+
+        assert_eq!(t1.p1(), parser.get_vertex(2));
+        assert_eq!(t1.p2(), parser.get_vertex(3));
+        assert_eq!(t1.p3(), parser.get_vertex(4));
+
+        assert_eq!(t2.p1(), parser.get_vertex(2));
+        assert_eq!(t2.p2(), parser.get_vertex(4));
+        assert_eq!(t2.p3(), parser.get_vertex(1));
+
+        assert!(total_area(&group) < total_area(&fan));
+    }
+
+    #[test]
+    fn flipping_normals_of_a_clockwise_triangle() {
+        let input = "
+v 0 1 0
+v -1 0 0
+v 1 0 0
+
+f 1 3 2
+";
+        let normal = |mut parser: ObjFileParser| {
+            let group = parser.build();
+            let tri = group.children().unwrap()[0]
+                .get_shape()
+                .as_triangle()
+                .unwrap();
+            tri.normal_at(&Point::new(0.0, 0.5, 0.0))
+        };
+
+        let clockwise = normal(ObjFileParser::new_input(String::from(input)));
+        let flipped = normal(ObjFileParser::new_input(String::from(input)).with_flip_normals(true));
+
+        assert_eq!(clockwise, Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(flipped, Vector::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn auto_orienting_an_inward_facing_mesh() {
+        // A tetrahedron with every face wound towards its centre
+        let input = "
+v 0 0 0
+v 1 0 0
+v 0 1 0
+v 0 0 1
+
+f 1 3 2
+f 1 2 4
+f 1 4 3
+f 2 3 4
+";
+        let mut parser = ObjFileParser::new_input(String::from(input)).with_auto_orient(true);
+        let group = parser.build();
+        let center = Point::new(0.25, 0.25, 0.25);
+
+        for child in group.children().unwrap() {
+            let tri = child.get_shape().as_triangle().unwrap();
+            let outward = tri.p1() - center;
+            assert!(tri.normal_at(&tri.p1()) * outward > 0.0);
+        }
+    }
+
+    #[test]
+    fn groups_are_built_in_first_seen_order() {
+        let input = "
+v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+v 0 2 0
+
+g Zebra
+f 1 2 3
+g Apple
+f 1 3 4
+g Zebra
+f 1 4 5";
+        let first_points = |group: &Object| -> Vec<Point> {
+            group
+                .children()
+                .unwrap()
+                .iter()
+                .map(|g| {
+                    g.children().unwrap()[0]
+                        .get_shape()
+                        .as_triangle()
+                        .unwrap()
+                        .p3()
+                })
+                .collect()
+        };
+
+        let a = ObjFileParser::new_input(String::from(input)).build();
+        let b = ObjFileParser::new_input(String::from(input)).build();
+
+        assert_eq!(a, b);
+        assert_eq!(
+            first_points(&a),
+            vec![Point::new(1.0, 0.0, 0.0), Point::new(1.0, 1.0, 0.0)]
+        );
+        // Faces that return to an earlier group are added to it rather than replacing it
+        assert_eq!(a.children().unwrap()[0].children().unwrap().len(), 2);
+    }
+
     #[test]
     fn triangles_in_groups() {
         let input = "
@@ -358,17 +815,21 @@ f 1 3 4";
             parser.faces[0],
             Face {
                 group: Some("FirstGroup".to_string()),
+                material: None,
                 vertices: vec![
                     FaceVertex {
                         vertex: 1,
+                        texcoord: None,
                         normal: None
                     },
                     FaceVertex {
                         vertex: 2,
+                        texcoord: None,
                         normal: None
                     },
                     FaceVertex {
                         vertex: 3,
+                        texcoord: None,
                         normal: None
                     }
                 ],
@@ -379,17 +840,21 @@ f 1 3 4";
             parser.faces[1],
             Face {
                 group: Some("SecondGroup".to_string()),
+                material: None,
                 vertices: vec![
                     FaceVertex {
                         vertex: 1,
+                        texcoord: None,
                         normal: None
                     },
                     FaceVertex {
                         vertex: 3,
+                        texcoord: None,
                         normal: None
                     },
                     FaceVertex {
                         vertex: 4,
+                        texcoord: None,
                         normal: None
                     }
                 ],
@@ -446,4 +911,189 @@ f 1/0/3 2/102/1 3/14/2";
 
         assert_eq!(t1, t2);
     }
+
+    #[test]
+    fn faces_with_texture_coordinates() {
+        let input = "
+v 0 1 0
+v -1 0 0
+v 1 0 0
+vt 0.5 1
+vt 0 0
+vt 1
+vn 0 0 -1
+f 1/1/1 2/2/1 3/3/1";
+
+        let mut parser = ObjFileParser::new_input(String::from(input));
+        let group = parser.build();
+        assert_eq!(parser.texcoords, vec![(0.5, 1.0), (0.0, 0.0), (1.0, 0.0)]);
+        assert_eq!(
+            parser.faces[0].vertices[0],
+            FaceVertex {
+                vertex: 1,
+                texcoord: Some(1),
+                normal: Some(1)
+            }
+        );
+
+        let tri = &group.children().unwrap()[0];
+        let smooth = tri.get_shape().as_smooth_triangle().unwrap();
+        assert_eq!(smooth.uvs(), Some([(0.5, 1.0), (0.0, 0.0), (1.0, 0.0)]));
+        assert_eq!(tri.uv_at(&Point::new(-1.0, 0.0, 0.0)), (0.0, 0.0));
+    }
+
+    #[test]
+    fn parsing_each_form_of_face_entry() {
+        let parser = ObjFileParser::new_input(String::new());
+        assert_eq!(parser.parse_face_entry("1"), (1, None, None));
+        assert_eq!(parser.parse_face_entry("1/2"), (1, Some(2), None));
+        assert_eq!(parser.parse_face_entry("1//3"), (1, None, Some(3)));
+        assert_eq!(parser.parse_face_entry("1/2/3"), (1, Some(2), Some(3)));
+    }
+
+    #[test]
+    fn faces_with_texture_coordinates_and_no_normals() {
+        let input = "
+v 0 1 0
+v -1 0 0
+v 1 0 0
+vt 0.5 1
+vt 0 0
+vt 1 0
+f 1/1 2/2 3/3";
+
+        let mut parser = ObjFileParser::new_input(String::from(input));
+        let group = parser.build();
+        let tri = group.children().unwrap()[0]
+            .get_shape()
+            .as_triangle()
+            .unwrap();
+        assert_eq!(tri.uvs(), Some([(0.5, 1.0), (0.0, 0.0), (1.0, 0.0)]));
+        assert_eq!(parser.faces[0].vertices[2].normal, None);
+    }
+
+    #[test]
+    fn parsing_mtl_materials() {
+        let input = "
+Kd 0 1 0
+newmtl shiny
+Ka 0.2 0.4 0.6
+Kd 0.5 0.25 1
+Ns 300
+newmtl glass
+Tr 0.9
+illum 4";
+        let base = Material::default().with_reflective(0.5);
+        let materials = parse_mtl(input, &base);
+        assert_eq!(materials.len(), 2);
+
+        let shiny = &materials["shiny"];
+        assert!(shiny.ambient.approx_eq(0.4));
+        assert_eq!(shiny.shininess, 300.0);
+        assert_eq!(shiny.reflective, 0.5);
+        assert_eq!(
+            shiny.pattern.pattern_at(&Point::new(0.0, 0.0, 0.0)),
+            Color::new(0.5, 0.25, 1.0)
+        );
+
+        let glass = &materials["glass"];
+        assert_eq!(glass.transparency, 0.9);
+        assert_eq!(glass.pattern, base.pattern);
+    }
+
+    #[test]
+    fn faces_use_the_mtl_material_active_when_read() {
+        let group = ObjFileParser::new_file("./assets/obj/two_materials.obj")
+            .unwrap()
+            .build();
+        let children = group.children().unwrap();
+        assert_eq!(children.len(), 3);
+
+        let origin = Point::new(0.0, 0.0, 0.0);
+        let red = children[0].get_material();
+        assert_eq!(red.pattern.pattern_at(&origin), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(red.shininess, 50.0);
+        let blue = children[1].get_material();
+        assert_eq!(blue.pattern.pattern_at(&origin), Color::new(0.0, 0.0, 1.0));
+        assert_eq!(blue.transparency, 0.75);
+
+        // Unknown material names fall back to the parser's material
+        assert_eq!(children[2].get_material(), Material::default());
+    }
+
+    #[test]
+    fn loading_a_missing_file_returns_an_io_error() {
+        let parser = ObjFileParser::new_file("./assets/obj/does_not_exist.obj");
+        assert!(matches!(parser, Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn building_a_quad_into_an_indexed_mesh() {
+        let input = "
+v -1 1 0
+v -1 -1 0
+v 1 -1 0
+v 1 1 0
+
+f 1 2 3 4
+";
+        let group = ObjFileParser::new_input(String::from(input)).build();
+        let mesh = ObjFileParser::new_input(String::from(input)).build_mesh();
+
+        assert!(mesh.children().is_none());
+        let indexed = mesh.get_shape().as_indexed_mesh().unwrap();
+        assert_eq!(indexed.triangle_count(), 2);
+        assert_eq!(indexed.vertices().len(), 4);
+        assert_eq!(group.children().unwrap().len(), 2);
+
+        for (x, y) in [(-0.5, -0.25), (0.5, 0.25), (0.9, -0.9), (1.5, 0.0)] {
+            let r = Ray::new(Point::new(x, y, -2.0), Vector::new(0.0, 0.0, 1.0));
+            let mut want = Intersections::new();
+            group.intersect(&r, &mut want);
+            let mut got = Intersections::new();
+            mesh.intersect(&r, &mut got);
+
+            assert_eq!(got.len(), want.len());
+            for i in 0..got.len() {
+                assert!(got[i].t().approx_eq(want[i].t()));
+                let point = r.position_at(got[i].t());
+                assert_eq!(
+                    got[i].object().normal_at(&point, &got[i]),
+                    want[i].object().normal_at(&point, &want[i])
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn malformed_lines_are_reported_with_their_line_number() {
+        let input = "v 0 0 0
+v 1 0 0
+v 0 x 0
+f 1 2 3";
+        let result = ObjFileParser::new_input(String::from(input)).try_build();
+        match result {
+            Err(Error::ObjParse { line, reason }) => {
+                assert_eq!(line, 3);
+                assert!(reason.contains("'x'"));
+            }
+            _ => panic!("expected an OBJ parse error"),
+        }
+
+        let input = "v 0 0 0
+v 1 0 0
+v 0 1 0
+
+f 1 2";
+        let result = ObjFileParser::new_input(String::from(input)).try_build();
+        assert!(matches!(result, Err(Error::ObjParse { line: 5, .. })));
+
+        // The lenient build still skips them
+        let mut parser = ObjFileParser::new_input(String::from(input));
+        parser.parse();
+        assert_eq!(parser.faces.len(), 0);
+        assert!(ObjFileParser::new_input(String::from("v 0 0 0\nf 1 1 1"))
+            .try_build()
+            .is_ok());
+    }
 }
@@ -0,0 +1,185 @@
+use crate::{draw::canvas::Canvas, error::Error};
+
+// Reads plain (P3) and binary (P6) PPM images. Values are rescaled from the file's
+// maximum to 0-255. Image files are sRGB encoded, so the canvas is marked as sRGB and
+// pixel_at decodes its pixels back to linear color.
+pub fn parse(bytes: &[u8]) -> Result<Canvas, Error> {
+    let mut reader = Reader { bytes, pos: 0 };
+    let magic = reader.token()?;
+    if magic != "P3" && magic != "P6" {
+        return Err(Error::PpmParse(format!(
+            "unsupported magic number {}",
+            magic
+        )));
+    }
+    let width = reader.number()?;
+    let height = reader.number()?;
+    let max = reader.number()?;
+    if max == 0 || max > 255 {
+        return Err(Error::PpmParse(format!(
+            "unsupported maximum value {}",
+            max
+        )));
+    }
+
+    // Checked before allocating so a bad header can't overflow or ask for a huge canvas.
+    // Every value takes at least one byte, plus a separator in plain files.
+    let count = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(3))
+        .ok_or_else(|| Error::PpmParse(format!("image size {}x{} is too large", width, height)))?;
+    let remaining = bytes.len().saturating_sub(reader.pos);
+    let min_len = if magic == "P6" {
+        count
+    } else {
+        count.saturating_mul(2).saturating_sub(1)
+    };
+    if min_len > remaining {
+        return Err(Error::PpmParse(
+            "raster is shorter than the image".to_string(),
+        ));
+    }
+
+    let values: Vec<usize> = if magic == "P6" {
+        // A single whitespace byte separates the header from the raster
+        let start = reader.pos + 1;
+        let raster = bytes
+            .get(start..start + count)
+            .ok_or_else(|| Error::PpmParse("raster is shorter than the image".to_string()))?;
+        raster.iter().map(|b| *b as usize).collect()
+    } else {
+        (0..count)
+            .map(|_| reader.number())
+            .collect::<Result<_, _>>()?
+    };
+
+    let mut canvas = Canvas::new(width, height).with_srgb();
+    for (byte, value) in canvas.as_bytes_mut().iter_mut().zip(values) {
+        *byte = ((value.min(max) * 255 + max / 2) / max) as u8;
+    }
+    Ok(canvas)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    // Next whitespace separated token, skipping comments that run from # to the end of a line
+    fn token(&mut self) -> Result<String, Error> {
+        loop {
+            match self.bytes.get(self.pos) {
+                Some(b'#') => {
+                    while self.bytes.get(self.pos).is_some_and(|b| *b != b'\n') {
+                        self.pos += 1;
+                    }
+                }
+                Some(b) if b.is_ascii_whitespace() => self.pos += 1,
+                Some(_) => break,
+                None => return Err(Error::PpmParse("unexpected end of file".to_string())),
+            }
+        }
+
+        let start = self.pos;
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(|b| !b.is_ascii_whitespace())
+        {
+            self.pos += 1;
+        }
+        Ok(String::from_utf8_lossy(&self.bytes[start..self.pos]).into_owned())
+    }
+
+    fn number(&mut self) -> Result<usize, Error> {
+        let token = self.token()?;
+        token
+            .parse()
+            .map_err(|_| Error::PpmParse(format!("expected a number, found {}", token)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse;
+    use crate::{draw::color::Color, error::Error};
+
+    #[test]
+    fn reading_a_file_with_the_wrong_magic_number() {
+        let got = parse(b"P32\n1 1\n255\n0 0 0\n");
+        assert!(matches!(got, Err(Error::PpmParse(_))));
+    }
+
+    #[test]
+    fn reading_a_ppm_returns_a_canvas_of_the_right_size() {
+        let body = "0 0 0 ".repeat(20);
+        let c = parse(format!("P3\n10 2\n255\n{}", body).as_bytes()).unwrap();
+        assert_eq!(c.dimensions(), (10, 2));
+    }
+
+    #[test]
+    fn reading_pixel_data_from_a_ppm_file() {
+        let ppm = b"P3
+4 3
+255
+255 127 0  0 127 255  127 255 0  255 255 255
+0 0 0  255 0 0  0 255 0  0 0 255
+255 255 0  0 255 255  255 0 255  127 127 127
+";
+        let c = parse(ppm).unwrap();
+        let half = 127.0 / 255.0;
+        assert_eq!(c.pixel_at((0, 0)), Some(Color::srgb(1.0, half, 0.0)));
+        assert_eq!(c.pixel_at((3, 0)), Some(Color::new(1.0, 1.0, 1.0)));
+        assert_eq!(c.pixel_at((1, 1)), Some(Color::new(1.0, 0.0, 0.0)));
+        assert_eq!(c.pixel_at((3, 2)), Some(Color::srgb(half, half, half)));
+    }
+
+    #[test]
+    fn ppm_parsing_ignores_comments_and_rescales_values() {
+        let ppm = b"P3
+# this is a comment
+2 1
+# this, too
+100
+100 100 100  # and this
+0 50 100
+";
+        let c = parse(ppm).unwrap();
+        assert_eq!(c.pixel_at((0, 0)), Some(Color::white()));
+        assert_eq!(c.as_bytes()[3..], [0, 128, 255]);
+    }
+
+    #[test]
+    fn oversized_headers_are_rejected_before_allocating() {
+        let too_large = format!("P6\n{} {}\n255\n", usize::MAX, 2);
+        assert!(matches!(
+            parse(too_large.as_bytes()),
+            Err(Error::PpmParse(_))
+        ));
+        let huge = b"P3\n100000 100000\n255\n0 0 0\n";
+        assert!(matches!(parse(huge), Err(Error::PpmParse(_))));
+        let short = b"P6\n4 4\n255\n\x00\x01\x02";
+        assert!(matches!(parse(short), Err(Error::PpmParse(_))));
+    }
+
+    #[test]
+    fn reading_a_binary_ppm() {
+        let mut ppm = b"P6\n2 1\n255\n".to_vec();
+        ppm.extend_from_slice(&[255, 0, 0, 10, 32, 200]);
+        let c = parse(&ppm).unwrap();
+        assert_eq!(c.as_bytes(), [255, 0, 0, 10, 32, 200]);
+    }
+
+    #[test]
+    fn ppm_pixels_are_decoded_from_srgb() {
+        // Mid grey in the file is about a fifth of the light in linear terms
+        let c = parse(b"P3\n1 1\n255\n128 128 128\n").unwrap();
+        assert!(c.is_srgb());
+        let grey = c.pixel_at((0, 0)).unwrap();
+        let byte = 128.0 / 255.0;
+        assert_eq!(grey, Color::srgb(byte, byte, byte));
+        assert!(grey.luminance() < 0.25);
+        assert_eq!(c.as_bytes(), [128, 128, 128]);
+    }
+}
@@ -0,0 +1,420 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    draw::color::Color,
+    error::Error,
+    math::{
+        matrix::Matrix,
+        point::Point,
+        transformation::{named_transform, Transformable},
+        tuple::Tuple,
+        vector::Vector,
+    },
+    render::{
+        camera::Camera,
+        light::Light,
+        lights::{
+            directional_light::DirectionalLight, point_light::PointLight, spot_light::SpotLight,
+        },
+        material::{Material, Materialable},
+        object::Object,
+        pattern::Pattern,
+        world::World,
+    },
+};
+
+// A world and the camera to render it with, read from a scene description
+#[derive(Debug)]
+pub struct Scene {
+    pub world: World,
+    pub camera: Camera,
+}
+
+impl Scene {
+    pub fn from_json(path: &str) -> Result<Self, Error> {
+        Self::parse_json(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn parse_json(input: &str) -> Result<Self, Error> {
+        let file: SceneFile =
+            serde_json::from_str(input).map_err(|err| Error::SceneParse(err.to_string()))?;
+        file.build()
+    }
+}
+
+// Transforms are lists like ["translate", 1, 2, 3], applied in the order they're listed
+type TransformDesc = Vec<Value>;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneFile {
+    camera: CameraDesc,
+    #[serde(default)]
+    lights: Vec<LightDesc>,
+    #[serde(default)]
+    materials: HashMap<String, MaterialDesc>,
+    #[serde(default)]
+    objects: Vec<ObjectDesc>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDesc {
+    width: usize,
+    height: usize,
+    field_of_view: f64,
+    from: [f64; 3],
+    to: [f64; 3],
+    up: [f64; 3],
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+enum LightDesc {
+    Point {
+        position: [f64; 3],
+        intensity: [f64; 3],
+    },
+    Directional {
+        direction: [f64; 3],
+        intensity: [f64; 3],
+    },
+    Spot {
+        position: [f64; 3],
+        direction: [f64; 3],
+        intensity: [f64; 3],
+        inner_angle: f64,
+        outer_angle: f64,
+    },
+}
+
+// Only the fields that are set change the default material
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct MaterialDesc {
+    color: Option<[f64; 3]>,
+    pattern: Option<PatternDesc>,
+    ambient: Option<f64>,
+    diffuse: Option<f64>,
+    specular: Option<f64>,
+    shininess: Option<f64>,
+    reflective: Option<f64>,
+    transparency: Option<f64>,
+    refractive_index: Option<f64>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MaterialRef {
+    Named(String),
+    Inline(Box<MaterialDesc>),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PatternDesc {
+    #[serde(rename = "type")]
+    kind: String,
+    colors: [[f64; 3]; 2],
+    #[serde(default)]
+    transform: Vec<TransformDesc>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ObjectDesc {
+    shape: String,
+    min: Option<f64>,
+    max: Option<f64>,
+    closed: Option<bool>,
+    #[serde(default)]
+    transform: Vec<TransformDesc>,
+    material: Option<MaterialRef>,
+}
+
+impl SceneFile {
+    fn build(&self) -> Result<Scene, Error> {
+        let mut world = World::new();
+        for light in &self.lights {
+            world.add_light(light.build());
+        }
+        for object in &self.objects {
+            world.add_object(object.build(&self.materials)?);
+        }
+        Ok(Scene {
+            world,
+            camera: self.camera.build(),
+        })
+    }
+}
+
+impl CameraDesc {
+    fn build(&self) -> Camera {
+        Camera::new(self.width, self.height, self.field_of_view).view_transform(
+            &point(self.from),
+            &point(self.to),
+            &vector(self.up),
+        )
+    }
+}
+
+impl LightDesc {
+    fn build(&self) -> Light {
+        match self {
+            Self::Point {
+                position,
+                intensity,
+            } => Light::Point(PointLight::new(point(*position), color(*intensity))),
+            Self::Directional {
+                direction,
+                intensity,
+            } => Light::Directional(DirectionalLight::new(vector(*direction), color(*intensity))),
+            Self::Spot {
+                position,
+                direction,
+                intensity,
+                inner_angle,
+                outer_angle,
+            } => Light::Spot(SpotLight::new(
+                point(*position),
+                vector(*direction),
+                color(*intensity),
+                *inner_angle,
+                *outer_angle,
+            )),
+        }
+    }
+}
+
+impl MaterialDesc {
+    fn build(&self) -> Result<Material, Error> {
+        let mut material = Material::default();
+        if let Some(c) = self.color {
+            material.pattern = Pattern::new_solid(color(c));
+        }
+        if let Some(pattern) = &self.pattern {
+            material.pattern = pattern.build()?;
+        }
+        let scalars = [
+            (self.ambient, &mut material.ambient),
+            (self.diffuse, &mut material.diffuse),
+            (self.specular, &mut material.specular),
+            (self.shininess, &mut material.shininess),
+            (self.reflective, &mut material.reflective),
+            (self.transparency, &mut material.transparency),
+            (self.refractive_index, &mut material.refractive_index),
+        ];
+        for (value, field) in scalars {
+            if let Some(value) = value {
+                *field = value;
+            }
+        }
+        Ok(material)
+    }
+}
+
+impl PatternDesc {
+    fn build(&self) -> Result<Pattern, Error> {
+        let [a, b] = self.colors.map(color);
+        let pattern = match self.kind.as_str() {
+            "stripe" => Pattern::new_stripe(a, b),
+            "gradient" => Pattern::new_gradient(a, b),
+            "ring" => Pattern::new_ring(a, b),
+            "checker" => Pattern::new_checker(a, b),
+            kind => return Err(scene_error(format!("unknown pattern type \"{}\"", kind))),
+        };
+        Ok(pattern.with_transform(transform(&self.transform)?))
+    }
+}
+
+impl ObjectDesc {
+    fn build(&self, materials: &HashMap<String, MaterialDesc>) -> Result<Object, Error> {
+        let min = self.min.unwrap_or(-f64::INFINITY);
+        let max = self.max.unwrap_or(f64::INFINITY);
+        let closed = self.closed.unwrap_or(false);
+        let object = match self.shape.as_str() {
+            "sphere" => Object::new_sphere(),
+            "plane" => Object::new_plane(),
+            "cube" => Object::new_cube(),
+            "cylinder" => Object::new_cylinder(min, max, closed),
+            "cone" => Object::new_cone(min, max, closed),
+            shape => return Err(scene_error(format!("unknown shape \"{}\"", shape))),
+        };
+
+        let material = match &self.material {
+            Some(MaterialRef::Named(name)) => materials
+                .get(name)
+                .ok_or_else(|| scene_error(format!("unknown material \"{}\"", name)))?
+                .build()?,
+            Some(MaterialRef::Inline(desc)) => desc.build()?,
+            None => Material::default(),
+        };
+        Ok(object
+            .with_material(material)
+            .with_transform(transform(&self.transform)?))
+    }
+}
+
+fn transform(ops: &[TransformDesc]) -> Result<Matrix, Error> {
+    ops.iter()
+        .try_fold(Matrix::identity(), |m, op| Ok(operation(op)? * m))
+}
+
+fn operation(op: &[Value]) -> Result<Matrix, Error> {
+    let name = op
+        .first()
+        .and_then(Value::as_str)
+        .ok_or_else(|| scene_error("a transform has to start with its name".to_string()))?;
+    let args = op[1..]
+        .iter()
+        .map(|arg| {
+            arg.as_f64()
+                .ok_or_else(|| scene_error(format!("{} takes numbers, found {}", name, arg)))
+        })
+        .collect::<Result<Vec<f64>, Error>>()?;
+
+    named_transform(name, &args).ok_or_else(|| {
+        scene_error(format!(
+            "unknown transform \"{}\" with {} arguments",
+            name,
+            args.len()
+        ))
+    })
+}
+
+fn scene_error(reason: String) -> Error {
+    Error::SceneParse(reason)
+}
+
+fn color([r, g, b]: [f64; 3]) -> Color {
+    Color::new(r, g, b)
+}
+
+fn point([x, y, z]: [f64; 3]) -> Point {
+    Point::new(x, y, z)
+}
+
+fn vector([x, y, z]: [f64; 3]) -> Vector {
+    Vector::new(x, y, z)
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+
+    use super::Scene;
+    use crate::{
+        draw::color::Color,
+        error::Error,
+        math::{
+            point::Point,
+            transformation::{view_transform, Transformable},
+            tuple::Tuple,
+            vector::Vector,
+        },
+        render::{camera::Camera, world::World},
+    };
+
+    const DEFAULT_WORLD: &str = r#"{
+        "camera": {
+            "width": 11,
+            "height": 11,
+            "field_of_view": 1.5707963267948966,
+            "from": [0, 0, -5],
+            "to": [0, 0, 0],
+            "up": [0, 1, 0]
+        },
+        "lights": [
+            { "type": "point", "position": [-10, 10, -10], "intensity": [1, 1, 1] }
+        ],
+        "materials": {
+            "green": { "color": [0.8, 1.0, 0.6], "diffuse": 0.7, "specular": 0.2 }
+        },
+        "objects": [
+            { "shape": "sphere", "material": "green" },
+            { "shape": "sphere", "transform": [["scale", 0.5, 0.5, 0.5]] }
+        ]
+    }"#;
+
+    #[test]
+    fn parsing_a_scene_matching_the_default_world() {
+        let scene = Scene::parse_json(DEFAULT_WORLD).unwrap();
+
+        let from = Point::new(0.0, 0.0, -5.0);
+        let to = Point::new(0.0, 0.0, 0.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        assert_eq!(scene.camera.hsize(), 11);
+        assert_eq!(
+            scene.camera.get_transform(),
+            view_transform(&from, &to, &up)
+        );
+
+        let camera = Camera::new(11, 11, PI / 2.0).view_transform(&from, &to, &up);
+        let image = scene.world.render(&scene.camera);
+        assert_eq!(image, World::default().render(&camera));
+        let ray = scene.camera.ray_for_pixel(5, 5);
+        assert_eq!(
+            scene.world.color_at(&ray, 5),
+            Color::new(0.38066, 0.47583, 0.2855)
+        );
+    }
+
+    #[test]
+    fn transforms_apply_in_the_order_they_are_listed() {
+        let scene = Scene::parse_json(
+            r#"{
+                "camera": {
+                    "width": 1, "height": 1, "field_of_view": 1.0,
+                    "from": [0, 0, -5], "to": [0, 0, 0], "up": [0, 1, 0]
+                },
+                "objects": [
+                    {
+                        "shape": "cylinder", "min": 0, "max": 1, "closed": true,
+                        "transform": [["scale", 2, 2, 2], ["translate", 0, 0, 10]]
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let bounds = scene.world.bounds();
+        assert_eq!(bounds.min(), Point::new(-2.0, 0.0, 8.0));
+        assert_eq!(bounds.max(), Point::new(2.0, 2.0, 12.0));
+    }
+
+    #[test]
+    fn unknown_names_are_reported() {
+        let scene = |object: &str| {
+            Scene::parse_json(&format!(
+                r#"{{
+                    "camera": {{
+                        "width": 1, "height": 1, "field_of_view": 1.0,
+                        "from": [0, 0, -5], "to": [0, 0, 0], "up": [0, 1, 0]
+                    }},
+                    "objects": [{}]
+                }}"#,
+                object
+            ))
+        };
+        let reason = |object: &str| match scene(object) {
+            Err(Error::SceneParse(reason)) => reason,
+            other => panic!("expected a scene error, got {:?}", other),
+        };
+
+        assert_eq!(
+            reason(r#"{ "shape": "teapot" }"#),
+            "unknown shape \"teapot\""
+        );
+        assert_eq!(
+            reason(r#"{ "shape": "sphere", "material": "gold" }"#),
+            "unknown material \"gold\""
+        );
+        assert_eq!(
+            reason(r#"{ "shape": "sphere", "transform": [["spin", 1]] }"#),
+            "unknown transform \"spin\" with 1 arguments"
+        );
+        assert!(matches!(scene("{}"), Err(Error::SceneParse(_))));
+    }
+}
@@ -0,0 +1,239 @@
+use crate::{
+    error::Error,
+    math::{point::Point, tuple::Tuple, vector::Vector},
+    render::{
+        material::{Material, Materialable},
+        object::Object,
+    },
+};
+
+#[derive(Debug, Clone, PartialEq)]
+struct Facet {
+    normal: Vector,
+    vertices: [Point; 3],
+}
+
+// Reads ASCII and binary STL meshes into a group of flat triangles
+#[derive(Debug)]
+pub struct StlParser {
+    facets: Vec<Facet>,
+    material: Material,
+}
+
+impl StlParser {
+    pub fn new_file(path: &str) -> Result<Self, Error> {
+        Self::new_input(&std::fs::read(path)?)
+    }
+
+    // Binary files may also start with "solid", so text is only read as ASCII when it
+    // isn't the size its binary header promises and has no NUL bytes
+    pub fn new_input(bytes: &[u8]) -> Result<Self, Error> {
+        let binary_len = triangle_count(bytes).map(|count| 84 + 50 * count);
+        let ascii =
+            bytes.starts_with(b"solid") && binary_len != Some(bytes.len()) && !bytes.contains(&0);
+        let facets = if ascii {
+            parse_ascii(&String::from_utf8_lossy(bytes))?
+        } else {
+            parse_binary(bytes)?
+        };
+        Ok(Self {
+            facets,
+            material: Material::default(),
+        })
+    }
+
+    pub fn build(&self) -> Object {
+        let triangles = self
+            .facets
+            .iter()
+            .map(|facet| facet.triangle().with_material(self.material.clone()))
+            .collect();
+        Object::new_group(triangles)
+    }
+
+    pub fn build_with_material(&mut self, mat: Material) -> Object {
+        self.material = mat;
+        self.build()
+    }
+}
+
+impl Facet {
+    // Winds the triangle so its normal points along the facet normal. Files that leave
+    // the normal zeroed fall back to STL's counter-clockwise winding.
+    fn triangle(&self) -> Object {
+        let [p1, p2, p3] = self.vertices;
+        let counter_clockwise = (p2 - p1).cross(&(p3 - p1));
+        let facing = if self.normal.magnitude() > 0.0 {
+            self.normal
+        } else {
+            counter_clockwise
+        };
+        // Triangles take their normal from the clockwise winding
+        if facing * counter_clockwise > 0.0 {
+            Object::new_tri(p1, p3, p2)
+        } else {
+            Object::new_tri(p1, p2, p3)
+        }
+    }
+}
+
+fn parse_ascii(input: &str) -> Result<Vec<Facet>, Error> {
+    let mut tokens = input.split_whitespace();
+    let mut facets = vec![];
+    let mut normal = Vector::new(0.0, 0.0, 0.0);
+    let mut vertices = vec![];
+
+    while let Some(token) = tokens.next() {
+        match token {
+            "normal" => {
+                let [x, y, z] = ascii_triple(&mut tokens)?;
+                normal = Vector::new(x, y, z);
+            }
+            "vertex" => {
+                let [x, y, z] = ascii_triple(&mut tokens)?;
+                vertices.push(Point::new(x, y, z));
+            }
+            "endfacet" => {
+                let vertices: [Point; 3] =
+                    std::mem::take(&mut vertices)
+                        .try_into()
+                        .map_err(|v: Vec<Point>| {
+                            Error::StlParse(format!("facet has {} vertices, expected 3", v.len()))
+                        })?;
+                facets.push(Facet { normal, vertices });
+                normal = Vector::new(0.0, 0.0, 0.0);
+            }
+            _ => {}
+        }
+    }
+    Ok(facets)
+}
+
+fn ascii_triple<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<[f64; 3], Error> {
+    let mut next = || {
+        let token = tokens
+            .next()
+            .ok_or_else(|| Error::StlParse("unexpected end of file".to_string()))?;
+        token
+            .parse::<f64>()
+            .map_err(|_| Error::StlParse(format!("expected a number, found {}", token)))
+    };
+    Ok([next()?, next()?, next()?])
+}
+
+// An 80 byte header, a little endian u32 triangle count, then 50 bytes per triangle:
+// twelve f32s for the normal and vertices and a u16 attribute count
+fn parse_binary(bytes: &[u8]) -> Result<Vec<Facet>, Error> {
+    let count = triangle_count(bytes)
+        .ok_or_else(|| Error::StlParse("file is shorter than the binary header".to_string()))?;
+    let records = bytes
+        .get(84..84 + 50 * count)
+        .ok_or_else(|| Error::StlParse(format!("file is shorter than its {} triangles", count)))?;
+
+    Ok(records
+        .chunks_exact(50)
+        .map(|record| {
+            let float = |i: usize| {
+                let b = &record[i * 4..i * 4 + 4];
+                f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64
+            };
+            let point = |i: usize| Point::new(float(i), float(i + 1), float(i + 2));
+            Facet {
+                normal: Vector::new(float(0), float(1), float(2)),
+                vertices: [point(3), point(6), point(9)],
+            }
+        })
+        .collect())
+}
+
+fn triangle_count(bytes: &[u8]) -> Option<usize> {
+    let c = bytes.get(80..84)?;
+    Some(u32::from_le_bytes([c[0], c[1], c[2], c[3]]) as usize)
+}
+
+#[cfg(test)]
+mod test {
+    use super::StlParser;
+    use crate::{
+        error::Error,
+        math::{point::Point, tuple::Tuple, vector::Vector},
+    };
+
+    #[test]
+    fn parsing_an_ascii_cube() {
+        let cube = StlParser::new_file("./assets/stl/cube.stl")
+            .unwrap()
+            .build();
+        let children = cube.children().unwrap();
+        assert_eq!(children.len(), 12);
+
+        let firsts = [
+            (1.0, -1.0, -1.0),
+            (1.0, -1.0, -1.0),
+            (-1.0, -1.0, -1.0),
+            (-1.0, -1.0, -1.0),
+            (-1.0, 1.0, -1.0),
+            (-1.0, 1.0, -1.0),
+            (-1.0, -1.0, -1.0),
+            (-1.0, -1.0, -1.0),
+            (-1.0, -1.0, 1.0),
+            (-1.0, -1.0, 1.0),
+            (-1.0, -1.0, -1.0),
+            (-1.0, -1.0, -1.0),
+        ];
+        for (i, (x, y, z)) in firsts.into_iter().enumerate() {
+            let tri = children[i].get_shape().as_triangle().unwrap();
+            assert_eq!(tri.p1(), Point::new(x, y, z));
+        }
+
+        // Triangles face along their facet normals
+        let right = children[0].get_shape().as_triangle().unwrap();
+        assert_eq!(right.normal_at(&right.p1()), Vector::new(1.0, 0.0, 0.0));
+        let top = children[4].get_shape().as_triangle().unwrap();
+        assert_eq!(top.normal_at(&top.p1()), Vector::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn parsing_a_binary_stl() {
+        // The header starts with "solid" like some exporters write
+        let mut bytes = b"solid binary".to_vec();
+        bytes.resize(80, 0);
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        for f in [
+            0.0f32, 0.0, 0.0, 0.0, 1.0, 0.0, -1.0, 0.0, 0.0, 1.0, 0.0, 0.0,
+        ] {
+            bytes.extend_from_slice(&f.to_le_bytes());
+        }
+        bytes.extend_from_slice(&[0, 0]);
+
+        let group = StlParser::new_input(&bytes).unwrap().build();
+        let children = group.children().unwrap();
+        assert_eq!(children.len(), 1);
+        let tri = children[0].get_shape().as_triangle().unwrap();
+        assert_eq!(tri.p1(), Point::new(0.0, 1.0, 0.0));
+        // A zeroed facet normal falls back to the counter-clockwise winding
+        assert_eq!(tri.normal_at(&tri.p1()), Vector::new(0.0, 0.0, 1.0));
+
+        bytes.truncate(100);
+        assert!(matches!(
+            StlParser::new_input(&bytes),
+            Err(Error::StlParse(_))
+        ));
+    }
+
+    #[test]
+    fn facets_need_three_vertices() {
+        let input = b"solid bad
+facet normal 0 0 1
+outer loop
+vertex 0 0 0
+vertex 1 0 0
+endloop
+endfacet
+endsolid bad";
+        assert!(matches!(
+            StlParser::new_input(input),
+            Err(Error::StlParse(_))
+        ));
+    }
+}
@@ -0,0 +1,892 @@
+use std::collections::HashMap;
+
+use crate::{
+    draw::color::Color,
+    error::Error,
+    math::{
+        matrix::Matrix,
+        point::Point,
+        transformation::{named_transform, Transformable},
+        tuple::Tuple,
+        vector::Vector,
+    },
+    render::{
+        camera::Camera,
+        light::Light,
+        lights::point_light::PointLight,
+        material::{Material, Materialable},
+        object::Object,
+        pattern::Pattern,
+        world::World,
+    },
+};
+
+// Reads a scene in the YAML format of the book's bonus scenes: a list of `add` entries for
+// the camera, lights and shapes, and `define` entries naming materials or transforms for
+// later entries to reuse. Only the YAML those files use is understood: block lists and
+// maps, flow lists like [1, 2, 3], plain scalars and # comments.
+pub fn parse_scene(input: &str) -> Result<(World, Camera), Error> {
+    let root = parse_yaml(input)?;
+    let scene = SceneBuilder::from_entries(&root)?;
+    let camera = scene
+        .camera
+        .ok_or_else(|| root.error("the scene has no camera".to_string()))?;
+
+    let mut world = World::new();
+    for light in scene.lights {
+        world.add_light(light);
+    }
+    for object in scene.objects {
+        world.add_object(object);
+    }
+    Ok((world, camera))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Node {
+    line: usize,
+    value: Value,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Scalar(String),
+    List(Vec<Node>),
+    Map(Vec<(String, Node)>),
+}
+
+impl Node {
+    fn error(&self, reason: String) -> Error {
+        Error::YamlParse {
+            line: self.line,
+            reason,
+        }
+    }
+
+    fn as_str(&self) -> Result<&str, Error> {
+        match &self.value {
+            Value::Scalar(s) => Ok(s),
+            _ => Err(self.error("expected a single value".to_string())),
+        }
+    }
+
+    fn as_f64(&self) -> Result<f64, Error> {
+        let s = self.as_str()?;
+        s.parse()
+            .map_err(|_| self.error(format!("expected a number, found \"{}\"", s)))
+    }
+
+    fn as_usize(&self) -> Result<usize, Error> {
+        let s = self.as_str()?;
+        s.parse()
+            .map_err(|_| self.error(format!("expected a whole number, found \"{}\"", s)))
+    }
+
+    fn as_bool(&self) -> Result<bool, Error> {
+        match self.as_str()? {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            s => Err(self.error(format!("expected true or false, found \"{}\"", s))),
+        }
+    }
+
+    fn as_list(&self) -> Result<&[Node], Error> {
+        match &self.value {
+            Value::List(items) => Ok(items),
+            _ => Err(self.error("expected a list".to_string())),
+        }
+    }
+
+    fn as_map(&self) -> Result<&[(String, Node)], Error> {
+        match &self.value {
+            Value::Map(entries) => Ok(entries),
+            _ => Err(self.error("expected a map".to_string())),
+        }
+    }
+
+    fn as_triple(&self) -> Result<(f64, f64, f64), Error> {
+        match self.as_list()? {
+            [x, y, z] => Ok((x.as_f64()?, y.as_f64()?, z.as_f64()?)),
+            _ => Err(self.error("expected a list of three numbers".to_string())),
+        }
+    }
+
+    fn as_color(&self) -> Result<Color, Error> {
+        let (r, g, b) = self.as_triple()?;
+        Ok(Color::new(r, g, b))
+    }
+
+    fn as_point(&self) -> Result<Point, Error> {
+        let (x, y, z) = self.as_triple()?;
+        Ok(Point::new(x, y, z))
+    }
+
+    fn as_vector(&self) -> Result<Vector, Error> {
+        let (x, y, z) = self.as_triple()?;
+        Ok(Vector::new(x, y, z))
+    }
+
+    fn get(&self, key: &str) -> Option<&Node> {
+        match &self.value {
+            Value::Map(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn require(&self, key: &str) -> Result<&Node, Error> {
+        self.get(key)
+            .ok_or_else(|| self.error(format!("missing \"{}\"", key)))
+    }
+
+    // Rejects keys outside of allowed so typos don't go unnoticed
+    fn check_keys(&self, allowed: &[&str]) -> Result<(), Error> {
+        for (key, value) in self.as_map()? {
+            if !allowed.contains(&key.as_str()) {
+                return Err(value.error(format!("unknown key \"{}\"", key)));
+            }
+        }
+        Ok(())
+    }
+}
+
+struct SceneBuilder {
+    defines: HashMap<String, Node>,
+    lights: Vec<Light>,
+    objects: Vec<Object>,
+    camera: Option<Camera>,
+}
+
+impl SceneBuilder {
+    fn from_entries(root: &Node) -> Result<Self, Error> {
+        let mut scene = Self {
+            defines: HashMap::new(),
+            lights: vec![],
+            objects: vec![],
+            camera: None,
+        };
+        for entry in root.as_list()? {
+            scene.add_entry(entry)?;
+        }
+        Ok(scene)
+    }
+
+    fn add_entry(&mut self, entry: &Node) -> Result<(), Error> {
+        if let Some(name) = entry.get("define") {
+            entry.check_keys(&["define", "extend", "value"])?;
+            let value = entry.require("value")?;
+            let value = match entry.get("extend") {
+                Some(base) => self.extend(base, value)?,
+                None => value.clone(),
+            };
+            self.defines.insert(name.as_str()?.to_string(), value);
+            return Ok(());
+        }
+
+        let kind = entry
+            .get("add")
+            .ok_or_else(|| entry.error("expected an add or define entry".to_string()))?;
+        match kind.as_str()? {
+            "camera" => self.camera = Some(camera(entry)?),
+            "light" => self.lights.push(light(entry)?),
+            _ => {
+                let object = self.object(entry)?;
+                self.objects.push(object);
+            }
+        }
+        Ok(())
+    }
+
+    // A define's value with its own entries laid over the ones of the define it extends
+    fn extend(&self, base: &Node, value: &Node) -> Result<Node, Error> {
+        let mut entries = self.resolve(base)?.as_map()?.to_vec();
+        for (key, node) in value.as_map()? {
+            entries.retain(|(k, _)| k != key);
+            entries.push((key.clone(), node.clone()));
+        }
+        Ok(Node {
+            line: value.line,
+            value: Value::Map(entries),
+        })
+    }
+
+    // Names stand in for the value they were defined with
+    fn resolve<'a>(&'a self, node: &'a Node) -> Result<&'a Node, Error> {
+        match &node.value {
+            Value::Scalar(name) => self
+                .defines
+                .get(name)
+                .ok_or_else(|| node.error(format!("unknown define \"{}\"", name))),
+            _ => Ok(node),
+        }
+    }
+
+    fn object(&self, entry: &Node) -> Result<Object, Error> {
+        entry.check_keys(&[
+            "add",
+            "material",
+            "transform",
+            "min",
+            "max",
+            "closed",
+            "children",
+            "file",
+        ])?;
+        let min = entry.get("min").map_or(Ok(-f64::INFINITY), Node::as_f64)?;
+        let max = entry.get("max").map_or(Ok(f64::INFINITY), Node::as_f64)?;
+        let closed = entry.get("closed").map_or(Ok(false), Node::as_bool)?;
+
+        let kind = entry.require("add")?;
+        let mut object = match kind.as_str()? {
+            "sphere" => Object::new_sphere(),
+            "plane" => Object::new_plane(),
+            "cube" => Object::new_cube(),
+            "cylinder" => Object::new_cylinder(min, max, closed),
+            "cone" => Object::new_cone(min, max, closed),
+            "group" => Object::new_group(
+                entry
+                    .require("children")?
+                    .as_list()?
+                    .iter()
+                    .map(|child| self.object(child))
+                    .collect::<Result<_, _>>()?,
+            ),
+            "obj" => Object::new_model(entry.require("file")?.as_str()?)?,
+            shape => return Err(kind.error(format!("unknown shape \"{}\"", shape))),
+        };
+
+        if let Some(material) = entry.get("material") {
+            object = object.with_material(self.material(material)?);
+        }
+        if let Some(transform) = entry.get("transform") {
+            object = object.with_transform(self.transform(transform)?);
+        }
+        Ok(object)
+    }
+
+    fn material(&self, node: &Node) -> Result<Material, Error> {
+        let mut material = Material::default();
+        for (key, value) in self.resolve(node)?.as_map()? {
+            match key.as_str() {
+                "color" => material.pattern = Pattern::new_solid(value.as_color()?),
+                "pattern" => material.pattern = self.pattern(value)?,
+                "ambient" => material.ambient = value.as_f64()?,
+                "diffuse" => material.diffuse = value.as_f64()?,
+                "specular" => material.specular = value.as_f64()?,
+                "shininess" => material.shininess = value.as_f64()?,
+                "reflective" => material.reflective = value.as_f64()?,
+                "transparency" => material.transparency = value.as_f64()?,
+                "refractive-index" => material.refractive_index = value.as_f64()?,
+                _ => return Err(value.error(format!("unknown material key \"{}\"", key))),
+            }
+        }
+        Ok(material)
+    }
+
+    fn pattern(&self, node: &Node) -> Result<Pattern, Error> {
+        node.check_keys(&["type", "colors", "transform"])?;
+        let colors = node.require("colors")?;
+        let (a, b) = match colors.as_list()? {
+            [a, b] => (a.as_color()?, b.as_color()?),
+            _ => return Err(colors.error("a pattern takes two colors".to_string())),
+        };
+
+        let kind = node.require("type")?;
+        let pattern = match kind.as_str()? {
+            "stripes" => Pattern::new_stripe(a, b),
+            "gradient" => Pattern::new_gradient(a, b),
+            "rings" => Pattern::new_ring(a, b),
+            "checkers" => Pattern::new_checker(a, b),
+            other => return Err(kind.error(format!("unknown pattern type \"{}\"", other))),
+        };
+        match node.get("transform") {
+            Some(transform) => Ok(pattern.with_transform(self.transform(transform)?)),
+            None => Ok(pattern),
+        }
+    }
+
+    // Transforms apply in the order they're listed, a name expands to the transforms
+    // it was defined with
+    fn transform(&self, node: &Node) -> Result<Matrix, Error> {
+        self.expand_transform(node, &mut vec![])
+    }
+
+    // expanding holds the names being expanded, so a define that names itself, directly
+    // or through others, is an error rather than endless recursion
+    fn expand_transform(&self, node: &Node, expanding: &mut Vec<String>) -> Result<Matrix, Error> {
+        let name = match &node.value {
+            Value::Scalar(name) if expanding.contains(name) => {
+                return Err(node.error(format!("define \"{}\" refers to itself", name)));
+            }
+            Value::Scalar(name) => Some(name.clone()),
+            _ => None,
+        };
+        expanding.extend(name.clone());
+
+        let mut m = Matrix::identity();
+        for step in self.resolve(node)?.as_list()? {
+            let step_matrix = match &step.value {
+                Value::List(parts) => operation(step, parts)?,
+                _ => self.expand_transform(step, expanding)?,
+            };
+            m = step_matrix * m;
+        }
+
+        if name.is_some() {
+            expanding.pop();
+        }
+        Ok(m)
+    }
+}
+
+fn operation(step: &Node, parts: &[Node]) -> Result<Matrix, Error> {
+    let (name, args) = parts
+        .split_first()
+        .ok_or_else(|| step.error("empty transform".to_string()))?;
+    let name = name.as_str()?;
+    let args = args
+        .iter()
+        .map(Node::as_f64)
+        .collect::<Result<Vec<_>, _>>()?;
+    named_transform(name, &args).ok_or_else(|| {
+        step.error(format!(
+            "unknown transform \"{}\" with {} arguments",
+            name,
+            args.len()
+        ))
+    })
+}
+
+fn camera(entry: &Node) -> Result<Camera, Error> {
+    entry.check_keys(&[
+        "add",
+        "width",
+        "height",
+        "field-of-view",
+        "from",
+        "to",
+        "up",
+    ])?;
+    let camera = Camera::new(
+        entry.require("width")?.as_usize()?,
+        entry.require("height")?.as_usize()?,
+        entry.require("field-of-view")?.as_f64()?,
+    );
+    Ok(camera.view_transform(
+        &entry.require("from")?.as_point()?,
+        &entry.require("to")?.as_point()?,
+        &entry.require("up")?.as_vector()?,
+    ))
+}
+
+fn light(entry: &Node) -> Result<Light, Error> {
+    entry.check_keys(&["add", "at", "intensity"])?;
+    Ok(Light::Point(PointLight::new(
+        entry.require("at")?.as_point()?,
+        entry.require("intensity")?.as_color()?,
+    )))
+}
+
+// One non-blank line with its comment removed
+#[derive(Clone, Copy)]
+struct Line<'a> {
+    number: usize,
+    indent: usize,
+    text: &'a str,
+}
+
+fn parse_yaml(input: &str) -> Result<Node, Error> {
+    let mut lines: Vec<Line> = input
+        .lines()
+        .enumerate()
+        .filter_map(|(index, raw)| {
+            let text = strip_comment(raw).trim_end();
+            let trimmed = text.trim_start();
+            (!trimmed.is_empty()).then(|| Line {
+                number: index + 1,
+                indent: text.len() - trimmed.len(),
+                text: trimmed,
+            })
+        })
+        .collect();
+
+    let first = match lines.first() {
+        Some(line) => *line,
+        None => {
+            return Ok(Node {
+                line: 1,
+                value: Value::List(vec![]),
+            })
+        }
+    };
+    let mut pos = 0;
+    let root = parse_block(&mut lines, &mut pos, first.indent)?;
+    match lines.get(pos) {
+        Some(line) => Err(Error::YamlParse {
+            line: line.number,
+            reason: "unexpected indentation".to_string(),
+        }),
+        None => Ok(root),
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    let bytes = line.as_bytes();
+    let start = (0..bytes.len()).find(|&i| bytes[i] == b'#' && (i == 0 || bytes[i - 1] == b' '));
+    match start {
+        Some(i) => &line[..i],
+        None => line,
+    }
+}
+
+fn is_list_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+// Splits "key: value" and "key:" lines, None for anything else
+fn split_key(text: &str) -> Option<(&str, &str)> {
+    if text.starts_with(['[', '"', '\'']) {
+        return None;
+    }
+    let (key, rest) = match text.find(": ") {
+        Some(i) => (&text[..i], &text[i + 2..]),
+        None => (text.strip_suffix(':')?, ""),
+    };
+    (!key.is_empty()).then(|| (key.trim_end(), rest.trim()))
+}
+
+// Parses the block starting at lines[pos], which sits at indent
+fn parse_block(lines: &mut [Line], pos: &mut usize, indent: usize) -> Result<Node, Error> {
+    let line = lines[*pos];
+    if is_list_item(line.text) {
+        parse_list(lines, pos, indent)
+    } else if split_key(line.text).is_some() {
+        parse_map(lines, pos, indent)
+    } else {
+        *pos += 1;
+        parse_flow(line.text, line.number)
+    }
+}
+
+fn parse_list(lines: &mut [Line], pos: &mut usize, indent: usize) -> Result<Node, Error> {
+    let number = lines[*pos].number;
+    let mut items = vec![];
+    while *pos < lines.len() && lines[*pos].indent == indent && is_list_item(lines[*pos].text) {
+        let line = lines[*pos];
+        let rest = line.text[1..].trim_start();
+        if rest.is_empty() {
+            *pos += 1;
+            items.push(parse_nested(lines, pos, indent, line.number)?);
+        } else {
+            // The item's content lines up with whatever follows it on later lines, so
+            // treat it as if it started on its own line at that column
+            let item_indent = indent + line.text.len() - rest.len();
+            lines[*pos] = Line {
+                indent: item_indent,
+                text: rest,
+                ..line
+            };
+            items.push(parse_block(lines, pos, item_indent)?);
+        }
+    }
+    Ok(Node {
+        line: number,
+        value: Value::List(items),
+    })
+}
+
+fn parse_map(lines: &mut [Line], pos: &mut usize, indent: usize) -> Result<Node, Error> {
+    let number = lines[*pos].number;
+    let mut entries = vec![];
+    while *pos < lines.len() && lines[*pos].indent == indent && !is_list_item(lines[*pos].text) {
+        let line = lines[*pos];
+        let (key, rest) = split_key(line.text).ok_or_else(|| Error::YamlParse {
+            line: line.number,
+            reason: format!("expected \"key: value\", found \"{}\"", line.text),
+        })?;
+        *pos += 1;
+
+        let value = if rest.is_empty() {
+            parse_nested(lines, pos, indent, line.number)?
+        } else {
+            parse_flow(rest, line.number)?
+        };
+        entries.push((key.to_string(), value));
+    }
+    Ok(Node {
+        line: number,
+        value: Value::Map(entries),
+    })
+}
+
+// The value of a "key:" or "-" line with nothing after it. Lists may sit at the same
+// indent as the key that owns them.
+fn parse_nested(
+    lines: &mut [Line],
+    pos: &mut usize,
+    indent: usize,
+    number: usize,
+) -> Result<Node, Error> {
+    match lines.get(*pos) {
+        Some(next)
+            if next.indent > indent || (next.indent == indent && is_list_item(next.text)) =>
+        {
+            parse_block(lines, pos, next.indent)
+        }
+        _ => Ok(Node {
+            line: number,
+            value: Value::Scalar(String::new()),
+        }),
+    }
+}
+
+// A value written on one line: a scalar or a flow list like [ translate, 1, 2, 3 ]
+fn parse_flow(text: &str, line: usize) -> Result<Node, Error> {
+    if !text.starts_with('[') {
+        return Ok(scalar(text, line));
+    }
+    let mut pos = 0;
+    let node = flow_value(text, &mut pos, line)?;
+    if !text[pos..].trim().is_empty() {
+        return Err(Error::YamlParse {
+            line,
+            reason: format!("unexpected \"{}\" after a list", text[pos..].trim()),
+        });
+    }
+    Ok(node)
+}
+
+fn flow_value(text: &str, pos: &mut usize, line: usize) -> Result<Node, Error> {
+    let bytes = text.as_bytes();
+    while bytes.get(*pos) == Some(&b' ') {
+        *pos += 1;
+    }
+    if bytes.get(*pos) != Some(&b'[') {
+        let start = *pos;
+        while bytes.get(*pos).is_some_and(|b| *b != b',' && *b != b']') {
+            *pos += 1;
+        }
+        return Ok(scalar(&text[start..*pos], line));
+    }
+
+    *pos += 1;
+    let mut items = vec![];
+    loop {
+        while bytes.get(*pos) == Some(&b' ') {
+            *pos += 1;
+        }
+        if bytes.get(*pos) == Some(&b']') {
+            *pos += 1;
+            break;
+        }
+        items.push(flow_value(text, pos, line)?);
+        while bytes.get(*pos) == Some(&b' ') {
+            *pos += 1;
+        }
+        match bytes.get(*pos) {
+            Some(b',') => *pos += 1,
+            Some(b']') => {}
+            _ => {
+                return Err(Error::YamlParse {
+                    line,
+                    reason: "unclosed [ in a list".to_string(),
+                })
+            }
+        }
+    }
+    Ok(Node {
+        line,
+        value: Value::List(items),
+    })
+}
+
+fn scalar(text: &str, line: usize) -> Node {
+    let text = text.trim();
+    let unquoted = ['"', '\'']
+        .iter()
+        .find_map(|q| text.strip_prefix(*q)?.strip_suffix(*q))
+        .unwrap_or(text);
+    Node {
+        line,
+        value: Value::Scalar(unquoted.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::FRAC_PI_2;
+
+    use super::*;
+
+    use crate::math::transformation::{rotate_x, scale, translate};
+
+    fn s(text: &str, line: usize) -> Node {
+        scalar(text, line)
+    }
+
+    #[test]
+    fn parsing_block_and_flow_yaml() {
+        let input = "
+# a comment
+- add: camera
+  from: [ -6, 6, -10 ] # trailing comment
+  nested:
+    - [ translate, 1, [2, 3] ]
+    - name
+- plain
+";
+        let got = parse_yaml(input).unwrap();
+        let want = Node {
+            line: 3,
+            value: Value::List(vec![
+                Node {
+                    line: 3,
+                    value: Value::Map(vec![
+                        ("add".to_string(), s("camera", 3)),
+                        (
+                            "from".to_string(),
+                            Node {
+                                line: 4,
+                                value: Value::List(vec![s("-6", 4), s("6", 4), s("-10", 4)]),
+                            },
+                        ),
+                        (
+                            "nested".to_string(),
+                            Node {
+                                line: 6,
+                                value: Value::List(vec![
+                                    Node {
+                                        line: 6,
+                                        value: Value::List(vec![
+                                            s("translate", 6),
+                                            s("1", 6),
+                                            Node {
+                                                line: 6,
+                                                value: Value::List(vec![s("2", 6), s("3", 6)]),
+                                            },
+                                        ]),
+                                    },
+                                    s("name", 7),
+                                ]),
+                            },
+                        ),
+                    ]),
+                },
+                s("plain", 8),
+            ]),
+        };
+        assert_eq!(got, want);
+    }
+
+    const COVER: &str = "
+# ======================================================
+# the camera
+# ======================================================
+
+- add: camera
+  width: 100
+  height: 100
+  field-of-view: 0.785
+  from: [ -6, 6, -10 ]
+  to: [ 6, 0, 6 ]
+  up: [ -0.45, 1, 0 ]
+
+- add: light
+  at: [ 50, 100, -50 ]
+  intensity: [ 1, 1, 1 ]
+
+# an optional second light for additional illumination
+- add: light
+  at: [ -400, 50, -10 ]
+  intensity: [ 0.2, 0.2, 0.2 ]
+
+- define: white-material
+  value:
+    color: [ 1, 1, 1 ]
+    diffuse: 0.7
+    ambient: 0.1
+    specular: 0.0
+    reflective: 0.1
+
+- define: blue-material
+  extend: white-material
+  value:
+    color: [ 0.537, 0.831, 0.914 ]
+
+- define: standard-transform
+  value:
+    - [ translate, 1, -1, 1 ]
+    - [ scale, 0.5, 0.5, 0.5 ]
+
+- define: large-object
+  value:
+    - standard-transform
+    - [ scale, 3.5, 3.5, 3.5 ]
+
+- add: plane
+  material:
+    color: [ 1, 1, 1 ]
+    ambient: 1
+    diffuse: 0
+    specular: 0
+  transform:
+    - [ rotate-x, 1.5707963267948966 ] # pi/2
+    - [ translate, 0, 0, 500 ]
+
+- add: sphere
+  material:
+    color: [ 0.373, 0.404, 0.550 ]
+    diffuse: 0.2
+    ambient: 0.0
+    specular: 1.0
+    shininess: 200
+    reflective: 0.7
+    transparency: 0.7
+    refractive-index: 1.5
+  transform:
+    - large-object
+
+- add: cube
+  material: blue-material
+  transform:
+    - large-object
+    - [ translate, 8.5, 1.5, -0.5 ]
+
+- add: cube
+  material: white-material
+  transform:
+    - large-object
+    - [ translate, 0, 0, 4 ]
+";
+
+    fn build(input: &str) -> SceneBuilder {
+        SceneBuilder::from_entries(&parse_yaml(input).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn parsing_the_cover_scene() {
+        let (_, camera) = parse_scene(COVER).unwrap();
+        assert_eq!(camera.hsize(), 100);
+        assert_eq!(camera.vsize(), 100);
+
+        let scene = build(COVER);
+        assert_eq!(scene.lights.len(), 2);
+        assert_eq!(scene.objects.len(), 4);
+
+        let objects = &scene.objects;
+        assert_eq!(
+            objects[0].get_transform(),
+            translate(0.0, 0.0, 500.0) * rotate_x(FRAC_PI_2)
+        );
+        let large_object = scale(3.5, 3.5, 3.5) * scale(0.5, 0.5, 0.5) * translate(1.0, -1.0, 1.0);
+        assert_eq!(objects[1].get_transform(), large_object);
+        assert_eq!(objects[1].get_material().refractive_index, 1.5);
+
+        // Extended defines keep the base's values under their own
+        let blue = objects[2].get_material();
+        assert_eq!(
+            blue.pattern.pattern_at(&Point::new(0.0, 0.0, 0.0)),
+            Color::new(0.537, 0.831, 0.914)
+        );
+        assert_eq!(blue.reflective, 0.1);
+        assert_eq!(blue.diffuse, 0.7);
+        assert_eq!(
+            objects[2].get_transform(),
+            translate(8.5, 1.5, -0.5) * large_object
+        );
+    }
+
+    #[test]
+    fn parsing_patterns_and_shape_options() {
+        let input = "
+- add: camera
+  width: 10
+  height: 10
+  field-of-view: 1
+  from: [ 0, 0, -5 ]
+  to: [ 0, 0, 0 ]
+  up: [ 0, 1, 0 ]
+- add: group
+  children:
+    - add: cylinder
+      min: 0
+      max: 2
+      closed: true
+      material:
+        pattern:
+          type: stripes
+          colors:
+            - [ 1, 0, 0 ]
+            - [ 0, 0, 1 ]
+          transform:
+            - [ scale, 0.5, 1, 1 ]
+";
+        let (world, _) = parse_scene(input).unwrap();
+        let bounds = world.bounds();
+        assert_eq!(bounds.min(), Point::new(-1.0, 0.0, -1.0));
+        assert_eq!(bounds.max(), Point::new(1.0, 2.0, 1.0));
+
+        let scene = build(input);
+        let cylinder = &scene.objects[0].children().unwrap()[0];
+        let pattern = cylinder.get_material().pattern;
+        assert_eq!(
+            pattern.pattern_at_object(cylinder, &Point::new(0.25, 1.0, 0.0)),
+            Color::red()
+        );
+        assert_eq!(
+            pattern.pattern_at_object(cylinder, &Point::new(0.75, 1.0, 0.0)),
+            Color::blue()
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_line() {
+        let camera = "- add: camera
+  width: 10
+  height: 10
+  field-of-view: 1
+  from: [ 0, 0, -5 ]
+  to: [ 0, 0, 0 ]
+  up: [ 0, 1, 0 ]
+";
+        let error = |body: &str| match parse_scene(&format!("{}{}", camera, body)) {
+            Err(Error::YamlParse { line, reason }) => (line, reason),
+            other => panic!("expected a YAML error, got {:?}", other),
+        };
+
+        assert_eq!(
+            error("- add: teapot\n"),
+            (8, "unknown shape \"teapot\"".to_string())
+        );
+        assert_eq!(
+            error("- add: sphere\n  material: gold\n"),
+            (9, "unknown define \"gold\"".to_string())
+        );
+        assert_eq!(
+            error("- add: sphere\n  transform:\n    - [ spin, 1 ]\n"),
+            (
+                10,
+                "unknown transform \"spin\" with 1 arguments".to_string()
+            )
+        );
+        assert_eq!(
+            error("- add: sphere\n  shadow: false\n"),
+            (9, "unknown key \"shadow\"".to_string())
+        );
+        assert_eq!(
+            error("- add: light\n  at: [ 1, 2 ]\n  intensity: [ 1, 1, 1 ]\n"),
+            (9, "expected a list of three numbers".to_string())
+        );
+
+        assert_eq!(
+            error("- define: a\n  value:\n    - b\n- define: b\n  value:\n    - [ scale, 2, 2, 2 ]\n    - a\n- add: sphere\n  transform: a\n"),
+            (14, "define \"a\" refers to itself".to_string())
+        );
+        assert_eq!(
+            error("- define: a\n  value:\n    - a\n- add: sphere\n  transform: a\n"),
+            (10, "define \"a\" refers to itself".to_string())
+        );
+
+        let no_camera = parse_scene("- add: sphere\n");
+        assert!(matches!(no_camera, Err(Error::YamlParse { line: 1, .. })));
+    }
+}
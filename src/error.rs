@@ -0,0 +1,45 @@
+use std::{fmt::Display, io};
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    ObjParse { line: usize, reason: String },
+    PpmParse(String),
+    StlParse(String),
+    SceneParse(String),
+    YamlParse { line: usize, reason: String },
+    NonInvertibleMatrix,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "IO error: {}", err),
+            Self::ObjParse { line, reason } => {
+                write!(f, "Error parsing OBJ file on line {}: {}", line, reason)
+            }
+            Self::PpmParse(reason) => write!(f, "Error parsing PPM file: {}", reason),
+            Self::StlParse(reason) => write!(f, "Error parsing STL file: {}", reason),
+            Self::SceneParse(reason) => write!(f, "Error parsing scene: {}", reason),
+            Self::YamlParse { line, reason } => {
+                write!(f, "Error parsing YAML scene on line {}: {}", line, reason)
+            }
+            Self::NonInvertibleMatrix => f.write_str("Non invertible matrix"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
@@ -1,8 +1,11 @@
+pub mod error;
+
 pub mod math {
     pub mod epsilon;
     pub mod matrix;
     pub mod point;
     pub mod ray;
+    pub mod rng;
     pub mod transformation;
     pub mod tuple;
     pub mod vector;
@@ -11,17 +14,27 @@ pub mod math {
 pub mod draw {
     pub mod canvas;
     pub mod color;
+    pub mod frame;
     pub mod io {
         pub mod obj;
+        pub mod ppm;
+        #[cfg(feature = "scene")]
+        pub mod scene;
+        pub mod stl;
+        pub mod yaml;
     }
 }
 
 pub mod render {
+    pub mod bounding_box;
     pub mod camera;
+    pub mod environment;
+    pub mod geometry;
     pub mod intersections;
     pub mod light;
     pub mod material;
     pub mod object;
+    pub mod render_config;
     pub mod shape;
     pub mod world;
 
@@ -30,9 +43,12 @@ pub mod render {
 
     pub mod shapes {
         pub mod cone;
+        pub mod csg;
         pub mod cube;
         pub mod cylinder;
+        pub mod disk;
         pub mod group;
+        pub mod indexed_mesh;
         pub mod plane;
         pub mod smooth_triangle;
         pub mod sphere;
@@ -41,6 +57,9 @@ pub mod render {
     }
 
     pub mod lights {
+        pub mod area_light;
+        pub mod directional_light;
         pub mod point_light;
+        pub mod spot_light;
     }
 }
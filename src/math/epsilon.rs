@@ -1,5 +1,10 @@
 pub const EPSILON: f64 = 1.0e-4;
 
+// Shared tolerance for deciding a ray runs parallel to a surface or axis in the shape
+// intersect methods. Magnitudes strictly below it are parallel, anything at or above it is
+// intersected normally, so every shape agrees on the boundary case
+pub const GEOMETRY_EPSILON: f64 = EPSILON;
+
 pub fn round(a: f64) -> f64 {
     (a * 10000.0).round() / 10000.0
 }
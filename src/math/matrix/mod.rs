@@ -1,8 +1,9 @@
 mod matrix2;
 mod matrix3;
 
-use std::ops::{Index, IndexMut, Mul};
+use std::ops::{Index, IndexMut, Mul, MulAssign};
 
+use crate::error::Error;
 use crate::math::epsilon::ApproxEq;
 use crate::math::matrix::matrix3::Matrix3;
 
@@ -88,22 +89,40 @@ impl Matrix {
         }
     }
 
-    fn is_invertible(&self) -> bool {
+    pub fn is_invertible(&self) -> bool {
         !(0.0).approx_eq(self.determinant())
     }
 
-    pub fn inverse(&self) -> Matrix {
-        if !self.is_invertible() {
-            panic!("Non invertible matrix")
+    // Works out every cofactor once, the determinant is the first row's expansion of them.
+    // Degenerate matrices, like a scale by zero, have no inverse and return an error.
+    pub fn try_inverse(&self) -> Result<Matrix, Error> {
+        let mut cofactors = Matrix::new();
+        for row in 0..4 {
+            for col in 0..4 {
+                cofactors[row][col] = self.cofactor(row, col);
+            }
+        }
+        let det: f64 = (0..4).map(|col| self[0][col] * cofactors[0][col]).sum();
+        if det.approx_eq(0.0) {
+            return Err(Error::NonInvertibleMatrix);
         }
+
         let mut m = Matrix::new();
         for row in 0..4 {
             for col in 0..4 {
-                let cofactor = self.cofactor(row, col);
-                m[col][row] = cofactor / self.determinant();
+                m[col][row] = cofactors[row][col] / det;
             }
         }
-        m
+        Ok(m)
+    }
+
+    pub fn inverse(&self) -> Matrix {
+        self.try_inverse().expect("Non invertible matrix")
+    }
+
+    // Composes transforms in reading order: the result applies self first, then other
+    pub fn then(&self, other: &Matrix) -> Matrix {
+        *other * *self
     }
 }
 
@@ -157,6 +176,12 @@ impl Mul for Matrix {
     }
 }
 
+impl MulAssign for Matrix {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
 impl<T> Mul<T> for Matrix
 where
     T: Tuple,
@@ -182,10 +207,12 @@ where
 
 #[cfg(test)]
 mod test {
+    use crate::error::Error;
     use crate::math::{
         epsilon::ApproxEq,
         matrix::{matrix3::Matrix3, Matrix},
         point::Point,
+        transformation::{rotate_x, rotate_y, scale, translate},
         tuple::Tuple,
         vector::Vector,
     };
@@ -422,6 +449,7 @@ mod test {
         ]);
         assert!(m2.determinant().approx_eq(0.0));
         assert!(!m2.is_invertible());
+        assert!(matches!(m2.try_inverse(), Err(Error::NonInvertibleMatrix)));
     }
 
     #[test]
@@ -505,4 +533,82 @@ mod test {
 
         assert_eq!(m_a, m_c * m_b.inverse());
     }
+
+    #[test]
+    fn inverse_times_matrix_is_identity() {
+        let samples = [
+            [
+                [-5.0, 2.0, 6.0, -8.0],
+                [1.0, -5.0, 1.0, 8.0],
+                [7.0, 7.0, -6.0, -7.0],
+                [1.0, -3.0, 7.0, 4.0],
+            ],
+            [
+                [8.0, -5.0, 9.0, 2.0],
+                [7.0, 5.0, 6.0, 1.0],
+                [-6.0, 0.0, 9.0, 6.0],
+                [-3.0, 0.0, -9.0, -4.0],
+            ],
+            [
+                [9.0, 3.0, 0.0, 9.0],
+                [-5.0, -2.0, -6.0, -3.0],
+                [-4.0, 9.0, 6.0, 4.0],
+                [-7.0, 6.0, 6.0, 2.0],
+            ],
+        ];
+        for data in samples {
+            let m = Matrix::new().with_data(data);
+            assert_eq!(m * m.inverse(), Matrix::identity());
+            assert_eq!(m.inverse() * m, Matrix::identity());
+        }
+    }
+
+    #[test]
+    fn degenerate_transforms_have_no_inverse() {
+        let flat = scale(0.0, 1.0, 1.0);
+        assert!(matches!(
+            flat.try_inverse(),
+            Err(Error::NonInvertibleMatrix)
+        ));
+        assert!(scale(2.0, 1.0, 1.0).try_inverse().is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "Non invertible matrix")]
+    fn inverting_a_degenerate_transform_panics() {
+        scale(0.0, 1.0, 1.0).inverse();
+    }
+
+    #[test]
+    fn repeated_inversion_stays_accurate() {
+        // Inverting back and forth shouldn't drift away from the original matrix
+        let original = translate(1.0, -2.0, 3.0) * rotate_y(0.7) * scale(2.0, 0.5, 3.0);
+        let mut m = original;
+        for _ in 0..1000 {
+            m = m.inverse().inverse();
+        }
+        assert_eq!(m, original);
+        assert_eq!(m * original.inverse(), Matrix::identity());
+    }
+
+    #[test]
+    fn matrix_mul_assign_matches_multiplication() {
+        let m_a = translate(1.0, 2.0, 3.0);
+        let m_b = scale(2.0, 2.0, 2.0);
+        let mut m_c = m_a;
+        m_c *= m_b;
+        assert_eq!(m_c, m_a * m_b);
+    }
+
+    #[test]
+    fn matrix_then_applies_transforms_in_reading_order() {
+        let p = Point::new(1.0, 0.0, 1.0);
+        let a = rotate_x(std::f64::consts::PI / 2.0);
+        let b = scale(5.0, 5.0, 5.0);
+        let c = translate(10.0, 5.0, 7.0);
+
+        assert_eq!(a.then(&b) * p, b * (a * p));
+        assert_eq!(a.then(&b).then(&c) * p, Point::new(15.0, 0.0, 7.0));
+        assert_ne!(a.then(&c) * p, c.then(&a) * p);
+    }
 }
@@ -43,6 +43,27 @@ pub fn rotate_z(angle: f64) -> Matrix {
     m
 }
 
+// Rodrigues' rotation about any axis through the origin, counter-clockwise looking
+// down the axis like rotate_x/y/z
+pub fn rotate_axis(axis: Vector, angle: f64) -> Matrix {
+    let axis = axis.normalize();
+    let (x, y, z) = (axis.x(), axis.y(), axis.z());
+    let (sin, cos) = angle.sin_cos();
+    let t = 1.0 - cos;
+
+    let mut m = Matrix::identity();
+    m[0][0] = cos + x * x * t;
+    m[0][1] = x * y * t - z * sin;
+    m[0][2] = x * z * t + y * sin;
+    m[1][0] = y * x * t + z * sin;
+    m[1][1] = cos + y * y * t;
+    m[1][2] = y * z * t - x * sin;
+    m[2][0] = z * x * t - y * sin;
+    m[2][1] = z * y * t + x * sin;
+    m[2][2] = cos + z * z * t;
+    m
+}
+
 pub fn shear(xy: f64, xz: f64, yx: f64, yz: f64, zx: f64, zy: f64) -> Matrix {
     let mut m = Matrix::identity();
     m[0][1] = xy;
@@ -55,6 +76,20 @@ pub fn shear(xy: f64, xz: f64, yx: f64, yz: f64, zx: f64, zy: f64) -> Matrix {
     m
 }
 
+// Looks up a transform the way scene files spell it, e.g. translate with x, y and z.
+// None if the name is unknown or takes a different number of arguments.
+pub fn named_transform(name: &str, args: &[f64]) -> Option<Matrix> {
+    match (name, args) {
+        ("translate", [x, y, z]) => Some(translate(*x, *y, *z)),
+        ("scale", [x, y, z]) => Some(scale(*x, *y, *z)),
+        ("rotate-x", [angle]) => Some(rotate_x(*angle)),
+        ("rotate-y", [angle]) => Some(rotate_y(*angle)),
+        ("rotate-z", [angle]) => Some(rotate_z(*angle)),
+        ("shear", [xy, xz, yx, yz, zx, zy]) => Some(shear(*xy, *xz, *yx, *yz, *zx, *zy)),
+        _ => None,
+    }
+}
+
 pub fn view_transform(from: &Point, to: &Point, up: &Vector) -> Matrix {
     let forward = (*to - *from).normalize();
     let up_normal = up.normalize();
@@ -116,6 +151,13 @@ pub trait Transformable {
         self.with_transform(rotate)
     }
 
+    fn rotate_axis(self, axis: Vector, angle: f64) -> Self
+    where
+        Self: Sized,
+    {
+        self.with_transform(rotate_axis(axis, angle))
+    }
+
     fn shear(self, xy: f64, xz: f64, yx: f64, yz: f64, zx: f64, zy: f64) -> Self
     where
         Self: Sized,
@@ -321,6 +363,44 @@ mod test {
         assert_eq!(got, want);
     }
 
+    #[test]
+    fn inverse_of_shear_unshears_points() {
+        let tx = shear(1.0, 2.0, 0.5, -1.0, 0.25, 3.0);
+        let inv = tx.inverse();
+        assert_eq!(inv * tx, Matrix::identity());
+        assert_eq!(tx * inv, Matrix::identity());
+
+        let points = [
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(2.0, 3.0, 4.0),
+            Point::new(-1.5, 0.25, 10.0),
+            Point::new(7.0, -8.0, -0.5),
+        ];
+        for p in points {
+            let sheared = tx * p;
+            assert_eq!(inv * sheared, p);
+        }
+
+        // The inverse of a single-axis shear is the opposite shear
+        assert_eq!(
+            shear(2.0, 0.0, 0.0, 0.0, 0.0, 0.0).inverse(),
+            shear(-2.0, 0.0, 0.0, 0.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn transformable_shear_round_trip() {
+        let ray = Ray::new(Point::new(1.0, 2.0, 3.0), Vector::new(0.0, 1.0, 0.0));
+        let sheared = ray.shear(1.0, 0.0, 0.0, 2.0, 0.5, 0.0);
+        assert_eq!(sheared.origin, Point::new(3.0, 8.0, 3.5));
+        assert_eq!(sheared.direction, Vector::new(1.0, 1.0, 0.0));
+
+        let restored = sheared.with_transform(sheared.get_transform().inverse());
+        assert_eq!(restored.origin, ray.origin);
+        assert_eq!(restored.direction, ray.direction);
+        assert_eq!(restored.get_transform(), Matrix::identity());
+    }
+
     #[test]
     fn can_apply_multiple_transforms_in_sequence() {
         let p = Point::new(1.0, 0.0, 1.0);
@@ -399,4 +479,32 @@ mod test {
         ]);
         assert_eq!(got, want);
     }
+
+    #[test]
+    fn rotating_about_an_arbitrary_axis() {
+        let p = Point::new(1.0, 2.0, 3.0);
+        assert_eq!(
+            rotate_axis(Vector::new(0.0, 0.0, 1.0), PI / 2.0) * p,
+            rotate_z(PI / 2.0) * p
+        );
+        assert_eq!(
+            rotate_axis(Vector::new(2.0, 0.0, 0.0), PI / 3.0) * p,
+            rotate_x(PI / 3.0) * p
+        );
+
+        // A third of a turn about the diagonal cycles the axes
+        let diagonal = rotate_axis(Vector::new(1.0, 1.0, 1.0), 2.0 * PI / 3.0);
+        assert_eq!(diagonal * p, Point::new(3.0, 1.0, 2.0));
+    }
+
+    #[test]
+    fn looking_up_transforms_by_name() {
+        assert_eq!(
+            named_transform("translate", &[1.0, 2.0, 3.0]),
+            Some(translate(1.0, 2.0, 3.0))
+        );
+        assert_eq!(named_transform("rotate-y", &[PI]), Some(rotate_y(PI)));
+        assert_eq!(named_transform("translate", &[1.0]), None);
+        assert_eq!(named_transform("spin", &[1.0]), None);
+    }
 }
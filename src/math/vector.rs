@@ -1,6 +1,9 @@
 use std::ops::{Add, Div, Mul, Neg, Sub};
 
-use super::{epsilon::ApproxEq, tuple::Tuple};
+use super::{
+    epsilon::{ApproxEq, EPSILON},
+    tuple::Tuple,
+};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Vector {
@@ -29,6 +32,33 @@ impl Vector {
             z: (self.x * other.y) - (self.y * other.x),
         }
     }
+
+    pub fn angle_between(&self, other: &Vector) -> f64 {
+        let cos = (*self * *other) / (self.magnitude() * other.magnitude());
+        cos.clamp(-1.0, 1.0).acos()
+    }
+
+    // Rotates this vector towards the target by at most max_angle radians, keeping its length
+    pub fn rotate_towards(&self, target: &Vector, max_angle: f64) -> Vector {
+        let angle = self.angle_between(target);
+        if angle <= max_angle {
+            return target.normalize() * self.magnitude();
+        }
+
+        let mut axis = self.cross(target);
+        if axis.magnitude() < EPSILON {
+            // Opposite vectors, any perpendicular axis will do
+            axis = self.cross(&Vector::new(1.0, 0.0, 0.0));
+            if axis.magnitude() < EPSILON {
+                axis = self.cross(&Vector::new(0.0, 1.0, 0.0));
+            }
+        }
+        let k = axis.normalize();
+        let (sin, cos) = max_angle.sin_cos();
+
+        // Rodrigues' rotation formula
+        *self * cos + k.cross(self) * sin + k * ((k * *self) * (1.0 - cos))
+    }
 }
 
 impl Tuple for Vector {
@@ -142,7 +172,9 @@ impl Div<f64> for Vector {
 
 #[cfg(test)]
 mod test {
-    use crate::math::tuple::Tuple;
+    use std::f64::consts::PI;
+
+    use crate::math::{epsilon::ApproxEq, tuple::Tuple};
 
     use super::{super::point::Point, Vector};
 
@@ -293,4 +325,25 @@ mod test {
         let want = Vector::new(1.0, 0.0, 0.0);
         assert_eq!(got, want);
     }
+
+    #[test]
+    fn angle_between_perpendicular_vectors() {
+        let v1 = Vector::new(1.0, 0.0, 0.0);
+        let v2 = Vector::new(0.0, 1.0, 0.0);
+        assert!(v1.angle_between(&v2).approx_eq(PI / 2.0));
+    }
+
+    #[test]
+    fn rotate_towards_is_clamped_to_max_angle() {
+        let root_2_2 = (2.0 as f64).sqrt() / 2.0;
+        let v = Vector::new(2.0, 0.0, 0.0);
+        let target = Vector::new(0.0, 1.0, 0.0);
+
+        let got = v.rotate_towards(&target, PI / 4.0);
+        assert_eq!(got, Vector::new(2.0 * root_2_2, 2.0 * root_2_2, 0.0));
+        assert!(got.angle_between(&v).approx_eq(PI / 4.0));
+
+        let got = v.rotate_towards(&target, PI);
+        assert_eq!(got, Vector::new(0.0, 2.0, 0.0));
+    }
 }
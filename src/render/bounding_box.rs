@@ -0,0 +1,399 @@
+use crate::math::{matrix::Matrix, point::Point, ray::Ray, tuple::Tuple};
+
+// Axis aligned box, an empty box has min above max on every axis
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    min: Point,
+    max: Point,
+}
+
+impl BoundingBox {
+    pub fn new(min: Point, max: Point) -> Self {
+        Self { min, max }
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            min: Point::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            max: Point::new(-f64::INFINITY, -f64::INFINITY, -f64::INFINITY),
+        }
+    }
+
+    pub fn min(&self) -> Point {
+        self.min
+    }
+
+    pub fn max(&self) -> Point {
+        self.max
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x() > self.max.x() || self.min.y() > self.max.y() || self.min.z() > self.max.z()
+    }
+
+    pub fn is_finite(&self) -> bool {
+        [self.min, self.max]
+            .iter()
+            .all(|p| p.x().is_finite() && p.y().is_finite() && p.z().is_finite())
+    }
+
+    pub fn center(&self) -> Point {
+        Point::new(
+            (self.min.x() + self.max.x()) / 2.0,
+            (self.min.y() + self.max.y()) / 2.0,
+            (self.min.z() + self.max.z()) / 2.0,
+        )
+    }
+
+    pub fn contains_point(&self, point: &Point) -> bool {
+        (self.min.x()..=self.max.x()).contains(&point.x())
+            && (self.min.y()..=self.max.y()).contains(&point.y())
+            && (self.min.z()..=self.max.z()).contains(&point.z())
+    }
+
+    pub fn contains_box(&self, other: &BoundingBox) -> bool {
+        self.contains_point(&other.min) && self.contains_point(&other.max)
+    }
+
+    pub fn add_point(&mut self, point: &Point) {
+        self.min = Point::new(
+            self.min.x().min(point.x()),
+            self.min.y().min(point.y()),
+            self.min.z().min(point.z()),
+        );
+        self.max = Point::new(
+            self.max.x().max(point.x()),
+            self.max.y().max(point.y()),
+            self.max.z().max(point.z()),
+        );
+    }
+
+    pub fn add_box(&mut self, other: &BoundingBox) {
+        if !other.is_empty() {
+            self.add_point(&other.min);
+            self.add_point(&other.max);
+        }
+    }
+
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        let mut merged = *self;
+        merged.add_box(other);
+        merged
+    }
+
+    // Slab test against the ray's whole line, so boxes behind the origin still count
+    // and no intersections with negative t are lost
+    pub fn intersects(&self, ray: &Ray) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        let axes = [
+            (
+                self.min.x(),
+                self.max.x(),
+                ray.origin.x(),
+                ray.direction.x(),
+            ),
+            (
+                self.min.y(),
+                self.max.y(),
+                ray.origin.y(),
+                ray.direction.y(),
+            ),
+            (
+                self.min.z(),
+                self.max.z(),
+                ray.origin.z(),
+                ray.direction.z(),
+            ),
+        ];
+        let (t_min, t_max) = axes.iter().fold(
+            (f64::NEG_INFINITY, f64::INFINITY),
+            |(t_min, t_max), (min, max, origin, direction)| {
+                let inv = 1.0 / direction;
+                let t0 = (min - origin) * inv;
+                let t1 = (max - origin) * inv;
+                (t_min.max(t0.min(t1)), t_max.min(t0.max(t1)))
+            },
+        );
+        t_min <= t_max
+    }
+
+    // Halves the box across the middle of its longest axis
+    pub fn split(&self) -> (BoundingBox, BoundingBox) {
+        let (min, max) = (self.min, self.max);
+        let dx = max.x() - min.x();
+        let dy = max.y() - min.y();
+        let dz = max.z() - min.z();
+        let longest = dx.max(dy).max(dz);
+
+        let (mut x0, mut y0, mut z0) = (min.x(), min.y(), min.z());
+        let (mut x1, mut y1, mut z1) = (max.x(), max.y(), max.z());
+        if longest == dx {
+            x0 = min.x() + dx / 2.0;
+            x1 = x0;
+        } else if longest == dy {
+            y0 = min.y() + dy / 2.0;
+            y1 = y0;
+        } else {
+            z0 = min.z() + dz / 2.0;
+            z1 = z0;
+        }
+
+        (
+            BoundingBox::new(min, Point::new(x1, y1, z1)),
+            BoundingBox::new(Point::new(x0, y0, z0), max),
+        )
+    }
+
+    pub fn corners(&self) -> [Point; 8] {
+        let (min, max) = (self.min, self.max);
+        [
+            Point::new(min.x(), min.y(), min.z()),
+            Point::new(min.x(), min.y(), max.z()),
+            Point::new(min.x(), max.y(), min.z()),
+            Point::new(min.x(), max.y(), max.z()),
+            Point::new(max.x(), min.y(), min.z()),
+            Point::new(max.x(), min.y(), max.z()),
+            Point::new(max.x(), max.y(), min.z()),
+            Point::new(max.x(), max.y(), max.z()),
+        ]
+    }
+
+    // Box around the transformed corners of this one
+    pub fn transform(&self, m: &Matrix) -> BoundingBox {
+        if self.is_empty() {
+            return *self;
+        }
+        let mut transformed = BoundingBox::empty();
+        for corner in self.corners() {
+            transformed.add_point(&Self::transform_point(m, &corner));
+        }
+        transformed
+    }
+
+    // Matrix * point, skipping zero entries so infinite coordinates (planes, open
+    // cylinders) don't turn into NaN
+    fn transform_point(m: &Matrix, p: &Point) -> Point {
+        let coords = [p.x(), p.y(), p.z(), 1.0];
+        let row = |i: usize| -> f64 {
+            (0..4)
+                .filter(|&j| m[i][j] != 0.0)
+                .map(|j| m[i][j] * coords[j])
+                .sum()
+        };
+        Point::new(row(0), row(1), row(2))
+    }
+}
+
+// Many boxes stored as one array per component, so testing a ray against all of them
+// runs over contiguous memory
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BoundingBoxes {
+    min_x: Vec<f64>,
+    min_y: Vec<f64>,
+    min_z: Vec<f64>,
+    max_x: Vec<f64>,
+    max_y: Vec<f64>,
+    max_z: Vec<f64>,
+}
+
+impl BoundingBoxes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.min_x.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min_x.is_empty()
+    }
+
+    pub fn push(&mut self, bounds: &BoundingBox) {
+        self.min_x.push(bounds.min.x());
+        self.min_y.push(bounds.min.y());
+        self.min_z.push(bounds.min.z());
+        self.max_x.push(bounds.max.x());
+        self.max_y.push(bounds.max.y());
+        self.max_z.push(bounds.max.z());
+    }
+
+    // Same test as BoundingBox::intersects, for every box at once
+    pub fn intersects(&self, ray: &Ray) -> Vec<bool> {
+        let len = self.len();
+        let mut t_min = vec![f64::NEG_INFINITY; len];
+        let mut t_max = vec![f64::INFINITY; len];
+        let axes = [
+            (&self.min_x, &self.max_x, ray.origin.x(), ray.direction.x()),
+            (&self.min_y, &self.max_y, ray.origin.y(), ray.direction.y()),
+            (&self.min_z, &self.max_z, ray.origin.z(), ray.direction.z()),
+        ];
+
+        for (mins, maxs, origin, direction) in axes {
+            let inv = 1.0 / direction;
+            for i in 0..len {
+                let t0 = (mins[i] - origin) * inv;
+                let t1 = (maxs[i] - origin) * inv;
+                t_min[i] = t_min[i].max(t0.min(t1));
+                t_max[i] = t_max[i].min(t0.max(t1));
+            }
+        }
+
+        (0..len)
+            .map(|i| {
+                let empty = self.min_x[i] > self.max_x[i]
+                    || self.min_y[i] > self.max_y[i]
+                    || self.min_z[i] > self.max_z[i];
+                !empty && t_min[i] <= t_max[i]
+            })
+            .collect()
+    }
+}
+
+impl FromIterator<BoundingBox> for BoundingBoxes {
+    fn from_iter<T: IntoIterator<Item = BoundingBox>>(iter: T) -> Self {
+        let mut boxes = Self::new();
+        for bounds in iter {
+            boxes.push(&bounds);
+        }
+        boxes
+    }
+}
+
+impl Default for BoundingBox {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+
+    use super::{BoundingBox, BoundingBoxes};
+    use crate::math::{
+        point::Point,
+        ray::Ray,
+        rng::unit_f64,
+        transformation::{rotate_y, scale, translate},
+        tuple::Tuple,
+        vector::Vector,
+    };
+
+    #[test]
+    fn adding_points_grows_an_empty_box() {
+        let mut bb = BoundingBox::empty();
+        assert!(bb.is_empty());
+        bb.add_point(&Point::new(-5.0, 2.0, 0.0));
+        bb.add_point(&Point::new(7.0, 0.0, -3.0));
+
+        assert!(!bb.is_empty());
+        assert_eq!(bb.min(), Point::new(-5.0, 0.0, -3.0));
+        assert_eq!(bb.max(), Point::new(7.0, 2.0, 0.0));
+        assert!(bb.contains_point(&Point::new(0.0, 1.0, -1.0)));
+        assert!(!bb.contains_point(&Point::new(0.0, 3.0, -1.0)));
+    }
+
+    #[test]
+    fn union_of_two_boxes() {
+        let a = BoundingBox::new(Point::new(-5.0, -2.0, 0.0), Point::new(7.0, 4.0, 4.0));
+        let b = BoundingBox::new(Point::new(8.0, -7.0, -2.0), Point::new(14.0, 2.0, 8.0));
+        let got = a.union(&b);
+        assert_eq!(got.min(), Point::new(-5.0, -7.0, -2.0));
+        assert_eq!(got.max(), Point::new(14.0, 4.0, 8.0));
+        assert_eq!(a.union(&BoundingBox::empty()), a);
+    }
+
+    #[test]
+    fn transforming_a_bounding_box() {
+        let bb = BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0));
+        let got = bb.transform(&(translate(1.0, 0.0, 0.0) * rotate_y(PI / 4.0)));
+        let r2 = f64::sqrt(2.0);
+        assert_eq!(got.min(), Point::new(1.0 - r2, -1.0, -r2));
+        assert_eq!(got.max(), Point::new(1.0 + r2, 1.0, r2));
+    }
+
+    #[test]
+    fn transforming_an_unbounded_box_keeps_finite_axes() {
+        let plane = BoundingBox::new(
+            Point::new(-f64::INFINITY, 0.0, -f64::INFINITY),
+            Point::new(f64::INFINITY, 0.0, f64::INFINITY),
+        );
+        let got = plane.transform(&(translate(0.0, 2.0, 0.0) * scale(2.0, 2.0, 2.0)));
+        assert_eq!(got.min().y(), 2.0);
+        assert_eq!(got.max().y(), 2.0);
+        assert_eq!(got.min().x(), -f64::INFINITY);
+        assert!(!got.is_finite());
+    }
+
+    #[test]
+    fn ray_against_a_box() {
+        let bb = BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0));
+        let hit = Ray::new(Point::new(5.0, 0.5, 0.0), Vector::new(-1.0, 0.0, 0.0));
+        let miss = Ray::new(Point::new(-2.0, 0.0, 0.0), Vector::new(2.0, 4.0, 6.0));
+        let parallel = Ray::new(Point::new(0.0, 2.0, 2.0), Vector::new(0.0, 0.0, -1.0));
+
+        assert!(bb.intersects(&hit));
+        assert!(!bb.intersects(&miss));
+        assert!(!bb.intersects(&parallel));
+        assert!(!BoundingBox::empty().intersects(&hit));
+    }
+
+    #[test]
+    fn box_arrays_match_single_box_tests() {
+        let random = |seed: u64, i: u64| unit_f64(&[seed, i]) * 10.0 - 5.0;
+        let random_point =
+            |seed: u64| Point::new(random(seed, 0), random(seed, 1), random(seed, 2));
+
+        let mut boxes: Vec<BoundingBox> = (0..100)
+            .map(|i| {
+                let mut bb = BoundingBox::empty();
+                bb.add_point(&random_point(i * 2));
+                bb.add_point(&random_point(i * 2 + 1));
+                bb
+            })
+            .collect();
+        boxes.push(BoundingBox::empty());
+        let arrays: BoundingBoxes = boxes.iter().copied().collect();
+
+        for r in 0..100 {
+            let ray = Ray::new(
+                random_point(1000 + r * 2),
+                random_point(1001 + r * 2) - Point::new(0.0, 0.0, 0.0),
+            );
+            let want: Vec<bool> = boxes.iter().map(|bb| bb.intersects(&ray)).collect();
+            assert_eq!(arrays.intersects(&ray), want);
+        }
+    }
+
+    #[test]
+    fn box_contains_another_box() {
+        let bb = BoundingBox::new(Point::new(5.0, -2.0, 0.0), Point::new(11.0, 4.0, 7.0));
+        let inside = BoundingBox::new(Point::new(6.0, -1.0, 1.0), Point::new(10.0, 3.0, 6.0));
+        let overlapping = BoundingBox::new(Point::new(4.0, -3.0, -1.0), Point::new(10.0, 3.0, 6.0));
+        assert!(bb.contains_box(&bb));
+        assert!(bb.contains_box(&inside));
+        assert!(!bb.contains_box(&overlapping));
+
+        let mut grown = bb;
+        grown.add_box(&overlapping);
+        assert!(grown.contains_box(&bb) && grown.contains_box(&overlapping));
+    }
+
+    #[test]
+    fn splitting_a_box_along_its_longest_axis() {
+        let bb = BoundingBox::new(Point::new(-1.0, -2.0, -3.0), Point::new(9.0, 5.5, 3.0));
+        let (left, right) = bb.split();
+        assert_eq!(left.min(), Point::new(-1.0, -2.0, -3.0));
+        assert_eq!(left.max(), Point::new(4.0, 5.5, 3.0));
+        assert_eq!(right.min(), Point::new(4.0, -2.0, -3.0));
+        assert_eq!(right.max(), Point::new(9.0, 5.5, 3.0));
+
+        let tall = BoundingBox::new(Point::new(-1.0, -2.0, -3.0), Point::new(5.0, 8.0, 3.0));
+        let (left, right) = tall.split();
+        assert_eq!(left.max(), Point::new(5.0, 3.0, 3.0));
+        assert_eq!(right.min(), Point::new(-1.0, 3.0, -3.0));
+    }
+}
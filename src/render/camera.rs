@@ -1,7 +1,22 @@
-use crate::math::{
-    matrix::Matrix, point::Point, ray::Ray, transformation::Transformable, tuple::Tuple,
+use crate::{
+    math::{
+        matrix::Matrix, point::Point, ray::Ray, transformation::Transformable, tuple::Tuple,
+        vector::Vector,
+    },
+    render::world::World,
 };
 
+// Perspective rays spread out from the eye. Orthographic rays all run along the view
+// direction from points spread over an image plane width world units across.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Projection {
+    #[default]
+    Perspective,
+    Orthographic {
+        width: f64,
+    },
+}
+
 #[derive(Debug)]
 pub struct Camera {
     hsize: usize,           // Horizontal size (px) of the picture that will be rendered
@@ -12,20 +27,13 @@ pub struct Camera {
     half_width: f64,        // Half of the picture's width in world space units
     half_height: f64,       // Half of the picture's height in world space units
     inv_matrix: Matrix,     // Cached inverse calculation of the camera's transform matrix
+    projection: Projection,
 }
 
 impl Camera {
     pub fn new(hsize: usize, vsize: usize, field_of_view: f64) -> Self {
         let half_view = (field_of_view / 2.0).tan();
-        let aspect_ratio = (hsize as f64) / (vsize as f64);
-        let (half_width, half_height) = {
-            if aspect_ratio >= 1.0 {
-                (half_view, half_view / aspect_ratio)
-            } else {
-                (half_view * aspect_ratio, half_view)
-            }
-        };
-        let pixel_size = (half_width * 2.0) / (hsize as f64);
+        let (half_width, half_height, pixel_size) = image_plane(hsize, vsize, half_view);
         Self {
             hsize,
             vsize,
@@ -35,20 +43,82 @@ impl Camera {
             half_width,
             transformation: Matrix::identity(),
             inv_matrix: Matrix::identity().inverse(),
+            projection: Projection::Perspective,
         }
     }
 
+    // Orthographic cameras size their image plane from the projection's width instead
+    // of the field of view
+    pub fn with_projection(self, projection: Projection) -> Self {
+        let half_view = match projection {
+            Projection::Perspective => (self.field_of_view / 2.0).tan(),
+            Projection::Orthographic { width } => width / 2.0,
+        };
+        let (half_width, half_height, pixel_size) = image_plane(self.hsize, self.vsize, half_view);
+        Self {
+            pixel_size,
+            half_width,
+            half_height,
+            projection,
+            ..self
+        }
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
     pub fn ray_for_pixel(&self, x: usize, y: usize) -> Ray {
-        let x_offset = ((x as f64) + 0.5) * self.pixel_size;
-        let y_offset = ((y as f64) + 0.5) * self.pixel_size;
+        self.ray_for_pixel_offset(x, y, 0.5, 0.5)
+    }
+
+    // (dx, dy) in [0, 1) picks the point inside the pixel the ray passes through,
+    // with (0, 0) being the pixel's top left corner
+    pub fn ray_for_pixel_offset(&self, x: usize, y: usize, dx: f64, dy: f64) -> Ray {
+        let x_offset = ((x as f64) + dx) * self.pixel_size;
+        let y_offset = ((y as f64) + dy) * self.pixel_size;
 
         let world_x = self.half_width - x_offset;
         let world_y = self.half_height - y_offset;
 
-        let pixel = self.inv_matrix * Point::new(world_x, world_y, -1.0);
-        let origin = self.inv_matrix * Point::new(0.0, 0.0, 0.0);
-        let direction = (pixel - origin).normalize();
-        Ray::new(origin, direction)
+        match self.projection {
+            Projection::Perspective => {
+                let pixel = self.inv_matrix * Point::new(world_x, world_y, -1.0);
+                let origin = self.inv_matrix * Point::new(0.0, 0.0, 0.0);
+                let direction = (pixel - origin).normalize();
+                Ray::new(origin, direction)
+            }
+            Projection::Orthographic { .. } => {
+                let origin = self.inv_matrix * Point::new(world_x, world_y, 0.0);
+                let direction = (self.inv_matrix * Vector::new(0.0, 0.0, -1.0)).normalize();
+                Ray::new(origin, direction)
+            }
+        }
+    }
+
+    // A camera of the same size and projection looking down +z at the world's bounds.
+    // Perspective cameras back off far enough that the sphere around the bounds fits
+    // inside the narrower side of the view. Orthographic rays don't spread, so their view
+    // is set by the projection's width and they only back off to start in front of it.
+    pub fn frame(self, world: &World, field_of_view: f64) -> Self {
+        let camera =
+            Camera::new(self.hsize, self.vsize, field_of_view).with_projection(self.projection);
+        let bounds = world.bounds();
+        if bounds.is_empty() {
+            return camera;
+        }
+
+        let center = bounds.center();
+        let radius = (bounds.max() - center).magnitude();
+        let distance = match camera.projection {
+            Projection::Perspective => {
+                let half_view = camera.half_width.min(camera.half_height).atan();
+                radius / half_view.sin()
+            }
+            Projection::Orthographic { .. } => 2.0 * radius,
+        };
+        let from = center + Vector::new(0.0, 0.0, -distance);
+        camera.view_transform(&from, &center, &Vector::new(0.0, 1.0, 0.0))
     }
 
     pub fn hsize(&self) -> usize {
@@ -76,10 +146,25 @@ impl Transformable for Camera {
             half_width: self.half_width,
             half_height: self.half_height,
             inv_matrix: new_tform.inverse(),
+            projection: self.projection,
         }
     }
 }
 
+// Half the width and height of the image plane, one unit in front of the camera, and
+// the size of a pixel on it. half_view is half the plane's longer side.
+fn image_plane(hsize: usize, vsize: usize, half_view: f64) -> (f64, f64, f64) {
+    let aspect_ratio = (hsize as f64) / (vsize as f64);
+    let (half_width, half_height) = {
+        if aspect_ratio >= 1.0 {
+            (half_view, half_view / aspect_ratio)
+        } else {
+            (half_view * aspect_ratio, half_view)
+        }
+    };
+    (half_width, half_height, (half_width * 2.0) / (hsize as f64))
+}
+
 #[cfg(test)]
 mod test {
     use crate::{
@@ -95,7 +180,7 @@ mod test {
     };
     use std::f64::consts::PI;
 
-    use super::Camera;
+    use super::{Camera, Projection};
     use crate::render::world::World;
 
     #[test]
@@ -136,6 +221,20 @@ mod test {
         assert_eq!(ray.direction, Vector::new(0.66519, 0.33259, -0.66851));
     }
 
+    #[test]
+    fn ray_for_pixel_samples_the_pixel_center() {
+        let c = Camera::new(201, 101, PI / 2.0).rotate_y(PI / 4.0);
+        for (x, y) in [(0, 0), (100, 50), (200, 100)] {
+            let want = c.ray_for_pixel(x, y);
+            let got = c.ray_for_pixel_offset(x, y, 0.5, 0.5);
+            assert_eq!(got.origin, want.origin);
+            assert_eq!(got.direction, want.direction);
+        }
+
+        let corner = c.ray_for_pixel_offset(100, 50, 0.0, 0.0);
+        assert_ne!(corner.direction, c.ray_for_pixel(100, 50).direction);
+    }
+
     #[test]
     fn constructing_a_ray_when_camera_is_transformed() {
         let tform = rotate_y(PI / 4.0) * translate(0.0, -2.0, 5.0);
@@ -167,4 +266,62 @@ mod test {
             assert!(false);
         }
     }
+
+    #[test]
+    fn framing_the_default_world_keeps_it_in_view() {
+        let world = World::default();
+        let c = Camera::new(160, 90, PI / 2.0).frame(&world, PI / 3.0);
+        assert!(c.field_of_view.approx_eq(PI / 3.0));
+
+        // Both spheres sit inside the world's bounds
+        for corner in world.bounds().corners() {
+            let p = c.get_transform() * corner;
+            let depth = -p.z();
+            assert!(depth > 0.0);
+            assert!((p.x() / depth).abs() <= c.half_width + 1e-9);
+            assert!((p.y() / depth).abs() <= c.half_height + 1e-9);
+        }
+    }
+
+    #[test]
+    fn orthographic_rays_are_parallel() {
+        let c = Camera::new(201, 101, PI / 2.0)
+            .with_projection(Projection::Orthographic { width: 4.0 })
+            .rotate_y(PI / 4.0);
+        let center = c.ray_for_pixel(100, 50);
+        let corner = c.ray_for_pixel(0, 0);
+
+        let root_2_2 = 2.0_f64.sqrt() / 2.0;
+        let forward = Vector::new(root_2_2, 0.0, -root_2_2);
+        assert_eq!(center.direction, forward);
+        assert_eq!(corner.direction, forward);
+        assert_eq!(center.origin, Point::new(0.0, 0.0, 0.0));
+        // The plane is 4 units wide, so the corner pixel's center sits half a pixel in
+        // from its edges
+        let half = c.pixel_size / 2.0;
+        let offset = corner.origin - center.origin;
+        assert!(offset
+            .magnitude()
+            .approx_eq((2.0 - half).hypot(c.half_height - half)));
+        assert_eq!(Camera::new(1, 1, 1.0).projection(), Projection::Perspective);
+    }
+
+    #[test]
+    fn framing_keeps_an_orthographic_projection() {
+        let world = World::default();
+        let projection = Projection::Orthographic { width: 6.0 };
+        let c = Camera::new(160, 90, PI / 2.0)
+            .with_projection(projection)
+            .frame(&world, PI / 3.0);
+        assert_eq!(c.projection(), projection);
+        assert!(c.half_width.approx_eq(3.0));
+
+        // The whole world sits in front of the image plane and inside its width
+        for corner in world.bounds().corners() {
+            let p = c.get_transform() * corner;
+            assert!(-p.z() > 0.0);
+            assert!(p.x().abs() <= c.half_width);
+            assert!(p.y().abs() <= c.half_height);
+        }
+    }
 }
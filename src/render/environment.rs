@@ -0,0 +1,113 @@
+use crate::{
+    draw::color::Color,
+    math::{tuple::Tuple, vector::Vector},
+};
+
+// Angular radius of the sun disk, in radians
+const SUN_RADIUS: f64 = 0.02;
+
+// What a ray sees when it leaves the scene without hitting anything
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Environment {
+    Solid(Color),
+    Gradient(Color, Color), // Blends from the first color straight down to the second straight up
+    Sky {
+        sun_direction: Vector,
+        sun_color: Color,
+        turbidity: f64, // 1.0 is a clear sky, higher values wash the blue out towards the horizon haze
+    },
+}
+
+impl Environment {
+    pub fn color_at(&self, direction: &Vector) -> Color {
+        match self {
+            Self::Solid(color) => *color,
+            Self::Gradient(bottom, top) => {
+                let t = (direction.normalize().y() + 1.0) / 2.0;
+                *bottom + (*top - *bottom) * t
+            }
+            Self::Sky {
+                sun_direction,
+                sun_color,
+                turbidity,
+            } => {
+                let direction = direction.normalize();
+                let cos_sun = direction * sun_direction.normalize();
+                if cos_sun >= SUN_RADIUS.cos() {
+                    return *sun_color;
+                }
+
+                let horizon = Self::horizon_color();
+                let haze = ((turbidity - 1.0) / 9.0).clamp(0.0, 1.0);
+                let zenith = Color::new(0.15, 0.35, 0.75) * (1.0 - haze) + horizon * haze;
+                let height = direction.y().max(0.0);
+                let sky = horizon + (zenith - horizon) * height;
+
+                // Soft glow around the sun, wider in hazy skies
+                let glow = cos_sun.max(0.0).powf(64.0 / turbidity.max(1.0)) * 0.5;
+                sky + *sun_color * glow
+            }
+        }
+    }
+
+    // Color of the sky gradient at the horizon, before any sun glow
+    pub fn horizon_color() -> Color {
+        Color::new(0.8, 0.85, 0.9)
+    }
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self::Solid(Color::black())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Environment;
+    use crate::{
+        draw::color::Color,
+        math::{tuple::Tuple, vector::Vector},
+    };
+
+    fn test_sky() -> Environment {
+        Environment::Sky {
+            sun_direction: Vector::new(0.0, 1.0, 0.0),
+            sun_color: Color::new(1.0, 0.9, 0.7),
+            turbidity: 2.0,
+        }
+    }
+
+    #[test]
+    fn solid_environment_is_the_same_in_every_direction() {
+        let env = Environment::Solid(Color::red());
+        assert_eq!(env.color_at(&Vector::new(0.0, 1.0, 0.0)), Color::red());
+        assert_eq!(env.color_at(&Vector::new(1.0, -1.0, 0.0)), Color::red());
+        assert_eq!(Environment::default(), Environment::Solid(Color::black()));
+    }
+
+    #[test]
+    fn looking_at_the_sun_returns_the_sun_color() {
+        let env = test_sky();
+        let got = env.color_at(&Vector::new(0.001, 1.0, 0.0));
+        assert_eq!(got, Color::new(1.0, 0.9, 0.7));
+    }
+
+    #[test]
+    fn looking_at_the_horizon_returns_the_horizon_color() {
+        let env = test_sky();
+        let got = env.color_at(&Vector::new(1.0, 0.0, 0.0));
+        assert_eq!(got, Environment::horizon_color());
+    }
+
+    #[test]
+    fn gradient_environment_blends_on_the_vertical_direction() {
+        let env = Environment::Gradient(Color::white(), Color::blue());
+        assert_eq!(env.color_at(&Vector::new(0.0, -2.0, 0.0)), Color::white());
+        assert_eq!(env.color_at(&Vector::new(0.0, 1.0, 0.0)), Color::blue());
+        assert_eq!(
+            env.color_at(&Vector::new(1.0, 0.0, 0.0)),
+            Color::new(0.5, 0.5, 1.0)
+        );
+    }
+}
@@ -0,0 +1,120 @@
+// Ray queries against simple primitives given directly by their dimensions, without
+// building an Object. Each returns the t values of the intersections in ascending order.
+
+use crate::{
+    math::{epsilon::GEOMETRY_EPSILON, point::Point, ray::Ray, tuple::Tuple, vector::Vector},
+    render::{
+        intersections::LocalTs,
+        shapes::{cube::Cube, sphere::Sphere},
+    },
+};
+
+pub fn intersect_ray_sphere(ray: &Ray, center: &Point, radius: f64) -> Vec<f64> {
+    // Scaling origin and direction by the same amount leaves t unchanged
+    let local = Ray::new(
+        Point::new(0.0, 0.0, 0.0) + (ray.origin - *center) / radius,
+        ray.direction / radius,
+    );
+    sorted(Sphere::new().local_intersect_ts(&local))
+}
+
+pub fn intersect_ray_plane(ray: &Ray, point: &Point, normal: &Vector) -> Vec<f64> {
+    let denom = ray.direction * *normal;
+    if denom.abs() < GEOMETRY_EPSILON {
+        return vec![];
+    }
+    vec![((*point - ray.origin) * *normal) / denom]
+}
+
+// Axis aligned box between the min and max corners
+pub fn intersect_ray_box(ray: &Ray, min: &Point, max: &Point) -> Vec<f64> {
+    let half = (*max - *min) / 2.0;
+    let center = *min + half;
+    let to_local = |v: Vector| Vector::new(v.x() / half.x(), v.y() / half.y(), v.z() / half.z());
+    let local = Ray::new(
+        Point::new(0.0, 0.0, 0.0) + to_local(ray.origin - center),
+        to_local(ray.direction),
+    );
+    sorted(Cube::new().local_intersect_ts(&local))
+}
+
+fn sorted(ts: LocalTs) -> Vec<f64> {
+    let mut ts = ts.to_vec();
+    ts.sort_by(|a, b| a.total_cmp(b));
+    ts
+}
+
+#[cfg(test)]
+mod test {
+    use super::{intersect_ray_box, intersect_ray_plane, intersect_ray_sphere};
+    use crate::{
+        math::{
+            epsilon::ApproxEq, point::Point, ray::Ray, transformation::Transformable, tuple::Tuple,
+            vector::Vector,
+        },
+        render::{intersections::Intersections, object::Object},
+    };
+
+    fn object_ts(obj: &Object, ray: &Ray) -> Vec<f64> {
+        let mut ints = Intersections::new();
+        obj.intersect(ray, &mut ints);
+        ints.iter().map(|int| int.t()).collect()
+    }
+
+    fn assert_ts(got: Vec<f64>, want: Vec<f64>) {
+        assert_eq!(got.len(), want.len());
+        for (got, want) in got.into_iter().zip(want) {
+            assert!(got.approx_eq(want));
+        }
+    }
+
+    #[test]
+    fn sphere_query_matches_the_sphere_shape() {
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_ts(
+            intersect_ray_sphere(&ray, &Point::new(0.0, 0.0, 0.0), 1.0),
+            vec![4.0, 6.0],
+        );
+
+        let sphere = Object::new_sphere()
+            .scale(2.0, 2.0, 2.0)
+            .translate(1.0, 0.0, 3.0);
+        assert_ts(
+            intersect_ray_sphere(&ray, &Point::new(1.0, 0.0, 3.0), 2.0),
+            object_ts(&sphere, &ray),
+        );
+    }
+
+    #[test]
+    fn plane_query_hits_once_unless_parallel() {
+        let ray = Ray::new(Point::new(0.0, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let up = Vector::new(0.0, 1.0, 0.0);
+        assert_ts(
+            intersect_ray_plane(&ray, &Point::new(0.0, 0.0, 0.0), &up),
+            vec![1.0],
+        );
+
+        let parallel = Ray::new(Point::new(0.0, 1.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+        assert!(intersect_ray_plane(&parallel, &Point::new(0.0, 0.0, 0.0), &up).is_empty());
+    }
+
+    #[test]
+    fn box_query_matches_the_cube_shape() {
+        let ray = Ray::new(Point::new(5.0, 0.5, 0.0), Vector::new(-1.0, 0.0, 0.0));
+        assert_ts(
+            intersect_ray_box(
+                &ray,
+                &Point::new(-1.0, -1.0, -1.0),
+                &Point::new(1.0, 1.0, 1.0),
+            ),
+            vec![4.0, 6.0],
+        );
+
+        let cube = Object::new_cube()
+            .scale(2.0, 1.0, 3.0)
+            .translate(0.0, 1.0, 0.0);
+        let min = Point::new(-2.0, 0.0, -3.0);
+        let max = Point::new(2.0, 2.0, 3.0);
+        assert_ts(intersect_ray_box(&ray, &min, &max), object_ts(&cube, &ray));
+    }
+}
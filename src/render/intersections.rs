@@ -1,15 +1,21 @@
-use std::{cmp::Ordering, ops::Index, slice};
+use std::{
+    cmp::Ordering,
+    iter::Take,
+    ops::{Deref, Index},
+    slice,
+};
 
 use crate::math::{epsilon::EPSILON, point::Point, ray::Ray, vector::Vector};
 use crate::render::object::Object;
 
-use super::material::Materialable;
+use super::material::{Material, Materialable};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Intersection<'a> {
     t: f64,
     u: f64,
     v: f64,
+    face: usize, // Which face of a mesh was hit
     object: &'a Object,
 }
 
@@ -19,6 +25,7 @@ impl<'a> Intersection<'a> {
             t,
             u: 0.0,
             v: 0.0,
+            face: 0,
             object,
         }
     }
@@ -44,6 +51,15 @@ impl<'a> Intersection<'a> {
         self.v = v;
         self
     }
+
+    pub fn face(&self) -> usize {
+        self.face
+    }
+
+    pub fn with_face(mut self, face: usize) -> Self {
+        self.face = face;
+        self
+    }
 }
 
 impl<'a> std::cmp::Eq for Intersection<'a> {}
@@ -73,12 +89,14 @@ impl<'a> Ord for Intersection<'a> {
 #[derive(Debug)]
 pub struct Intersections<'a> {
     intersections: Vec<Intersection<'a>>,
+    tests: usize, // Objects tested against the ray, including group children
 }
 
 impl<'a> Intersections<'a> {
     pub fn new() -> Self {
         Self {
             intersections: Vec::<Intersection<'a>>::with_capacity(16),
+            tests: 0,
         }
     }
 
@@ -105,6 +123,40 @@ impl<'a> Intersections<'a> {
         self.sort();
     }
 
+    // Keeps only the intersections matching the predicate, still in sorted order
+    pub fn filter(&mut self, pred: impl Fn(&Intersection<'a>) -> bool) {
+        self.intersections.retain(pred);
+    }
+
+    pub fn tests(&self) -> usize {
+        self.tests
+    }
+
+    pub fn count_tests(&mut self, tests: usize) {
+        self.tests += tests;
+    }
+
+    // Merges another sorted list into this one in a single pass
+    pub fn merge(&mut self, other: Intersections<'a>) {
+        self.tests += other.tests;
+        let mine = std::mem::take(&mut self.intersections);
+        let mut merged = Vec::with_capacity(mine.len() + other.len());
+        let mut left = mine.into_iter().peekable();
+        let mut right = other.intersections.into_iter().peekable();
+
+        while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+            if r < l {
+                merged.extend(right.next());
+            } else {
+                merged.extend(left.next());
+            }
+        }
+        merged.extend(left);
+        merged.extend(right);
+
+        self.intersections = merged;
+    }
+
     pub fn get_hit(&self) -> Option<&Intersection> {
         self.iter().find(|int| int.t() > 0.0)
     }
@@ -136,6 +188,52 @@ impl<'a> Index<usize> for Intersections<'a> {
     }
 }
 
+// The t values where a ray crosses a single primitive. None is crossed more than
+// four times, so they're kept inline rather than allocated for every test.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalTs {
+    ts: [f64; 4],
+    len: usize,
+}
+
+impl LocalTs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, t: f64) {
+        self.ts[self.len] = t;
+        self.len += 1;
+    }
+
+    pub fn as_slice(&self) -> &[f64] {
+        &self.ts[..self.len]
+    }
+}
+
+impl Deref for LocalTs {
+    type Target = [f64];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl PartialEq for LocalTs {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl IntoIterator for LocalTs {
+    type Item = f64;
+    type IntoIter = Take<std::array::IntoIter<f64, 4>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.ts.into_iter().take(self.len)
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct HitComputation<'a> {
     pub t: f64,
@@ -187,6 +285,17 @@ impl<'a> HitComputation<'a> {
             }
         }
 
+        Self::from_parts(intersection, ray, n1.unwrap_or(1.0), n2.unwrap_or(1.0))
+    }
+
+    // Builds the computation for a hit on its own, treating the ray as travelling
+    // from empty space (index 1.0) into the hit object
+    pub fn from_single(intersection: &Intersection<'a>, ray: &Ray) -> Self {
+        let n2 = intersection.object.get_material().refractive_index;
+        Self::from_parts(intersection, ray, 1.0, n2)
+    }
+
+    fn from_parts(intersection: &Intersection<'a>, ray: &Ray, n1: f64, n2: f64) -> Self {
         let point = ray.position_at(intersection.t);
         let eye = -ray.direction;
         let object = &intersection.object;
@@ -218,8 +327,8 @@ impl<'a> HitComputation<'a> {
             over_point,
             under_point,
             cos_i,
-            n1: n1.unwrap_or(1.0),
-            n2: n2.unwrap_or(1.0),
+            n1,
+            n2,
         }
     }
 
@@ -242,6 +351,15 @@ impl<'a> HitComputation<'a> {
         r0 + (1.0 - r0) * (1.0 - cos).powi(5)
     }
 
+    // The material shading this hit, the object's interior material when the hit is
+    // on the inside of the surface and one is set
+    pub fn material(&self) -> Material {
+        match self.object.get_interior_material() {
+            Some(interior) if self.inside => interior,
+            _ => self.object.get_material(),
+        }
+    }
+
     pub fn n(&self) -> (f64, f64) {
         (self.n1, self.n2)
     }
@@ -249,7 +367,7 @@ impl<'a> HitComputation<'a> {
 
 #[cfg(test)]
 mod test {
-    use super::{HitComputation, Intersection, Intersections};
+    use super::{HitComputation, Intersection, Intersections, LocalTs};
     use crate::{
         math::{
             epsilon::{ApproxEq, EPSILON},
@@ -287,6 +405,21 @@ mod test {
         assert_eq!(xs[1].object(), &s);
     }
 
+    #[test]
+    fn filtering_keeps_matching_intersections_in_order() {
+        let s = Object::new_sphere();
+        let mut xs = Intersections::new().with_intersections(vec![
+            Intersection::new(5.0, &s),
+            Intersection::new(-3.0, &s),
+            Intersection::new(2.0, &s),
+            Intersection::new(-1.0, &s),
+        ]);
+        xs.filter(|int| int.t() > 0.0);
+
+        let ts: Vec<f64> = xs.iter().map(|int| int.t()).collect();
+        assert_eq!(ts, vec![2.0, 5.0]);
+    }
+
     #[test]
     fn hit_when_all_intersections_have_positive_t() {
         let s = Object::new_sphere();
@@ -471,4 +604,58 @@ mod test {
         assert_eq!(int.u(), 0.2);
         assert_eq!(int.v(), 0.4);
     }
+
+    #[test]
+    fn merging_two_sorted_intersection_lists() {
+        let s = Object::new_sphere();
+        let mut a = Intersections::new().with_intersections(
+            [1.0, 3.0, 5.0]
+                .iter()
+                .map(|t| Intersection::new(*t, &s))
+                .collect(),
+        );
+        let b = Intersections::new().with_intersections(
+            [2.0, 4.0, 6.0]
+                .iter()
+                .map(|t| Intersection::new(*t, &s))
+                .collect(),
+        );
+        a.merge(b);
+
+        let ts: Vec<f64> = a.iter().map(|i| i.t()).collect();
+        assert_eq!(ts, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    }
+
+    #[test]
+    fn hit_computation_from_a_single_intersection() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let shape = glass_sphere();
+        let i = Intersection::new(4.0, &shape);
+        let xs = Intersections::new().with_intersections(vec![i.clone()]);
+
+        let want = HitComputation::new(&xs, 0, &r);
+        let got = HitComputation::from_single(&i, &r);
+
+        assert_eq!(got.t, want.t);
+        assert_eq!(got.point, want.point);
+        assert_eq!(got.eye, want.eye);
+        assert_eq!(got.normal, want.normal);
+        assert_eq!(got.inside, want.inside);
+        assert_eq!(got.over_point, want.over_point);
+        assert_eq!(got.under_point, want.under_point);
+        assert_eq!(got.n(), want.n());
+        assert_eq!(got.n(), (1.0, 1.5));
+    }
+
+    #[test]
+    fn local_ts_hold_only_what_was_pushed() {
+        let mut ts = LocalTs::new();
+        assert!(ts.is_empty());
+
+        ts.push(4.0);
+        ts.push(6.0);
+        assert_eq!(ts.len(), 2);
+        assert_eq!(ts.as_slice(), [4.0, 6.0]);
+        assert_eq!(ts.into_iter().collect::<Vec<f64>>(), vec![4.0, 6.0]);
+    }
 }
@@ -3,18 +3,98 @@ use crate::math::point::Point;
 use crate::math::vector::Vector;
 use crate::render::material::Material;
 
-use super::lights::point_light::PointLight;
+use super::lights::{
+    area_light::AreaLight, directional_light::DirectionalLight, point_light::PointLight,
+    spot_light::SpotLight,
+};
 use super::object::Object;
+use super::world::World;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+// Diffuse and specular terms for light of the given intensity arriving along light_vector
+// (normalized, pointing at the light). Light passing through a transparent surface isn't
+// also scattered off of it, so both terms fade with transparency. Ambient, shadowing and
+// falloff are up to the caller.
+pub(crate) fn phong(
+    material: &Material,
+    effective_color: Color,
+    intensity: Color,
+    light_vector: Vector,
+    eye_vector: Vector,
+    normal_vector: Vector,
+    shininess: f64,
+) -> Color {
+    let light_dot_normal = light_vector * normal_vector;
+    if light_dot_normal < 0.0 {
+        return Color::black();
+    }
+
+    let opacity = 1.0 - material.transparency;
+    let diffuse = effective_color * material.diffuse * light_dot_normal;
+    let reflect_vector = -light_vector.reflect(&normal_vector);
+    let reflect_dot_eye = reflect_vector * eye_vector;
+    if reflect_dot_eye <= 0.0 {
+        return diffuse * opacity;
+    }
+
+    let factor = reflect_dot_eye.powf(shininess);
+    let tint = material.specular_color.unwrap_or(Color::white());
+    (diffuse + intensity * tint * material.specular * factor) * opacity
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Light {
     Point(PointLight),
+    Directional(DirectionalLight),
+    Spot(SpotLight),
+    Area(AreaLight),
 }
 
 impl Light {
-    pub fn get_position(&self) -> Point {
+    // Directional lights have no position
+    pub fn get_position(&self) -> Option<Point> {
+        match self {
+            Self::Point(p) => Some(p.position),
+            Self::Directional(_) => None,
+            Self::Spot(s) => Some(s.position),
+            Self::Area(a) => Some(a.position),
+        }
+    }
+
+    pub fn get_intensity(&self) -> Color {
+        match self {
+            Self::Point(p) => p.intensity,
+            Self::Directional(d) => d.intensity,
+            Self::Spot(s) => s.intensity,
+            Self::Area(a) => a.intensity,
+        }
+    }
+
+    // Unit vector from the point towards the light
+    pub fn direction_from(&self, point: &Point) -> Vector {
+        match self {
+            Self::Point(p) => (p.position - *point).normalize(),
+            Self::Directional(d) => -d.direction,
+            Self::Spot(s) => (s.position - *point).normalize(),
+            Self::Area(a) => (a.position - *point).normalize(),
+        }
+    }
+
+    // How far light travels to reach the point, infinite for directional lights
+    pub fn distance_from(&self, point: &Point) -> f64 {
         match self {
-            Self::Point(p) => p.position,
+            Self::Point(p) => (p.position - *point).magnitude(),
+            Self::Directional(_) => f64::INFINITY,
+            Self::Spot(s) => (s.position - *point).magnitude(),
+            Self::Area(a) => (a.position - *point).magnitude(),
+        }
+    }
+
+    pub fn in_range(&self, point: &Point) -> bool {
+        match self {
+            Self::Point(p) => p.in_range(point),
+            Self::Directional(_) => true,
+            Self::Spot(_) => true,
+            Self::Area(_) => true,
         }
     }
 
@@ -36,6 +116,87 @@ impl Light {
                 normal_vector,
                 in_shadow,
             ),
+            Self::Directional(d) => d.lighting(
+                object,
+                material,
+                point,
+                eye_vector,
+                normal_vector,
+                in_shadow,
+            ),
+            Self::Spot(s) => s.lighting(
+                object,
+                material,
+                point,
+                eye_vector,
+                normal_vector,
+                in_shadow,
+            ),
+            Self::Area(a) => a.lighting(
+                object,
+                material,
+                point,
+                eye_vector,
+                normal_vector,
+                if in_shadow { 0.0 } else { 1.0 },
+            ),
+        }
+    }
+
+    // How much of the light reaches the point, from 0.0 when fully shadowed to 1.0.
+    // Only area lights can be partly visible.
+    pub fn intensity_at(&self, point: &Point, world: &World) -> f64 {
+        match self {
+            Self::Area(a) => a.intensity_at(point, world),
+            _ => {
+                if world.is_shadowed(point, self) {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+        }
+    }
+
+    // Like lighting, but with the fraction of the light reaching the point in place of
+    // a shadow flag
+    pub fn lighting_with_intensity(
+        &self,
+        object: &Object,
+        material: &Material,
+        point: Point,
+        eye_vector: Vector,
+        normal_vector: Vector,
+        light_intensity: f64,
+    ) -> Color {
+        if let Self::Area(a) = self {
+            return a.lighting(
+                object,
+                material,
+                point,
+                eye_vector,
+                normal_vector,
+                light_intensity,
+            );
+        }
+
+        let shade = |in_shadow| {
+            self.lighting(
+                object,
+                material,
+                point,
+                eye_vector,
+                normal_vector,
+                in_shadow,
+            )
+        };
+        if light_intensity >= 1.0 {
+            shade(false)
+        } else if light_intensity <= 0.0 {
+            shade(true)
+        } else {
+            let shadowed = shade(true);
+            shadowed + (shade(false) - shadowed) * light_intensity
         }
     }
 }
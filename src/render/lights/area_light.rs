@@ -0,0 +1,268 @@
+use crate::math::point::Point;
+use crate::math::rng;
+use crate::math::tuple::Tuple;
+use crate::math::vector::Vector;
+use crate::render::{light::phong, object::Object, patterns::TexturePattern, world::World};
+use crate::{
+    draw::{canvas::Canvas, color::Color},
+    render::material::Material,
+};
+
+// A rectangular light spanned by two edges from a corner and split into a grid of cells.
+// Shading and shadows sample one point per cell, so partly hidden lights cast penumbrae.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AreaLight {
+    pub corner: Point,
+    pub uvec: Vector, // One cell along the first edge
+    pub usteps: usize,
+    pub vvec: Vector, // One cell along the second edge
+    pub vsteps: usize,
+    pub intensity: Color,
+    pub position: Point,          // Center of the light
+    pub jitter: Option<u64>, // Seed for jittering samples inside their cells, None samples centers
+    gobo: Option<TexturePattern>, // Image over the quad tinting each sample
+}
+
+impl AreaLight {
+    pub fn new(
+        corner: Point,
+        full_uvec: Vector,
+        usteps: usize,
+        full_vvec: Vector,
+        vsteps: usize,
+        intensity: Color,
+    ) -> Self {
+        let usteps = usteps.max(1);
+        let vsteps = vsteps.max(1);
+        Self {
+            corner,
+            uvec: full_uvec / usteps as f64,
+            usteps,
+            vvec: full_vvec / vsteps as f64,
+            vsteps,
+            intensity,
+            position: corner + full_uvec / 2.0 + full_vvec / 2.0,
+            jitter: None,
+            gobo: None,
+        }
+    }
+
+    pub fn with_jitter(self, seed: u64) -> Self {
+        Self {
+            jitter: Some(seed),
+            ..self
+        }
+    }
+
+    // Stretches the image over the quad, u along the first edge and v along the second,
+    // so each sample is tinted by the pixel under it. Black pixels let no light through.
+    pub fn with_gobo(self, gobo: Canvas) -> Self {
+        Self {
+            gobo: Some(TexturePattern::new(gobo)),
+            ..self
+        }
+    }
+
+    pub fn samples(&self) -> usize {
+        self.usteps * self.vsteps
+    }
+
+    // The sample point in cell (u, v). Jittered lights also key the offset on the point
+    // being shaded so neighbouring points see different samples instead of banding.
+    pub fn point_on_light(&self, u: usize, v: usize, shaded: &Point) -> Point {
+        let (du, dv) = self.cell_offset(u, v, shaded);
+        self.corner + self.uvec * (u as f64 + du) + self.vvec * (v as f64 + dv)
+    }
+
+    // Where the sample sits inside cell (u, v), each of its coordinates from 0 to 1
+    fn cell_offset(&self, u: usize, v: usize, shaded: &Point) -> (f64, f64) {
+        match self.jitter {
+            Some(seed) => {
+                let key = [
+                    seed,
+                    u as u64,
+                    v as u64,
+                    shaded.x().to_bits(),
+                    shaded.y().to_bits(),
+                    shaded.z().to_bits(),
+                ];
+                (
+                    rng::unit_f64(&[rng::hash(&key), 0]),
+                    rng::unit_f64(&[rng::hash(&key), 1]),
+                )
+            }
+            None => (0.5, 0.5),
+        }
+    }
+
+    fn sample_points(&self, shaded: &Point) -> impl Iterator<Item = Point> + '_ {
+        let shaded = *shaded;
+        (0..self.vsteps)
+            .flat_map(move |v| (0..self.usteps).map(move |u| self.point_on_light(u, v, &shaded)))
+    }
+
+    // Each sample point with the gobo color over it, white without a gobo
+    fn tinted_sample_points(&self, shaded: &Point) -> impl Iterator<Item = (Point, Color)> + '_ {
+        let shaded = *shaded;
+        (0..self.vsteps).flat_map(move |v| {
+            (0..self.usteps).map(move |u| {
+                let tint = match &self.gobo {
+                    Some(gobo) => {
+                        let (du, dv) = self.cell_offset(u, v, &shaded);
+                        gobo.pattern_at_uv(
+                            (u as f64 + du) / self.usteps as f64,
+                            (v as f64 + dv) / self.vsteps as f64,
+                        )
+                    }
+                    None => Color::white(),
+                };
+                (self.point_on_light(u, v, &shaded), tint)
+            })
+        })
+    }
+
+    // Fraction of the light's samples visible from the point, 0.0 to 1.0
+    pub fn intensity_at(&self, point: &Point, world: &World) -> f64 {
+        let visible = self
+            .sample_points(point)
+            .filter(|sample| !world.is_shadowed_from(point, sample))
+            .count();
+        visible as f64 / self.samples() as f64
+    }
+
+    // Averages the direct lighting over every sample, scaled by how much of the light
+    // reaches the point
+    pub fn lighting(
+        &self,
+        object: &Object,
+        material: &Material,
+        point: Point,
+        eye_vector: Vector,
+        normal_vector: Vector,
+        light_intensity: f64,
+    ) -> Color {
+        let effective_color = material.pattern.pattern_at_object(object, &point) * self.intensity;
+        let ambient = effective_color * material.ambient;
+        if light_intensity <= 0.0 {
+            return ambient;
+        }
+
+        let direct =
+            self.tinted_sample_points(&point)
+                .fold(Color::black(), |acc, (sample, tint)| {
+                    acc + phong(
+                        material,
+                        effective_color,
+                        self.intensity,
+                        (sample - point).normalize(),
+                        eye_vector,
+                        normal_vector,
+                        material.shininess,
+                    ) * tint
+                });
+
+        ambient + direct * (light_intensity / self.samples() as f64)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::math::transformation::Transformable;
+    use crate::render::material::Materialable;
+
+    fn test_light() -> AreaLight {
+        AreaLight::new(
+            Point::new(-1.0, 10.0, -1.0),
+            Vector::new(2.0, 0.0, 0.0),
+            4,
+            Vector::new(0.0, 0.0, 2.0),
+            2,
+            Color::white(),
+        )
+    }
+
+    #[test]
+    fn creating_an_area_light() {
+        let light = test_light();
+        assert_eq!(light.uvec, Vector::new(0.5, 0.0, 0.0));
+        assert_eq!(light.vvec, Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(light.samples(), 8);
+        assert_eq!(light.position, Point::new(0.0, 10.0, 0.0));
+    }
+
+    #[test]
+    fn finding_single_points_on_an_area_light() {
+        let light = test_light();
+        let shaded = Point::new(0.0, 0.0, 0.0);
+        assert_eq!(
+            light.point_on_light(0, 0, &shaded),
+            Point::new(-0.75, 10.0, -0.5)
+        );
+        assert_eq!(
+            light.point_on_light(3, 1, &shaded),
+            Point::new(0.75, 10.0, 0.5)
+        );
+
+        // Jittered samples stay inside their cell
+        let jittered = light.with_jitter(7).point_on_light(3, 1, &shaded);
+        assert!(jittered.x() >= 0.5 && jittered.x() <= 1.0);
+        assert!(jittered.z() >= 0.0 && jittered.z() <= 1.0);
+    }
+
+    #[test]
+    fn a_partly_occluded_point_gets_a_fraction_of_the_light() {
+        let light = test_light();
+        let point = Point::new(0.0, 0.0, 0.0);
+
+        let mut open = World::new();
+        open.add_object(Object::new_sphere().translate(0.0, -5.0, 0.0));
+        assert_eq!(light.intensity_at(&point, &open), 1.0);
+
+        // A slab over the negative x half hides half of the samples
+        let mut world = World::new();
+        world.add_object(
+            Object::new_cube()
+                .scale(5.0, 0.1, 5.0)
+                .translate(-5.0, 5.0, 0.0),
+        );
+        assert_eq!(light.intensity_at(&point, &world), 0.5);
+    }
+
+    #[test]
+    fn a_gobo_splits_the_light_it_casts() {
+        // A panel just over the floor, dark over its negative x half
+        let panel = AreaLight::new(
+            Point::new(-1.0, 0.25, -1.0),
+            Vector::new(2.0, 0.0, 0.0),
+            8,
+            Vector::new(0.0, 0.0, 2.0),
+            8,
+            Color::white(),
+        );
+        let mut gobo = Canvas::new(2, 1);
+        gobo.set_pixel((1, 0), &Color::white());
+        let floor = Object::new_plane();
+        let material = floor.get_material();
+        let ambient = material.pattern.pattern_at(&Point::new(0.0, 0.0, 0.0)) * material.ambient;
+        let eye = Vector::new(0.0, 1.0, 0.0);
+        let normal = Vector::new(0.0, 1.0, 0.0);
+        // Red channel of the light that arrives on top of the ambient term
+        let direct = |light: &AreaLight, x: f64| {
+            let color =
+                light.lighting(&floor, &material, Point::new(x, 0.0, 0.0), eye, normal, 1.0);
+            (color - ambient).as_tuple().0
+        };
+
+        assert!((direct(&panel, -0.9) - direct(&panel, 0.9)).abs() < 1e-9);
+
+        let split = panel.clone().with_gobo(gobo);
+        assert!(direct(&split, 0.9) > direct(&split, -0.9) * 2.0);
+        assert!(direct(&split, 0.9) < direct(&panel, 0.9));
+
+        // A fully black gobo leaves only the ambient term
+        let blocked = panel.with_gobo(Canvas::new(2, 1));
+        assert_eq!(direct(&blocked, 0.9), 0.0);
+    }
+}
@@ -0,0 +1,84 @@
+use crate::math::point::Point;
+use crate::math::vector::Vector;
+use crate::render::{light::phong, object::Object};
+use crate::{draw::color::Color, render::material::Material};
+
+// Light arriving along the same direction everywhere, like sunlight. It has no position
+// so there is no falloff and shadow rays run out to infinity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DirectionalLight {
+    pub direction: Vector, // The way the light travels, normalized
+    pub intensity: Color,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Vector, intensity: Color) -> Self {
+        Self {
+            direction: direction.normalize(),
+            intensity,
+        }
+    }
+
+    pub fn lighting(
+        &self,
+        object: &Object,
+        material: &Material,
+        point: Point,
+        eye_vector: Vector,
+        normal_vector: Vector,
+        in_shadow: bool,
+    ) -> Color {
+        let effective_color = material.pattern.pattern_at_object(object, &point) * self.intensity;
+        let ambient = effective_color * material.ambient;
+        if in_shadow {
+            return ambient;
+        }
+
+        let direct = phong(
+            material,
+            effective_color,
+            self.intensity,
+            -self.direction,
+            eye_vector,
+            normal_vector,
+            material.shininess,
+        );
+
+        ambient + direct
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::math::tuple::Tuple;
+
+    #[test]
+    fn directional_light_normalizes_its_direction() {
+        let light = DirectionalLight::new(Vector::new(0.0, -2.0, 0.0), Color::white());
+        assert_eq!(light.direction, Vector::new(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn surface_facing_a_directional_light_is_fully_lit_at_any_distance() {
+        let object = Object::new_sphere();
+        let material = Material::default();
+        let light = DirectionalLight::new(Vector::new(0.0, 0.0, 1.0), Color::white());
+        let eye = Vector::new(0.0, 0.0, -1.0);
+        let normal = Vector::new(0.0, 0.0, -1.0);
+
+        let want = Color::new(1.9, 1.9, 1.9);
+        for z in [0.0, 100.0, 1.0e6] {
+            let got = light.lighting(
+                &object,
+                &material,
+                Point::new(0.0, 0.0, z),
+                eye,
+                normal,
+                false,
+            );
+            assert_eq!(got, want);
+        }
+    }
+}
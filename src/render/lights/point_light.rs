@@ -1,12 +1,17 @@
 use crate::math::point::Point;
 use crate::math::vector::Vector;
-use crate::render::object::Object;
+use crate::render::{light::phong, object::Object};
 use crate::{draw::color::Color, render::material::Material};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct PointLight {
     pub position: Point,
     pub intensity: Color,
+    pub radius: f64,        // Size of the emitter, 0.0 for a true point light
+    pub range: Option<f64>, // Distance past which the light has no effect
+    pub constant: f64,      // Attenuation coefficients, direct light is divided by
+    pub linear: f64,        // constant + linear * d + quadratic * d^2
+    pub quadratic: f64,
 }
 
 impl PointLight {
@@ -14,9 +19,56 @@ impl PointLight {
         Self {
             position,
             intensity,
+            radius: 0.0,
+            range: None,
+            constant: 1.0,
+            linear: 0.0,
+            quadratic: 0.0,
         }
     }
 
+    pub fn with_radius(self, radius: f64) -> Self {
+        Self { radius, ..self }
+    }
+
+    pub fn with_range(self, range: f64) -> Self {
+        Self {
+            range: Some(range),
+            ..self
+        }
+    }
+
+    pub fn with_attenuation(self, constant: f64, linear: f64, quadratic: f64) -> Self {
+        Self {
+            constant,
+            linear,
+            quadratic,
+            ..self
+        }
+    }
+
+    pub fn attenuation(&self, distance: f64) -> f64 {
+        self.constant + self.linear * distance + self.quadratic * distance * distance
+    }
+
+    pub fn in_range(&self, point: &Point) -> bool {
+        match self.range {
+            Some(range) => (self.position - *point).magnitude() <= range,
+            None => true,
+        }
+    }
+
+    // A larger light covers more of the reflected directions, so widen the phong lobe
+    // by the light's angular size as seen from the point
+    fn effective_shininess(&self, shininess: f64, distance: f64) -> f64 {
+        if self.radius <= 0.0 {
+            return shininess;
+        }
+        let roughness = (2.0 / (shininess + 2.0)).sqrt();
+        let widened = (roughness + self.radius / (2.0 * distance)).min(1.0);
+        2.0 / widened.powi(2) - 2.0
+    }
+
     pub fn lighting(
         &self,
         object: &Object,
@@ -27,24 +79,25 @@ impl PointLight {
         in_shadow: bool,
     ) -> Color {
         let effective_color = material.pattern.pattern_at_object(object, &point) * self.intensity;
-        let light_vector = (self.position - point).normalize();
+        let to_light = self.position - point;
         let ambient = effective_color * material.ambient;
-        let light_dot_normal = light_vector * normal_vector;
-
-        let mut specular = Color::new(0.0, 0.0, 0.0);
-        let mut diffuse = Color::new(0.0, 0.0, 0.0);
-
-        if light_dot_normal >= 0.0 && !in_shadow {
-            diffuse = effective_color * material.diffuse * light_dot_normal;
-            let reflect_vector = -light_vector.reflect(&normal_vector);
-            let reflect_dot_eye = reflect_vector * eye_vector;
-            if reflect_dot_eye > 0.0 {
-                let factor = reflect_dot_eye.powf(material.shininess);
-                specular = self.intensity * material.specular * factor;
-            }
+        if in_shadow {
+            return ambient;
         }
 
-        ambient + diffuse + specular
+        let distance = to_light.magnitude();
+        let shininess = self.effective_shininess(material.shininess, distance);
+        let direct = phong(
+            material,
+            effective_color,
+            self.intensity,
+            to_light.normalize(),
+            eye_vector,
+            normal_vector,
+            shininess,
+        );
+
+        ambient + direct * (1.0 / self.attenuation(distance))
     }
 }
 
@@ -66,4 +119,61 @@ mod test {
         assert_eq!(light.intensity, want_int);
         assert_eq!(light.position, want_pos);
     }
+
+    #[test]
+    fn larger_lights_broaden_the_specular_highlight() {
+        let object = Object::new_sphere();
+        let material = Material::default()
+            .with_ambient(0.0)
+            .with_diffuse(0.0)
+            .with_specular(1.0);
+        let point = Point::new(0.0, 0.0, 0.0);
+        let normal = Vector::new(0.0, 0.0, -1.0);
+        let position = Point::new(0.0, 0.0, -10.0);
+        let specular = |light: PointLight, eye: Vector| {
+            light
+                .lighting(&object, &material, point, eye, normal, false)
+                .as_tuple()
+                .0
+        };
+
+        let small = PointLight::new(position, Color::white());
+        let large = small.with_radius(2.0);
+
+        // Straight down the reflection both peak at full intensity
+        let peak = Vector::new(0.0, 0.0, -1.0);
+        assert!(specular(small, peak) > 0.99);
+        assert!(specular(large, peak) > 0.99);
+
+        // Off to the side the larger light still contributes noticeably more
+        let off_peak = Vector::new(0.0, 0.3, -1.0).normalize();
+        assert!(specular(large, off_peak) > 2.0 * specular(small, off_peak));
+    }
+
+    #[test]
+    fn quadratic_attenuation_falls_off_with_the_square_of_distance() {
+        let object = Object::new_sphere();
+        let material = Material::default().with_ambient(0.0).with_specular(0.0);
+        let eye = Vector::new(0.0, 0.0, -1.0);
+        let normal = Vector::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::white())
+            .with_attenuation(0.0, 0.0, 1.0);
+        let shade = |z: f64| {
+            light
+                .lighting(
+                    &object,
+                    &material,
+                    Point::new(0.0, 0.0, z),
+                    eye,
+                    normal,
+                    false,
+                )
+                .as_tuple()
+                .0
+        };
+
+        let near = shade(0.0);
+        let far = shade(10.0);
+        assert!((far - near / 4.0).abs() < 1e-9);
+    }
 }
@@ -0,0 +1,219 @@
+use crate::math::point::Point;
+use crate::math::tuple::Tuple;
+use crate::math::vector::Vector;
+use crate::render::{light::phong, object::Object, patterns::TexturePattern};
+use crate::{
+    draw::{canvas::Canvas, color::Color},
+    render::material::Material,
+};
+
+// A point light limited to a cone. Inside the inner angle it shines at full strength,
+// fading smoothly to nothing at the outer angle. Angles are in radians from the axis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpotLight {
+    pub position: Point,
+    pub direction: Vector, // Axis of the cone, normalized
+    pub intensity: Color,
+    pub inner_angle: f64,
+    pub outer_angle: f64,
+    gobo: Option<TexturePattern>, // Image projected across the cone
+}
+
+impl SpotLight {
+    pub fn new(
+        position: Point,
+        direction: Vector,
+        intensity: Color,
+        inner_angle: f64,
+        outer_angle: f64,
+    ) -> Self {
+        Self {
+            position,
+            direction: direction.normalize(),
+            intensity,
+            inner_angle,
+            outer_angle,
+            gobo: None,
+        }
+    }
+
+    // Projects the image across the outer cone, so its edges meet the cone's rim. u runs
+    // to the right of the axis and v up, with up taken from +y unless the light points
+    // nearly straight up or down.
+    pub fn with_gobo(self, gobo: Canvas) -> Self {
+        Self {
+            gobo: Some(TexturePattern::new(gobo)),
+            ..self
+        }
+    }
+
+    // Where the ray from the light to the point crosses the gobo, (0.5, 0.5) on the axis
+    pub fn gobo_uv(&self, point: &Point) -> (f64, f64) {
+        let hint = if self.direction.y().abs() < 0.9 {
+            Vector::new(0.0, 1.0, 0.0)
+        } else {
+            Vector::new(1.0, 0.0, 0.0)
+        };
+        let right = hint.cross(&self.direction).normalize();
+        let up = self.direction.cross(&right);
+
+        let to_point = *point - self.position;
+        let depth = to_point * self.direction;
+        let radius = self.outer_angle.tan();
+        let across = |axis: Vector| ((to_point * axis) / (depth * radius) + 1.0) / 2.0;
+        (across(right), across(up))
+    }
+
+    // Tint the gobo puts on light reaching the point, white without one
+    pub fn gobo_at(&self, point: &Point) -> Color {
+        match &self.gobo {
+            Some(gobo) => {
+                let (u, v) = self.gobo_uv(point);
+                gobo.pattern_at_uv(u, v)
+            }
+            None => Color::white(),
+        }
+    }
+
+    // Share of the intensity reaching the point, 1.0 inside the inner cone and 0.0
+    // past the outer one
+    pub fn falloff(&self, point: &Point) -> f64 {
+        let cos_angle = (*point - self.position).normalize() * self.direction;
+        smoothstep(self.outer_angle.cos(), self.inner_angle.cos(), cos_angle)
+    }
+
+    pub fn lighting(
+        &self,
+        object: &Object,
+        material: &Material,
+        point: Point,
+        eye_vector: Vector,
+        normal_vector: Vector,
+        in_shadow: bool,
+    ) -> Color {
+        let effective_color = material.pattern.pattern_at_object(object, &point) * self.intensity;
+        let ambient = effective_color * material.ambient;
+        let falloff = self.falloff(&point);
+        if in_shadow || falloff <= 0.0 {
+            return ambient;
+        }
+
+        let direct = phong(
+            material,
+            effective_color,
+            self.intensity,
+            (self.position - point).normalize(),
+            eye_vector,
+            normal_vector,
+            material.shininess,
+        );
+
+        ambient + direct * self.gobo_at(&point) * falloff
+    }
+}
+
+fn smoothstep(edge0: f64, edge1: f64, x: f64) -> f64 {
+    if edge0 >= edge1 {
+        return if x >= edge1 { 1.0 } else { 0.0 };
+    }
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    use crate::math::{epsilon::ApproxEq, tuple::Tuple};
+
+    fn test_light() -> SpotLight {
+        SpotLight::new(
+            Point::new(0.0, 0.0, -10.0),
+            Vector::new(0.0, 0.0, 1.0),
+            Color::white(),
+            PI / 12.0,
+            PI / 6.0,
+        )
+    }
+
+    // A point on the z = 0 plane seen from the light at the given angle off its axis
+    fn point_at_angle(angle: f64) -> Point {
+        Point::new(10.0 * angle.tan(), 0.0, 0.0)
+    }
+
+    #[test]
+    fn spot_light_falloff_across_its_cones() {
+        let light = test_light();
+        assert_eq!(light.falloff(&Point::new(0.0, 0.0, 0.0)), 1.0);
+        assert_eq!(light.falloff(&point_at_angle(PI / 15.0)), 1.0);
+        assert_eq!(light.falloff(&point_at_angle(PI / 5.0)), 0.0);
+
+        let mid = light.falloff(&point_at_angle(PI / 8.0));
+        assert!(mid > 0.0 && mid < 1.0);
+    }
+
+    #[test]
+    fn lighting_with_a_spot_light() {
+        let object = Object::new_sphere();
+        let material = Material::default();
+        let light = test_light();
+        let eye = Vector::new(0.0, 0.0, -1.0);
+        let normal = Vector::new(0.0, 0.0, -1.0);
+        let shade = |point: Point| light.lighting(&object, &material, point, eye, normal, false);
+
+        // On axis it matches a plain point light head on
+        assert_eq!(shade(Point::new(0.0, 0.0, 0.0)), Color::new(1.9, 1.9, 1.9));
+
+        // Past the outer cone only the ambient term is left
+        assert_eq!(shade(point_at_angle(PI / 5.0)), Color::new(0.1, 0.1, 0.1));
+
+        // Between the cones the direct light is partly faded
+        let (r, _, _) = shade(point_at_angle(PI / 8.0)).as_tuple();
+        let (full, _, _) = SpotLight {
+            inner_angle: PI / 2.0,
+            outer_angle: PI / 2.0,
+            ..light.clone()
+        }
+        .lighting(
+            &object,
+            &material,
+            point_at_angle(PI / 8.0),
+            eye,
+            normal,
+            false,
+        )
+        .as_tuple();
+        assert!(r > 0.1 && r < full && !r.approx_eq(full));
+    }
+
+    #[test]
+    fn a_gobo_is_projected_across_the_cone() {
+        // Black on the left column, red top right and white bottom right
+        let mut gobo = Canvas::new(2, 2);
+        gobo.set_pixel((1, 0), &Color::red());
+        gobo.set_pixel((1, 1), &Color::white());
+        let light = test_light().with_gobo(gobo);
+
+        // Halfway out to the rim, up and to the right
+        let edge = 10.0 * (PI / 6.0).tan();
+        let point = Point::new(edge / 2.0, edge / 2.0, 0.0);
+        let (u, v) = light.gobo_uv(&point);
+        assert!(u.approx_eq(0.75) && v.approx_eq(0.75));
+        assert_eq!(light.gobo_at(&point), Color::red());
+        assert_eq!(light.gobo_uv(&Point::new(0.0, 0.0, 0.0)), (0.5, 0.5));
+
+        // Inside the inner cone but under a black texel only the ambient term is left
+        let object = Object::new_sphere();
+        let material = Material::default();
+        let eye = Vector::new(0.0, 0.0, -1.0);
+        let normal = Vector::new(0.0, 0.0, -1.0);
+        let dark = Point::new(-2.0, 0.0, 0.0);
+        assert_eq!(light.falloff(&dark), 1.0);
+        assert_eq!(
+            light.lighting(&object, &material, dark, eye, normal, false),
+            Color::new(0.1, 0.1, 0.1)
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use crate::draw::color::Color;
+
 use super::pattern::Pattern;
 
 pub const REFRACTION_VACUUM: f64 = 1.0;
@@ -6,7 +8,7 @@ pub const REFRACTION_WATER: f64 = 1.333;
 pub const REFRACTION_GLASS: f64 = 1.52;
 pub const REFRACTION_DIAMOND: f64 = 2.417;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Material {
     pub ambient: f64,
     pub diffuse: f64,
@@ -16,6 +18,10 @@ pub struct Material {
     pub transparency: f64,
     pub refractive_index: f64,
     pub pattern: Pattern,
+    pub specular_color: Option<Color>, // Tints highlights, None leaves them the light's color
+    pub emission: Color,               // Light the surface gives off, even in shadow
+    pub absorption: Color,             // Share of each channel absorbed per unit travelled inside
+    pub fresnel: bool,                 // Reflect by the Schlick term instead of reflective
 }
 
 impl Material {
@@ -38,6 +44,10 @@ impl Material {
             reflective,
             transparency,
             refractive_index,
+            specular_color: None,
+            emission: Color::black(),
+            absorption: Color::black(),
+            fresnel: false,
         }
     }
 
@@ -78,6 +88,35 @@ impl Material {
             ..self
         }
     }
+
+    pub fn with_specular_color(self, specular_color: Color) -> Self {
+        Self {
+            specular_color: Some(specular_color),
+            ..self
+        }
+    }
+
+    pub fn with_emission(self, emission: Color) -> Self {
+        Self { emission, ..self }
+    }
+
+    pub fn with_absorption(self, absorption: Color) -> Self {
+        Self { absorption, ..self }
+    }
+
+    pub fn with_fresnel(self, fresnel: bool) -> Self {
+        Self { fresnel, ..self }
+    }
+
+    // Beer's law: the share of light left after travelling distance units inside
+    pub fn transmittance(&self, distance: f64) -> Color {
+        let (r, g, b) = self.absorption.as_tuple();
+        Color::new(
+            (-r * distance).exp(),
+            (-g * distance).exp(),
+            (-b * distance).exp(),
+        )
+    }
 }
 
 impl Default for Material {
@@ -91,6 +130,10 @@ impl Default for Material {
             transparency: 0.0,
             refractive_index: 1.0,
             pattern: Pattern::default(),
+            specular_color: None,
+            emission: Color::black(),
+            absorption: Color::black(),
+            fresnel: false,
         }
     }
 }
@@ -170,13 +213,52 @@ pub trait Materialable {
         mat.refractive_index = refractive_index;
         self.with_material(mat)
     }
+
+    fn with_specular_color(self, specular_color: Color) -> Self
+    where
+        Self: Sized,
+    {
+        let mut mat = self.get_material();
+        mat.specular_color = Some(specular_color);
+        self.with_material(mat)
+    }
+
+    fn with_emission(self, emission: Color) -> Self
+    where
+        Self: Sized,
+    {
+        let mut mat = self.get_material();
+        mat.emission = emission;
+        self.with_material(mat)
+    }
+
+    fn with_absorption(self, absorption: Color) -> Self
+    where
+        Self: Sized,
+    {
+        let mut mat = self.get_material();
+        mat.absorption = absorption;
+        self.with_material(mat)
+    }
+
+    fn with_fresnel(self, fresnel: bool) -> Self
+    where
+        Self: Sized,
+    {
+        let mut mat = self.get_material();
+        mat.fresnel = fresnel;
+        self.with_material(mat)
+    }
 }
 
 #[cfg(test)]
 mod test {
     use crate::{
         draw::color::Color,
-        math::{epsilon::ApproxEq, point::Point, tuple::Tuple, vector::Vector},
+        math::{
+            epsilon::ApproxEq, point::Point, transformation::Transformable, tuple::Tuple,
+            vector::Vector,
+        },
         render::{light::Light, lights::point_light::PointLight, object::Object, pattern::Pattern},
     };
 
@@ -283,6 +365,47 @@ mod test {
         assert_eq!(got, want);
     }
 
+    #[test]
+    fn transmittance_falls_off_with_distance() {
+        let m = Material::default().with_absorption(Color::new(0.5, 0.0, 1.0));
+        assert_eq!(m.transmittance(0.0), Color::white());
+        assert_eq!(
+            m.transmittance(2.0),
+            Color::new((-1.0_f64).exp(), 1.0, (-2.0_f64).exp())
+        );
+    }
+
+    #[test]
+    fn fully_transparent_surface_has_no_diffuse_or_specular() {
+        let obj = Object::new_test_shape();
+        let m = Material {
+            transparency: 1.0,
+            ..Material::default()
+        };
+        let pos = Point::new(0.0, 0.0, 0.0);
+        let eye_vector = Vector::new(0.0, 0.0, -1.0);
+        let normal_vector = Vector::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let got = light.lighting(&obj, &m, pos, eye_vector, normal_vector, false);
+        let want = Color::new(0.1, 0.1, 0.1);
+        assert_eq!(got, want);
+    }
+
+    #[test]
+    fn specular_color_tints_the_highlight() {
+        let obj = Object::new_test_shape();
+        let m = Material::default()
+            .with_ambient(0.0)
+            .with_diffuse(0.0)
+            .with_specular_color(Color::red());
+        let pos = Point::new(0.0, 0.0, 0.0);
+        let eye_vector = Vector::new(0.0, 0.0, -1.0);
+        let normal_vector = Vector::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::white());
+        let got = light.lighting(&obj, &m, pos, eye_vector, normal_vector, false);
+        assert_eq!(got, Color::new(0.9, 0.0, 0.0));
+    }
+
     #[test]
     fn lighting_with_pattern_applied() {
         let obj = Object::new_test_shape();
@@ -322,6 +445,33 @@ mod test {
         assert_eq!(c2, Color::black());
     }
 
+    #[test]
+    fn lighting_follows_the_object_transform_of_a_pattern() {
+        let obj = Object::new_sphere().scale(2.0, 2.0, 2.0);
+        let material = Material::new(
+            Pattern::new_stripe(Color::white(), Color::black()),
+            1.0,
+            0.0,
+            0.0,
+            200.0,
+            0.0,
+            0.0,
+            1.0,
+        );
+        let light = Light::Point(PointLight::new(Point::new(0.0, 0.0, -10.0), Color::white()));
+
+        // x = 1.5 would be a black stripe if the sphere's scale were ignored
+        let got = light.lighting(
+            &obj,
+            &material,
+            Point::new(1.5, 0.0, 0.0),
+            Vector::new(0.0, 0.0, -1.0),
+            Vector::new(0.0, 0.0, -1.0),
+            false,
+        );
+        assert_eq!(got, Color::white());
+    }
+
     #[test]
     fn reflectivity_for_default_material() {
         let m = Material::default();
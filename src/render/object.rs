@@ -1,26 +1,89 @@
+use std::{collections::HashMap, f64::consts::PI, sync::OnceLock};
+
 use crate::{
-    draw::io::obj::ObjFileParser,
-    math::{matrix::Matrix, point::Point, ray::Ray, transformation::Transformable, vector::Vector},
+    draw::{color::Color, io::obj::ObjFileParser},
+    error::Error,
+    math::{
+        epsilon::EPSILON, matrix::Matrix, point::Point, ray::Ray, transformation::Transformable,
+        tuple::Tuple, vector::Vector,
+    },
     render::{
+        bounding_box::BoundingBox,
         intersections::Intersections,
         material::{Material, Materialable},
+        pattern::Pattern,
         shape::Shape,
         shapes::{
-            cone::Cone, cube::Cube, cylinder::Cylinder, group::GroupTree, plane::Plane,
-            sphere::Sphere, test_shape::TestShape, triangle::Triangle,
+            cone::Cone,
+            csg::{Csg, CsgOp},
+            cube::Cube,
+            cylinder::Cylinder,
+            disk::Disk,
+            group::GroupTree,
+            indexed_mesh::IndexedMesh,
+            plane::Plane,
+            sphere::Sphere,
+            test_shape::TestShape,
+            triangle::Triangle,
         },
     },
 };
 
 use super::{intersections::Intersection, shapes::smooth_triangle::SmoothTriangle};
 
+// World space vertex positions snapped to EPSILON so shared vertices compare equal
+type VertexKey = (i64, i64, i64);
+
+fn vertex_key(p: &Point) -> VertexKey {
+    (
+        (p.x() / EPSILON).round() as i64,
+        (p.y() / EPSILON).round() as i64,
+        (p.z() / EPSILON).round() as i64,
+    )
+}
+
+// A cut through an object, given in object space. Everything on the side the normal
+// points towards is discarded
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClipPlane {
+    pub point: Point,
+    pub normal: Vector,
+}
+
+impl ClipPlane {
+    pub fn new(point: Point, normal: Vector) -> Self {
+        Self { point, normal }
+    }
+
+    fn clips(&self, point: &Point) -> bool {
+        (*point - self.point) * self.normal > 0.0
+    }
+}
+
+// Inverse and inverse transpose of an object's transform, filled in the first time
+// they're needed. Derived from the transform, so they never affect equality.
+#[derive(Debug, Clone, Default)]
+struct CachedInverse(OnceLock<(Matrix, Matrix)>);
+
+impl PartialEq for CachedInverse {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+thread_local! {
+    static INVERSIONS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Object {
     shape: Shape,
     material: Material,
+    interior_material: Option<Material>,
+    clip_planes: Vec<ClipPlane>,
     transformation: Matrix,
-    inv_transformation: Matrix,
-    inv_transpose_transformation: Matrix,
+    inverses: CachedInverse,
 }
 
 impl Object {
@@ -28,9 +91,10 @@ impl Object {
         Object {
             shape: Shape::Sphere(Sphere::new()),
             material: Material::default(),
+            interior_material: None,
+            clip_planes: vec![],
             transformation: Matrix::identity(),
-            inv_transformation: Matrix::identity(),
-            inv_transpose_transformation: Matrix::identity(),
+            inverses: CachedInverse::default(),
         }
     }
 
@@ -38,9 +102,10 @@ impl Object {
         Object {
             shape: Shape::Plane(Plane::new()),
             material: Material::default(),
+            interior_material: None,
+            clip_planes: vec![],
             transformation: Matrix::identity(),
-            inv_transformation: Matrix::identity(),
-            inv_transpose_transformation: Matrix::identity(),
+            inverses: CachedInverse::default(),
         }
     }
 
@@ -48,9 +113,10 @@ impl Object {
         Object {
             shape: Shape::Cube(Cube::new()),
             material: Material::default(),
+            interior_material: None,
+            clip_planes: vec![],
             transformation: Matrix::identity(),
-            inv_transformation: Matrix::identity(),
-            inv_transpose_transformation: Matrix::identity(),
+            inverses: CachedInverse::default(),
         }
     }
 
@@ -58,9 +124,10 @@ impl Object {
         Object {
             shape: Shape::Cone(Cone::new().with_closed(closed).with_max(max).with_min(min)),
             material: Material::default(),
+            interior_material: None,
+            clip_planes: vec![],
             transformation: Matrix::identity(),
-            inv_transformation: Matrix::identity(),
-            inv_transpose_transformation: Matrix::identity(),
+            inverses: CachedInverse::default(),
         }
     }
 
@@ -73,9 +140,21 @@ impl Object {
                     .with_min(min),
             ),
             material: Material::default(),
+            interior_material: None,
+            clip_planes: vec![],
+            transformation: Matrix::identity(),
+            inverses: CachedInverse::default(),
+        }
+    }
+
+    pub fn new_disk(inner_radius: f64, outer_radius: f64) -> Self {
+        Object {
+            shape: Shape::Disk(Disk::new(inner_radius, outer_radius)),
+            material: Material::default(),
+            interior_material: None,
+            clip_planes: vec![],
             transformation: Matrix::identity(),
-            inv_transformation: Matrix::identity(),
-            inv_transpose_transformation: Matrix::identity(),
+            inverses: CachedInverse::default(),
         }
     }
 
@@ -83,9 +162,10 @@ impl Object {
         Object {
             shape: Shape::Triangle(Triangle::new(p1, p2, p3)),
             material: Material::default(),
+            interior_material: None,
+            clip_planes: vec![],
             transformation: Matrix::identity(),
-            inv_transformation: Matrix::identity(),
-            inv_transpose_transformation: Matrix::identity(),
+            inverses: CachedInverse::default(),
         }
     }
 
@@ -100,14 +180,26 @@ impl Object {
         Object {
             shape: Shape::SmoothTriangle(SmoothTriangle::new(p1, p2, p3, n1, n2, n3)),
             material: Material::default(),
+            interior_material: None,
+            clip_planes: vec![],
+            transformation: Matrix::identity(),
+            inverses: CachedInverse::default(),
+        }
+    }
+
+    pub fn new_mesh(mesh: IndexedMesh) -> Self {
+        Object {
+            shape: Shape::IndexedMesh(mesh),
+            material: Material::default(),
+            interior_material: None,
+            clip_planes: vec![],
             transformation: Matrix::identity(),
-            inv_transformation: Matrix::identity(),
-            inv_transpose_transformation: Matrix::identity(),
+            inverses: CachedInverse::default(),
         }
     }
 
-    pub fn new_model(path: &str) -> Object {
-        ObjFileParser::new_file(path).build()
+    pub fn new_model(path: &str) -> Result<Object, Error> {
+        ObjFileParser::new_file(path)?.try_build()
     }
 
     pub fn new_group(children: Vec<Object>) -> Self {
@@ -130,13 +222,61 @@ impl Object {
         object
     }
 
+    // Combines two solids into one, e.g. a Difference carves right out of left
+    pub fn new_csg(op: CsgOp, left: Object, right: Object) -> Self {
+        Object {
+            shape: Shape::Csg(Csg::new(op, left, right)),
+            material: Material::default(),
+            interior_material: None,
+            clip_planes: vec![],
+            transformation: Matrix::identity(),
+            inverses: CachedInverse::default(),
+        }
+    }
+
+    // Three thin cylinders along the x (red), y (green) and z (blue) axes. They only
+    // emit their color, so they read the same from every angle and in shadow.
+    pub fn axis_gizmo(length: f64) -> Self {
+        let radius = length * 0.02;
+        let axis = |color: Color| {
+            Object::new_cylinder(0.0, 1.0, true)
+                .with_pattern(Pattern::new_solid(color))
+                .with_ambient(0.0)
+                .with_diffuse(0.0)
+                .with_specular(0.0)
+                .with_emission(color)
+                .scale(radius, length, radius)
+        };
+
+        Object::new_group(vec![
+            axis(Color::red()).rotate_z(-PI / 2.0),
+            axis(Color::green()),
+            axis(Color::blue()).rotate_x(PI / 2.0),
+        ])
+    }
+
+    // A thin slab covering -extent..extent on x and z with its top at y = 0,
+    // checkered every `spacing` units
+    pub fn ground_grid(extent: f64, spacing: f64) -> Self {
+        let thickness = 0.01;
+        let tile = spacing / extent;
+        Object::new_cube()
+            .with_pattern(
+                Pattern::new_checker(Color::new(0.9, 0.9, 0.9), Color::new(0.4, 0.4, 0.4))
+                    .scale(tile, 2.0, tile),
+            )
+            .scale(extent, thickness, extent)
+            .translate(0.0, -thickness, 0.0)
+    }
+
     pub fn new_test_shape() -> Self {
         Object {
             shape: Shape::TestShape(TestShape::new()),
             material: Material::default(),
+            interior_material: None,
+            clip_planes: vec![],
             transformation: Matrix::identity(),
-            inv_transformation: Matrix::identity(),
-            inv_transpose_transformation: Matrix::identity(),
+            inverses: CachedInverse::default(),
         }
     }
 
@@ -144,14 +284,15 @@ impl Object {
         Object {
             shape: Shape::TestShape(TestShape::new()),
             material: Material::default(),
+            interior_material: None,
+            clip_planes: vec![],
             transformation: Matrix::identity(),
-            inv_transformation: Matrix::identity(),
-            inv_transpose_transformation: Matrix::identity(),
+            inverses: CachedInverse::default(),
         }
     }
 
     pub fn world_to_object(&self, p: &Point) -> Point {
-        self.inv_transformation * *p
+        self.inverse() * *p
     }
 
     pub fn children(&self) -> Option<&Vec<Object>> {
@@ -172,27 +313,207 @@ impl Object {
         self.normal_to_world(&local_normal)
     }
 
+    // Texture coordinates of a point on the surface, using the shape's own mapping
+    pub fn uv_at(&self, world_point: &Point) -> (f64, f64) {
+        self.shape.uv_at(&self.world_to_object(world_point))
+    }
+
     pub fn normal_to_world(&self, normal: &Vector) -> Vector {
-        (self.inv_transpose_transformation * *normal).normalize()
+        (self.inverses().1 * *normal).normalize()
     }
 
     pub fn intersect<'a>(&'a self, ray: &Ray, intersections: &mut Intersections<'a>) {
+        intersections.count_tests(1);
+        if self.clip_planes.is_empty() {
+            self.intersect_shape(ray, intersections);
+            return;
+        }
+
+        let mut hits = Intersections::new();
+        self.intersect_shape(ray, &mut hits);
+        let local_ray = ray.with_transform(self.inverse());
+        hits.filter(|int| {
+            let point = local_ray.position_at(int.t());
+            !self.clip_planes.iter().any(|plane| plane.clips(&point))
+        });
+        intersections.merge(hits);
+    }
+
+    fn intersect_shape<'a>(&'a self, ray: &Ray, intersections: &mut Intersections<'a>) {
         if self.shape.skip_world_to_local() {
             self.shape.intersect(ray, &self, intersections);
         } else {
-            let local_ray = ray.with_transform(self.inv_transformation);
+            let local_ray = ray.with_transform(self.inverse());
             self.shape.intersect(&local_ray, self, intersections);
         }
     }
 
+    // World space box around the object
+    pub fn bounds(&self) -> BoundingBox {
+        if self.shape.skip_world_to_local() {
+            self.shape.bounds()
+        } else {
+            self.shape.bounds().transform(&self.transformation)
+        }
+    }
+
     pub fn get_transform_inv(&self) -> Matrix {
-        self.inv_transformation
+        self.inverse()
+    }
+
+    fn inverse(&self) -> Matrix {
+        self.inverses().0
+    }
+
+    // Chained builders only change the transform, so invert once on first use
+    fn inverses(&self) -> &(Matrix, Matrix) {
+        self.inverses.0.get_or_init(|| {
+            #[cfg(test)]
+            INVERSIONS.with(|count| count.set(count.get() + 1));
+            let inverse = self.transformation.inverse();
+            (inverse, inverse.transpose())
+        })
+    }
+
+    // Material used when a ray hits the inside of the surface, e.g. the far wall of a
+    // cube seen from within
+    pub fn with_interior_material(mut self, material: Material) -> Self {
+        self.interior_material = Some(material);
+        self
+    }
+
+    pub fn get_interior_material(&self) -> Option<Material> {
+        self.interior_material.clone()
+    }
+
+    pub fn with_clip_plane(mut self, plane: ClipPlane) -> Self {
+        self.clip_planes.push(plane);
+        self
+    }
+
+    pub fn clip_planes(&self) -> &[ClipPlane] {
+        &self.clip_planes
+    }
+
+    // Groups and CSGs bake transforms into their children, so their clip planes, which
+    // live in the same space as the children, have to move with them
+    fn transformed_clip_planes(&self, transform: &Matrix) -> Vec<ClipPlane> {
+        let normal_tform = transform.inverse().transpose();
+        self.clip_planes
+            .iter()
+            .map(|plane| {
+                ClipPlane::new(
+                    *transform * plane.point,
+                    (normal_tform * plane.normal).normalize(),
+                )
+            })
+            .collect()
     }
 
     pub fn with_shape(mut self, shape: Shape) -> Self {
         self.shape = shape;
         self
     }
+
+    // Splits large groups, including those nested in groups and CSGs, into a bounding
+    // volume hierarchy. Hits are unchanged, rays just skip more of the scene.
+    pub fn divide(mut self, threshold: usize) -> Self {
+        self.shape = match self.shape {
+            Shape::Group(g) => Shape::Group(g.divide(threshold)),
+            Shape::Csg(c) => Shape::Csg(c.divide(threshold)),
+            shape => shape,
+        };
+        self
+    }
+
+    // Turns back-face culling on or off for every triangle in this object, including
+    // those nested inside groups
+    pub fn with_backface_cull(mut self, cull: bool) -> Self {
+        self.set_backface_cull(cull);
+        self
+    }
+
+    fn set_backface_cull(&mut self, cull: bool) {
+        match &mut self.shape {
+            Shape::Group(g) => {
+                for child in g.children_mut() {
+                    child.set_backface_cull(cull);
+                }
+            }
+            Shape::Triangle(t) => *t = t.clone().with_backface_cull(cull),
+            Shape::SmoothTriangle(st) => *st = st.clone().with_backface_cull(cull),
+            _ => {}
+        }
+    }
+
+    // Sets the texture coordinates at the vertices of a triangle. Other shapes keep their
+    // own mapping.
+    pub fn with_uvs(mut self, uvs: [(f64, f64); 3]) -> Self {
+        match &mut self.shape {
+            Shape::Triangle(t) => *t = t.clone().with_uvs(uvs),
+            Shape::SmoothTriangle(st) => *st = st.clone().with_uvs(uvs),
+            _ => {}
+        }
+        self
+    }
+
+    // Replaces every triangle in the object (or group tree) with a smooth triangle whose
+    // vertex normals are the average of the world space face normals sharing that vertex
+    pub fn recompute_normals(&mut self) {
+        let mut sums = HashMap::<VertexKey, Vector>::new();
+        self.accumulate_face_normals(&mut sums);
+        self.apply_vertex_normals(&sums);
+    }
+
+    fn triangle_points(&self) -> Option<(Point, Point, Point)> {
+        match &self.shape {
+            Shape::Triangle(t) => Some((t.p1(), t.p2(), t.p3())),
+            Shape::SmoothTriangle(st) => Some((st.p1(), st.p2(), st.p3())),
+            _ => None,
+        }
+    }
+
+    fn accumulate_face_normals(&self, sums: &mut HashMap<VertexKey, Vector>) {
+        if let Some(children) = self.children() {
+            for child in children {
+                child.accumulate_face_normals(sums);
+            }
+        } else if let Some((p1, p2, p3)) = self.triangle_points() {
+            let face_normal = self.normal_to_world(&Triangle::new(p1, p2, p3).normal_at(&p1));
+            for p in [p1, p2, p3] {
+                let sum = sums
+                    .entry(vertex_key(&(self.transformation * p)))
+                    .or_insert_with(|| Vector::new(0.0, 0.0, 0.0));
+                *sum = *sum + face_normal;
+            }
+        }
+    }
+
+    fn apply_vertex_normals(&mut self, sums: &HashMap<VertexKey, Vector>) {
+        if let Shape::Group(g) = &mut self.shape {
+            for child in g.children_mut() {
+                child.apply_vertex_normals(sums);
+            }
+        } else if let Some((p1, p2, p3)) = self.triangle_points() {
+            // Object space normals are mapped back with the transpose of the transform,
+            // which undoes the inverse transpose applied by normal_to_world
+            let to_object = self.transformation.transpose();
+            let [n1, n2, n3] = [p1, p2, p3].map(|p| {
+                let world_normal = sums[&vertex_key(&(self.transformation * p))].normalize();
+                (to_object * world_normal).normalize()
+            });
+            let (cull, uvs) = match &self.shape {
+                Shape::Triangle(t) => (t.backface_cull(), t.uvs()),
+                Shape::SmoothTriangle(st) => (st.backface_cull(), st.uvs()),
+                _ => (false, None),
+            };
+            let smooth = SmoothTriangle::new(p1, p2, p3, n1, n2, n3).with_backface_cull(cull);
+            self.shape = Shape::SmoothTriangle(match uvs {
+                Some(uvs) => smooth.with_uvs(uvs),
+                None => smooth,
+            });
+        }
+    }
 }
 
 impl Transformable for Object {
@@ -214,14 +535,27 @@ impl Transformable for Object {
                     children_group_builders,
                 );
 
-                group_builder.build()
+                Object {
+                    interior_material: self.interior_material.clone(),
+                    clip_planes: self.transformed_clip_planes(&new_transformation),
+                    ..group_builder.build()
+                }
             }
+            Shape::Csg(c) => Object {
+                interior_material: self.interior_material.clone(),
+                clip_planes: self.transformed_clip_planes(&new_transformation),
+                ..Object::new_csg(
+                    c.op(),
+                    c.left().clone().with_transform(new_transformation),
+                    c.right().clone().with_transform(new_transformation),
+                )
+                .with_material(self.material.clone())
+            },
             _ => {
                 let new_transformation = new_transformation * self.transformation;
                 Object {
                     transformation: new_transformation,
-                    inv_transformation: new_transformation.inverse(),
-                    inv_transpose_transformation: new_transformation.inverse().transpose(),
+                    inverses: CachedInverse::default(),
                     ..self
                 }
             }
@@ -234,14 +568,15 @@ impl Materialable for Object {
         Object {
             shape: self.shape,
             material,
+            interior_material: self.interior_material,
+            clip_planes: self.clip_planes,
             transformation: self.transformation,
-            inv_transformation: self.inv_transformation,
-            inv_transpose_transformation: self.inv_transpose_transformation,
+            inverses: self.inverses,
         }
     }
 
     fn get_material(&self) -> Material {
-        self.material
+        self.material.clone()
     }
 }
 
@@ -396,4 +731,227 @@ mod test {
         let want = Vector::new(0.285703, 0.42854, -0.857160);
         assert_eq!(got, want);
     }
+
+    #[test]
+    fn recomputed_normals_average_shared_vertices() {
+        let t1 = Object::new_tri(
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+        );
+        let t2 = Object::new_tri(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, -1.0),
+        );
+        let mut quad = Object::new_group(vec![t1, t2]);
+        quad.recompute_normals();
+
+        let children = quad.children().unwrap();
+        let st1 = children[0].get_shape().as_smooth_triangle().unwrap();
+        let st2 = children[1].get_shape().as_smooth_triangle().unwrap();
+
+        // Faces have normals (0, 0, -1) and (-0.7071, 0, -0.7071), so the shared edge
+        // gets the normalized sum of both
+        let shared = Vector::new(-0.38268, 0.0, -0.92388);
+        assert_eq!(st1.n1(), shared);
+        assert_eq!(st1.n2(), shared);
+        assert_eq!(st1.n3(), Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(st2.n1(), shared);
+        assert_eq!(st2.n2(), shared);
+        assert_eq!(st2.n3(), Vector::new(-0.70711, 0.0, -0.70711));
+    }
+
+    #[test]
+    fn recomputed_normals_follow_the_object_transform() {
+        let mut tri = Object::new_tri(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+        )
+        .rotate_y(PI / 2.0);
+        tri.recompute_normals();
+
+        let int = Intersection::new(1.0, &tri).with_u_v(0.25, 0.25);
+        let got = tri.normal_at(&Point::new(0.0, 0.5, 0.0), &int);
+        assert_eq!(got, Vector::new(-1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn axis_gizmo_has_red_green_blue_emissive_axes() {
+        let gizmo = Object::axis_gizmo(2.0);
+        let children = gizmo.children().unwrap();
+        assert_eq!(children.len(), 3);
+
+        let origin = Point::new(0.0, 0.0, 0.0);
+        for (child, color) in children
+            .iter()
+            .zip([Color::red(), Color::green(), Color::blue()])
+        {
+            let material = child.get_material();
+            assert_eq!(material.pattern.pattern_at(&origin), color);
+            assert_eq!(material.emission, color);
+            assert_eq!(material.ambient, 0.0);
+            assert_eq!(material.diffuse, 0.0);
+            assert_eq!(material.specular, 0.0);
+        }
+
+        let x_tip = children[0].get_transform() * Point::new(0.0, 1.0, 0.0);
+        assert_eq!(x_tip, Point::new(2.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn ground_grid_is_a_checkered_slab_topped_at_zero() {
+        let grid = Object::ground_grid(5.0, 1.0);
+        let bounds = grid.bounds();
+        assert_eq!(bounds.min(), Point::new(-5.0, -0.02, -5.0));
+        assert_eq!(bounds.max(), Point::new(5.0, 0.0, 5.0));
+
+        // The checkers change every spacing units across the top
+        let pattern = grid.get_material().pattern;
+        let color_at = |x: f64, z: f64| pattern.pattern_at_object(&grid, &Point::new(x, 0.0, z));
+        let light = Color::new(0.9, 0.9, 0.9);
+        let dark = Color::new(0.4, 0.4, 0.4);
+        assert_eq!(color_at(0.5, 0.5), light);
+        assert_eq!(color_at(1.5, 0.5), dark);
+        assert_eq!(color_at(2.5, 0.5), light);
+        assert_eq!(color_at(1.5, 1.5), light);
+        assert_eq!(color_at(-0.5, 0.5), dark);
+    }
+
+    #[test]
+    fn clip_planes_discard_intersections_on_the_clipped_side() {
+        let obj = Object::new_sphere()
+            .with_clip_plane(ClipPlane::new(
+                Point::new(0.0, 0.0, 0.0),
+                Vector::new(0.0, -1.0, 0.0),
+            ))
+            .scale(2.0, 2.0, 2.0);
+
+        let tests = vec![
+            (
+                Point::new(0.0, -5.0, 0.0),
+                Vector::new(0.0, 1.0, 0.0),
+                vec![7.0],
+            ),
+            (
+                Point::new(-5.0, 1.0, 0.0),
+                Vector::new(1.0, 0.0, 0.0),
+                vec![5.0 - f64::sqrt(3.0), 5.0 + f64::sqrt(3.0)],
+            ),
+            (
+                Point::new(-5.0, -1.0, 0.0),
+                Vector::new(1.0, 0.0, 0.0),
+                vec![],
+            ),
+        ];
+
+        for (origin, direction, want) in tests {
+            let mut ints = Intersections::new();
+            obj.intersect(&Ray::new(origin, direction), &mut ints);
+            let got: Vec<f64> = ints.iter().map(|int| int.t()).collect();
+            assert_eq!(got.len(), want.len());
+            for (got, want) in got.iter().zip(want) {
+                assert!((got - want).abs() < EPSILON);
+            }
+        }
+    }
+
+    #[test]
+    fn transforming_a_clipped_csg_keeps_and_moves_its_clip_planes() {
+        let inside = Material::default().with_ambient(1.0);
+        let csg = Object::new_csg(
+            CsgOp::Union,
+            Object::new_sphere(),
+            Object::new_sphere().translate(0.0, 0.0, 10.0),
+        )
+        .with_interior_material(inside.clone())
+        .with_clip_plane(ClipPlane::new(
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, -1.0, 0.0),
+        ))
+        .translate(0.0, 3.0, 0.0);
+
+        assert_eq!(csg.get_interior_material(), Some(inside));
+        assert_eq!(
+            csg.clip_planes(),
+            &[ClipPlane::new(
+                Point::new(0.0, 3.0, 0.0),
+                Vector::new(0.0, -1.0, 0.0)
+            )]
+        );
+
+        // Only the upper half of the moved sphere is left
+        let mut ints = Intersections::new();
+        let ray = Ray::new(Point::new(0.0, -5.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        csg.intersect(&ray, &mut ints);
+        let got: Vec<f64> = ints.iter().map(|int| int.t()).collect();
+        assert_eq!(got.len(), 1);
+        assert!((got[0] - 9.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn transforming_a_clipped_group_keeps_and_moves_its_clip_planes() {
+        let inside = Material::default().with_ambient(1.0);
+        let group = Object::new_group(vec![Object::new_sphere()])
+            .with_interior_material(inside.clone())
+            .with_clip_plane(ClipPlane::new(
+                Point::new(0.0, 0.0, 0.0),
+                Vector::new(0.0, -1.0, 0.0),
+            ))
+            .translate(0.0, 3.0, 0.0);
+
+        assert_eq!(group.get_interior_material(), Some(inside));
+        assert_eq!(
+            group.clip_planes(),
+            &[ClipPlane::new(
+                Point::new(0.0, 3.0, 0.0),
+                Vector::new(0.0, -1.0, 0.0)
+            )]
+        );
+
+        // Only the upper half of the moved sphere is left
+        let mut ints = Intersections::new();
+        let ray = Ray::new(Point::new(0.0, -5.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        group.intersect(&ray, &mut ints);
+        let got: Vec<f64> = ints.iter().map(|int| int.t()).collect();
+        assert_eq!(got.len(), 1);
+        assert!((got[0] - 9.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn chained_transforms_are_inverted_once_on_first_use() {
+        INVERSIONS.with(|count| count.set(0));
+        let obj = Object::new_sphere()
+            .scale(1.0, 2.0, 3.0)
+            .rotate_x(PI / 3.0)
+            .rotate_y(PI / 5.0)
+            .translate(4.0, 5.0, 6.0);
+        assert_eq!(INVERSIONS.with(|count| count.get()), 0);
+
+        assert_eq!(obj.get_transform_inv(), obj.get_transform().inverse());
+        obj.world_to_object(&Point::new(1.0, 1.0, 1.0));
+        obj.normal_to_world(&Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(INVERSIONS.with(|count| count.get()), 1);
+    }
+
+    #[test]
+    fn object_bounds_follow_its_transform() {
+        let s = Object::new_sphere()
+            .scale(0.5, 2.0, 4.0)
+            .translate(1.0, -3.0, 5.0);
+        let bb = s.bounds();
+        assert_eq!(bb.min(), Point::new(0.5, -5.0, 1.0));
+        assert_eq!(bb.max(), Point::new(1.5, -1.0, 9.0));
+    }
+
+    #[test]
+    fn uv_coordinates_follow_the_object_transform() {
+        let sphere = Object::new_sphere().scale(2.0, 2.0, 2.0);
+        assert_eq!(sphere.uv_at(&Point::new(0.0, 0.0, -2.0)), (0.0, 0.5));
+        assert_eq!(sphere.uv_at(&Point::new(2.0, 0.0, 0.0)), (0.25, 0.5));
+
+        let plane = Object::new_plane().translate(0.5, 0.0, 0.0);
+        assert_eq!(plane.uv_at(&Point::new(0.75, 0.0, 1.5)), (0.25, 0.5));
+    }
 }
@@ -1,16 +1,24 @@
 use crate::{
-    draw::color::Color,
-    math::{matrix::Matrix, point::Point, transformation::Transformable},
+    draw::{canvas::Canvas, color::Color},
+    math::{
+        matrix::Matrix,
+        point::Point,
+        transformation::{scale, Transformable},
+        tuple::Tuple,
+    },
 };
 
 use crate::render::patterns::{SolidPattern, StripePattern, TestPattern};
 
 use super::{
     object::Object,
-    patterns::{CheckerPattern, GradientPattern, NoisePattern, RingPattern},
+    patterns::{
+        BlendPattern, CheckerPattern, GradientMode, GradientPattern, MarblePattern, NoisePattern,
+        PerturbPattern, RingPattern, TexturePattern, UvCheckerPattern, WoodPattern, WrapMode,
+    },
 };
 
-#[derive(Clone, Debug, Copy, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 enum PatternType {
     Stripe(StripePattern),
     Solid(SolidPattern),
@@ -19,9 +27,15 @@ enum PatternType {
     Ring(RingPattern),
     Checker(CheckerPattern),
     Noise(NoisePattern),
+    Marble(MarblePattern),
+    Wood(WoodPattern),
+    Blend(BlendPattern),
+    Perturb(PerturbPattern),
+    Texture(TexturePattern),
+    UvChecker(UvCheckerPattern),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Pattern {
     pattern: PatternType,
     transformation: Matrix,
@@ -29,54 +43,108 @@ pub struct Pattern {
 }
 
 impl Pattern {
-    pub fn new_solid(color: Color) -> Self {
+    // Every pattern starts untransformed, so its inverse is the identity too
+    fn from_type(pattern: PatternType) -> Self {
         Self {
-            pattern: PatternType::Solid(SolidPattern::new(color)),
+            pattern,
             transformation: Matrix::identity(),
-            inv_transform: Matrix::identity().inverse(),
+            inv_transform: Matrix::identity(),
         }
     }
 
+    pub fn new_solid(color: Color) -> Self {
+        Self::from_type(PatternType::Solid(SolidPattern::new(color)))
+    }
+
     pub fn new_stripe(a: Color, b: Color) -> Self {
-        Self {
-            pattern: PatternType::Stripe(StripePattern::new(a, b)),
-            transformation: Matrix::identity(),
-            inv_transform: Matrix::identity(),
-        }
+        Self::new_stripe_patterns(Self::new_solid(a), Self::new_solid(b))
+    }
+
+    pub fn new_stripe_patterns(a: Pattern, b: Pattern) -> Self {
+        Self::from_type(PatternType::Stripe(StripePattern::new(a, b)))
     }
 
     pub fn new_gradient(a: Color, b: Color) -> Self {
-        Self {
-            pattern: PatternType::Gradient(GradientPattern::new(a, b)),
-            transformation: Matrix::identity(),
-            inv_transform: Matrix::identity(),
+        Self::new_gradient_patterns(Self::new_solid(a), Self::new_solid(b))
+    }
+
+    pub fn new_gradient_patterns(a: Pattern, b: Pattern) -> Self {
+        Self::from_type(PatternType::Gradient(GradientPattern::new(a, b)))
+    }
+
+    // Sets how a gradient pattern extends past its two colors, other patterns are unchanged
+    pub fn with_gradient_mode(self, mode: GradientMode) -> Self {
+        match self.pattern {
+            PatternType::Gradient(p) => Self {
+                pattern: PatternType::Gradient(p.with_mode(mode)),
+                ..self
+            },
+            _ => self,
         }
     }
 
     pub fn new_ring(a: Color, b: Color) -> Self {
-        Self {
-            pattern: PatternType::Ring(RingPattern::new(a, b)),
-            transformation: Matrix::identity(),
-            inv_transform: Matrix::identity(),
-        }
+        Self::new_ring_patterns(Self::new_solid(a), Self::new_solid(b))
+    }
+
+    pub fn new_ring_patterns(a: Pattern, b: Pattern) -> Self {
+        Self::from_type(PatternType::Ring(RingPattern::new(a, b)))
     }
 
     pub fn new_checker(a: Color, b: Color) -> Self {
-        Self {
-            pattern: PatternType::Checker(CheckerPattern::new(a, b)),
-            transformation: Matrix::identity(),
-            inv_transform: Matrix::identity(),
-        }
+        Self::new_checker_patterns(Self::new_solid(a), Self::new_solid(b))
+    }
+
+    pub fn new_checker_patterns(a: Pattern, b: Pattern) -> Self {
+        Self::from_type(PatternType::Checker(CheckerPattern::new(a, b)))
     }
 
     pub fn new_noise(c_a: Color, c_b: Color, threshold: f64) -> Self {
-        Self {
-            pattern: PatternType::Noise(NoisePattern::new(c_a, c_b, threshold)),
-            transformation: Matrix::identity(),
-            inv_transform: Matrix::identity(),
+        Self::from_type(PatternType::Noise(NoisePattern::new(c_a, c_b, threshold)))
+    }
+
+    pub fn new_marble(a: Color, b: Color, turbulence: f64) -> Self {
+        Self::from_type(PatternType::Marble(MarblePattern::new(a, b, turbulence)))
+    }
+
+    pub fn new_wood(a: Color, b: Color, turbulence: f64) -> Self {
+        Self::from_type(PatternType::Wood(WoodPattern::new(a, b, turbulence)))
+    }
+
+    // Averages two patterns, each keeping its own transform
+    pub fn new_blend(a: Pattern, b: Pattern) -> Self {
+        Self::from_type(PatternType::Blend(BlendPattern::new(a, b)))
+    }
+
+    // Jitters every lookup into the wrapped pattern by up to scale units with perlin noise
+    pub fn new_perturb(pattern: Pattern, scale: f64) -> Self {
+        Self::from_type(PatternType::Perturb(PerturbPattern::new(pattern, scale)))
+    }
+
+    // Maps an image onto whatever shape uses it, see Shape::uv_at
+    pub fn new_texture(canvas: Canvas) -> Self {
+        Self::from_type(PatternType::Texture(TexturePattern::new(canvas)))
+    }
+
+    // Sets how a texture pattern maps u and v outside of [0, 1], other patterns are
+    // unchanged
+    pub fn with_wrap_mode(self, mode: WrapMode) -> Self {
+        match self.pattern {
+            PatternType::Texture(p) => Self {
+                pattern: PatternType::Texture(p.with_wrap(mode)),
+                ..self
+            },
+            _ => self,
         }
     }
 
+    // Checkers in texture space, width by height squares across the whole surface
+    pub fn new_uv_checker(width: usize, height: usize, a: Color, b: Color) -> Self {
+        Self::from_type(PatternType::UvChecker(UvCheckerPattern::new(
+            width, height, a, b,
+        )))
+    }
+
     pub fn pattern_at(&self, point: &Point) -> Color {
         match &self.pattern {
             PatternType::Stripe(p) => p.pattern_at(point),
@@ -86,14 +154,54 @@ impl Pattern {
             PatternType::Ring(p) => p.pattern_at(point),
             PatternType::Checker(p) => p.pattern_at(point),
             PatternType::Noise(p) => p.pattern_at(point),
+            PatternType::Marble(p) => p.pattern_at(point),
+            PatternType::Wood(p) => p.pattern_at(point),
+            PatternType::Blend(p) => p.pattern_at(point),
+            PatternType::Perturb(p) => p.pattern_at(point),
+            PatternType::Texture(p) => p.pattern_at(point),
+            PatternType::UvChecker(p) => p.pattern_at(point),
+        }
+    }
+
+    // Color at texture coordinates (u, v). Patterns defined in 3D read them as the
+    // point (u, 0, v).
+    pub fn pattern_at_uv(&self, u: f64, v: f64) -> Color {
+        match &self.pattern {
+            PatternType::Texture(p) => p.pattern_at_uv(u, v),
+            PatternType::UvChecker(p) => p.pattern_at_uv(u, v),
+            _ => self.pattern_at(&Point::new(u, 0.0, v)),
+        }
+    }
+
+    // Color at a point in the pattern space of a pattern containing this one
+    // Solid colors and untransformed patterns skip the matrix multiply
+    pub(crate) fn nested_pattern_at(&self, point: &Point) -> Color {
+        match &self.pattern {
+            PatternType::Solid(p) => p.pattern_at(point),
+            _ if self.inv_transform == Matrix::identity() => self.pattern_at(point),
+            _ => self.pattern_at(&(self.inv_transform * *point)),
         }
     }
 
     pub fn new_test() -> Self {
+        Self::from_type(PatternType::Test(TestPattern::new()))
+    }
+
+    // Rescales the pattern so one tile spans the given number of units along each axis,
+    // keeping any rotation and translation already applied to it
+    pub fn tile_size(self, units: f64) -> Self {
+        let m = self.transformation;
+        let axis_scale =
+            |col: usize| (m[0][col].powi(2) + m[1][col].powi(2) + m[2][col].powi(2)).sqrt();
+        let tform = m * scale(
+            units / axis_scale(0),
+            units / axis_scale(1),
+            units / axis_scale(2),
+        );
         Self {
-            pattern: PatternType::Test(TestPattern::new()),
-            transformation: Matrix::identity(),
-            inv_transform: Matrix::identity(),
+            pattern: self.pattern,
+            transformation: tform,
+            inv_transform: tform.inverse(),
         }
     }
 
@@ -101,17 +209,20 @@ impl Pattern {
         let obj_inv_tform = obj.get_transform_inv();
         let obj_point = obj_inv_tform * *world_point;
         let pattern_point = self.inv_transform * obj_point;
-        self.pattern_at(&pattern_point)
+        match &self.pattern {
+            // Patterns defined over the surface use the shape's own (u, v) mapping
+            PatternType::Texture(_) | PatternType::UvChecker(_) => {
+                let (u, v) = obj.get_shape().uv_at(&pattern_point);
+                self.pattern_at_uv(u, v)
+            }
+            _ => self.pattern_at(&pattern_point),
+        }
     }
 }
 
 impl Default for Pattern {
     fn default() -> Self {
-        Self {
-            pattern: PatternType::Solid(SolidPattern::new(Color::white())),
-            transformation: Matrix::identity(),
-            inv_transform: Matrix::identity().inverse(),
-        }
+        Self::from_type(PatternType::Solid(SolidPattern::new(Color::white())))
     }
 }
 
@@ -135,7 +246,7 @@ mod test {
 
     use super::{Pattern, Transformable};
     use crate::{
-        draw::color::Color,
+        draw::{canvas::Canvas, color::Color},
         math::{matrix::Matrix, point::Point, transformation::translate, tuple::Tuple},
         render::{material::Materialable, object::Object},
     };
@@ -155,12 +266,23 @@ mod test {
     #[test]
     fn stripes_with_a_pattern_transformation() {
         let pat = Pattern::new_stripe(Color::white(), Color::black()).scale(2.0, 2.0, 2.0);
-        let obj = Object::new_sphere().with_pattern(pat);
+        let obj = Object::new_sphere().with_pattern(pat.clone());
         let c = obj
             .get_material()
             .pattern
             .pattern_at_object(&obj, &Point::new(1.5, 0.0, 0.0));
         assert_eq!(c, Color::white());
+
+        // Unscaled, the same point would already be in the second (black) stripe
+        let plain = Pattern::new_stripe(Color::white(), Color::black());
+        assert_eq!(
+            plain.pattern_at_object(&obj, &Point::new(1.5, 0.0, 0.0)),
+            Color::black()
+        );
+        assert_eq!(
+            pat.pattern_at_object(&obj, &Point::new(2.5, 0.0, 0.0)),
+            Color::black()
+        );
     }
 
     #[test]
@@ -175,6 +297,29 @@ mod test {
         assert_eq!(c, Color::white());
     }
 
+    #[test]
+    fn stripes_on_an_object_inside_a_transformed_group() {
+        let sphere = Object::new_sphere()
+            .with_pattern(Pattern::new_stripe(Color::white(), Color::black()).scale(0.5, 0.5, 0.5))
+            .translate(0.5, 0.0, 0.0);
+        let group = Object::new_group(vec![sphere]).scale(2.0, 2.0, 2.0);
+        let child = &group.children().unwrap()[0];
+
+        // World x = 2.5 -> group space 1.25 -> object space 0.75 -> pattern space 1.5
+        let c = child
+            .get_material()
+            .pattern
+            .pattern_at_object(child, &Point::new(2.5, 0.0, 0.0));
+        assert_eq!(c, Color::black());
+
+        // World x = 3.5 -> group space 1.75 -> object space 1.25 -> pattern space 2.5
+        let c = child
+            .get_material()
+            .pattern
+            .pattern_at_object(child, &Point::new(3.5, 0.0, 0.0));
+        assert_eq!(c, Color::white());
+    }
+
     #[test]
     fn default_pattern_transformation() {
         let pat = Pattern::new_test();
@@ -186,4 +331,156 @@ mod test {
         let pat = Pattern::new_test().translate(1.0, 2.0, 3.0);
         assert_eq!(pat.get_transform(), translate(1.0, 2.0, 3.0));
     }
+
+    #[test]
+    fn tile_size_sets_stripe_width_in_world_units() {
+        let stripes = Pattern::new_stripe(Color::white(), Color::black());
+        for pat in [
+            stripes.clone().tile_size(0.5),
+            stripes.scale(3.0, 3.0, 3.0).tile_size(0.5),
+        ] {
+            let plane = Object::new_plane().with_pattern(pat.clone());
+            let color_at = |x: f64| pat.pattern_at_object(&plane, &Point::new(x, 0.0, 0.0));
+            assert_eq!(color_at(0.25), Color::white());
+            assert_eq!(color_at(0.75), Color::black());
+            assert_eq!(color_at(1.25), Color::white());
+            assert_eq!(color_at(1.75), Color::black());
+        }
+    }
+
+    #[test]
+    fn blending_solid_white_and_black_gives_gray() {
+        let pat = Pattern::new_blend(
+            Pattern::new_solid(Color::white()),
+            Pattern::new_solid(Color::black()),
+        );
+        let gray = Color::new(0.5, 0.5, 0.5);
+        for point in [
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(1.5, -3.0, 7.25),
+            Point::new(-20.0, 4.0, 0.5),
+        ] {
+            assert_eq!(pat.pattern_at(&point), gray);
+        }
+    }
+
+    #[test]
+    fn blended_patterns_keep_their_own_transforms() {
+        let wide = Pattern::new_stripe(Color::white(), Color::black()).scale(2.0, 1.0, 1.0);
+        let pat = Pattern::new_blend(wide, Pattern::new_stripe(Color::white(), Color::black()));
+        assert_eq!(pat.pattern_at(&Point::new(0.5, 0.0, 0.0)), Color::white());
+        assert_eq!(
+            pat.pattern_at(&Point::new(1.5, 0.0, 0.0)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(pat.pattern_at(&Point::new(3.5, 0.0, 0.0)), Color::black());
+    }
+
+    #[test]
+    fn checker_of_two_stripe_patterns() {
+        let red_white = Pattern::new_stripe(Color::red(), Color::white());
+        let blue_black = Pattern::new_stripe(Color::blue(), Color::black()).scale(0.5, 1.0, 1.0);
+        let pat = Pattern::new_checker_patterns(red_white, blue_black);
+
+        // Even cells show the red and white stripes, odd cells the narrower blue and black
+        assert_eq!(pat.pattern_at(&Point::new(0.5, 0.0, 0.0)), Color::red());
+        assert_eq!(pat.pattern_at(&Point::new(0.5, 0.0, 1.5)), Color::black());
+        assert_eq!(pat.pattern_at(&Point::new(1.25, 0.0, 0.0)), Color::blue());
+        assert_eq!(pat.pattern_at(&Point::new(1.75, 0.0, 0.0)), Color::black());
+        assert_eq!(pat.pattern_at(&Point::new(1.5, 0.0, 1.5)), Color::white());
+    }
+
+    #[test]
+    fn perturbing_by_zero_leaves_the_pattern_unchanged() {
+        let stripes = Pattern::new_stripe(Color::white(), Color::black()).scale(0.3, 1.0, 1.0);
+        let still = Pattern::new_perturb(stripes.clone(), 0.0);
+        let wavy = Pattern::new_perturb(stripes.clone(), 0.5);
+
+        let mut moved = 0;
+        for i in 0..50 {
+            let f = i as f64;
+            let point = Point::new(f * 0.137 - 3.0, f * 0.071, 2.0 - f * 0.113);
+            assert_eq!(still.pattern_at(&point), stripes.nested_pattern_at(&point));
+            if wavy.pattern_at(&point) != stripes.nested_pattern_at(&point) {
+                moved += 1;
+            }
+        }
+        assert!(moved > 0);
+    }
+
+    #[test]
+    fn texture_patterns_use_the_shapes_uv_mapping() {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.set_pixel((0, 0), &Color::red());
+        canvas.set_pixel((1, 0), &Color::green());
+        canvas.set_pixel((0, 1), &Color::blue());
+        canvas.set_pixel((1, 1), &Color::white());
+        let pat = Pattern::new_texture(canvas);
+
+        // Planes repeat the image every unit in x and z
+        let plane = Object::new_plane().with_pattern(pat.clone());
+        let color_at = |x: f64, z: f64| pat.pattern_at_object(&plane, &Point::new(x, 0.0, z));
+        assert_eq!(color_at(0.25, 0.25), Color::blue());
+        assert_eq!(color_at(0.75, 0.75), Color::green());
+        assert_eq!(color_at(2.75, -0.75), Color::white());
+    }
+
+    #[test]
+    fn uv_checkers_follow_the_shapes_mapping() {
+        let pat = Pattern::new_uv_checker(16, 8, Color::black(), Color::white());
+        let sphere = Object::new_sphere().with_pattern(pat.clone());
+        let color_at =
+            |x: f64, y: f64, z: f64| pat.pattern_at_object(&sphere, &Point::new(x, y, z));
+
+        // The same squares a spherical map lands on, as in the book
+        let tests = [
+            (0.4315, 0.4670, 0.7719, Color::white()),
+            (-0.9654, 0.2552, -0.0534, Color::black()),
+            (0.1039, 0.7090, 0.6975, Color::white()),
+            (-0.4986, -0.7856, -0.3663, Color::black()),
+            (-0.0317, -0.9395, 0.3411, Color::black()),
+            (0.4809, -0.7721, 0.4154, Color::black()),
+            (0.0285, -0.9612, -0.2745, Color::black()),
+            (-0.5734, -0.2162, -0.7903, Color::white()),
+            (0.7688, -0.1470, 0.6223, Color::black()),
+            (-0.7652, 0.2175, 0.6060, Color::black()),
+        ];
+        for (x, y, z, want) in tests {
+            assert_eq!(color_at(x, y, z), want, "({}, {}, {})", x, y, z);
+        }
+
+        // Planes tile the checkers every unit
+        let plane = Object::new_plane();
+        let pat = Pattern::new_uv_checker(2, 2, Color::black(), Color::white());
+        assert_eq!(
+            pat.pattern_at_object(&plane, &Point::new(0.25, 0.0, 0.25)),
+            Color::black()
+        );
+        assert_eq!(
+            pat.pattern_at_object(&plane, &Point::new(1.75, 0.0, 0.25)),
+            Color::white()
+        );
+    }
+
+    #[test]
+    fn nested_lookups_skip_the_transform_when_it_cannot_matter() {
+        // A solid color is the same everywhere, whatever transform it carries
+        let solid = Pattern::new_solid(Color::red()).scale(3.0, 3.0, 3.0);
+        assert_eq!(
+            solid.nested_pattern_at(&Point::new(7.0, 0.0, 0.0)),
+            Color::red()
+        );
+
+        // An untransformed sub-pattern reads the point as given
+        let test = Pattern::new_test();
+        let point = Point::new(0.25, 0.5, 0.75);
+        assert_eq!(test.nested_pattern_at(&point), Color::new(0.25, 0.5, 0.75));
+
+        // A transformed one still maps the point into its own space
+        let scaled = Pattern::new_test().scale(2.0, 2.0, 2.0);
+        assert_eq!(
+            scaled.nested_pattern_at(&point),
+            Color::new(0.125, 0.25, 0.375)
+        );
+    }
 }
@@ -1,8 +1,12 @@
+use std::{f64::consts::PI, sync::Arc};
+
 use crate::{
-    draw::color::Color,
+    draw::{canvas::Canvas, color::Color},
     math::{epsilon::ApproxEq, point::Point, tuple::Tuple},
 };
 
+use super::{pattern::Pattern, shapes::sphere::Sphere};
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct SolidPattern {
     color: Color,
@@ -18,22 +22,25 @@ impl SolidPattern {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct StripePattern {
-    a: Color,
-    b: Color,
+    a: Arc<Pattern>,
+    b: Arc<Pattern>,
 }
 
 impl StripePattern {
-    pub fn new(a: Color, b: Color) -> Self {
-        Self { a, b }
+    pub fn new(a: Pattern, b: Pattern) -> Self {
+        Self {
+            a: Arc::new(a),
+            b: Arc::new(b),
+        }
     }
 
     pub fn pattern_at(&self, point: &Point) -> Color {
         if (point.x().floor().abs() as usize) % 2 == 0 {
-            self.a
+            self.a.nested_pattern_at(point)
         } else {
-            self.b
+            self.b.nested_pattern_at(point)
         }
     }
 }
@@ -51,60 +58,92 @@ impl TestPattern {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+// How a gradient behaves outside of x in [0, 1]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum GradientMode {
+    #[default]
+    Clamp, // Holds the edge colors
+    Repeat, // Starts over from a at every whole number
+    Mirror, // Runs back and forth between a and b
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct GradientPattern {
-    a: Color,
-    b: Color,
+    a: Arc<Pattern>,
+    b: Arc<Pattern>,
+    mode: GradientMode,
 }
 
 impl GradientPattern {
-    pub fn new(a: Color, b: Color) -> Self {
-        Self { a, b }
+    pub fn new(a: Pattern, b: Pattern) -> Self {
+        Self {
+            a: Arc::new(a),
+            b: Arc::new(b),
+            mode: GradientMode::default(),
+        }
+    }
+
+    pub fn with_mode(self, mode: GradientMode) -> Self {
+        Self { mode, ..self }
     }
 
     pub fn pattern_at(&self, point: &Point) -> Color {
-        self.a + point.x() * (self.b - self.a)
+        let x = point.x();
+        let t = match self.mode {
+            GradientMode::Clamp => x.clamp(0.0, 1.0),
+            GradientMode::Repeat => x - x.floor(),
+            GradientMode::Mirror => 1.0 - (x.rem_euclid(2.0) - 1.0).abs(),
+        };
+        let a = self.a.nested_pattern_at(point);
+        let b = self.b.nested_pattern_at(point);
+        a + t * (b - a)
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct RingPattern {
-    a: Color,
-    b: Color,
+    a: Arc<Pattern>,
+    b: Arc<Pattern>,
 }
 
 impl RingPattern {
-    pub fn new(a: Color, b: Color) -> Self {
-        Self { a, b }
+    pub fn new(a: Pattern, b: Pattern) -> Self {
+        Self {
+            a: Arc::new(a),
+            b: Arc::new(b),
+        }
     }
 
     pub fn pattern_at(&self, point: &Point) -> Color {
         let distance = ((point.x() * point.x()) + (point.z() * point.z())).sqrt();
         if (distance.floor() as usize) % 2 == 0 {
-            self.a
+            self.a.nested_pattern_at(point)
         } else {
-            self.b
+            self.b.nested_pattern_at(point)
         }
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct CheckerPattern {
-    a: Color,
-    b: Color,
+    a: Arc<Pattern>,
+    b: Arc<Pattern>,
 }
 
 impl CheckerPattern {
-    pub fn new(a: Color, b: Color) -> Self {
-        Self { a, b }
+    pub fn new(a: Pattern, b: Pattern) -> Self {
+        Self {
+            a: Arc::new(a),
+            b: Arc::new(b),
+        }
     }
 
     pub fn pattern_at(&self, point: &Point) -> Color {
         let sum = point.x().floor() + point.y().floor() + point.z().floor();
         if (sum % 2.0).approx_eq(0.0) {
-            self.a
+            self.a.nested_pattern_at(point)
         } else {
-            self.b
+            self.b.nested_pattern_at(point)
         }
     }
 }
@@ -237,19 +276,222 @@ impl NoisePattern {
     }
 }
 
+// Perlin noise summed over a few octaves
+fn turbulence(point: &Point) -> f64 {
+    (0..4)
+        .map(|octave| {
+            let f = (1 << octave) as f64;
+            NoisePattern::perlin(point.x() * f, point.y() * f, point.z() * f) / f
+        })
+        .sum()
+}
+
+// Sine veins along x bent by turbulence
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MarblePattern {
+    a: Color,
+    b: Color,
+    turbulence: f64,
+}
+
+impl MarblePattern {
+    pub fn new(a: Color, b: Color, turbulence: f64) -> Self {
+        Self { a, b, turbulence }
+    }
+
+    pub fn pattern_at(&self, point: &Point) -> Color {
+        let vein = (point.x() * PI + self.turbulence * turbulence(point)).sin();
+        let t = 0.5 + 0.5 * vein;
+        self.a + t * (self.b - self.a)
+    }
+}
+
+// Rings around the y axis, wobbled by turbulence
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct WoodPattern {
+    a: Color,
+    b: Color,
+    turbulence: f64,
+}
+
+impl WoodPattern {
+    pub fn new(a: Color, b: Color, turbulence: f64) -> Self {
+        Self { a, b, turbulence }
+    }
+
+    pub fn pattern_at(&self, point: &Point) -> Color {
+        let distance = (point.x().powi(2) + point.z().powi(2)).sqrt();
+        let ring = distance + self.turbulence * turbulence(point);
+        let t = ring - ring.floor();
+        self.a + t * (self.b - self.a)
+    }
+}
+
+// Moves each lookup by up to scale along every axis using perlin noise before asking the
+// wrapped pattern, so straight edges come out wavy
+#[derive(Debug, Clone, PartialEq)]
+pub struct PerturbPattern {
+    pattern: Arc<Pattern>,
+    scale: f64,
+}
+
+impl PerturbPattern {
+    pub fn new(pattern: Pattern, scale: f64) -> Self {
+        Self {
+            pattern: Arc::new(pattern),
+            scale,
+        }
+    }
+
+    pub fn pattern_at(&self, point: &Point) -> Color {
+        let (x, y, z) = (point.x(), point.y(), point.z());
+        // Offset samples keep the three axes from moving in lockstep
+        let jittered = Point::new(
+            x + self.scale * NoisePattern::perlin(x, y, z),
+            y + self.scale * NoisePattern::perlin(x, y, z + 1.0),
+            z + self.scale * NoisePattern::perlin(x, y, z + 2.0),
+        );
+        self.pattern.nested_pattern_at(&jittered)
+    }
+}
+
+// Average of two patterns, each evaluated with its own transform on top of the blend's
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlendPattern {
+    a: Arc<Pattern>,
+    b: Arc<Pattern>,
+}
+
+impl BlendPattern {
+    pub fn new(a: Pattern, b: Pattern) -> Self {
+        Self {
+            a: Arc::new(a),
+            b: Arc::new(b),
+        }
+    }
+
+    pub fn pattern_at(&self, point: &Point) -> Color {
+        (self.a.nested_pattern_at(point) + self.b.nested_pattern_at(point)) * 0.5
+    }
+}
+
+// Checkers laid out in (u, v) space, width across and height up, so they follow the
+// surface instead of cutting through it like the 3D checker pattern
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct UvCheckerPattern {
+    width: usize,
+    height: usize,
+    a: Color,
+    b: Color,
+}
+
+impl UvCheckerPattern {
+    pub fn new(width: usize, height: usize, a: Color, b: Color) -> Self {
+        Self {
+            width,
+            height,
+            a,
+            b,
+        }
+    }
+
+    pub fn pattern_at_uv(&self, u: f64, v: f64) -> Color {
+        let u2 = (u * self.width as f64).floor();
+        let v2 = (v * self.height as f64).floor();
+        if (u2 + v2).rem_euclid(2.0) == 0.0 {
+            self.a
+        } else {
+            self.b
+        }
+    }
+
+    pub fn pattern_at(&self, point: &Point) -> Color {
+        let (u, v) = Sphere::uv_at(point);
+        self.pattern_at_uv(u, v)
+    }
+}
+
+// How a texture maps u and v outside of [0, 1] back onto the image
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum WrapMode {
+    Clamp, // Holds the edge pixels
+    #[default]
+    Repeat, // Tiles the image
+    Mirror, // Tiles the image, flipping every other copy
+}
+
+impl WrapMode {
+    fn wrap(&self, t: f64) -> f64 {
+        if (0.0..=1.0).contains(&t) {
+            return t;
+        }
+        match self {
+            WrapMode::Clamp => t.clamp(0.0, 1.0),
+            WrapMode::Repeat => t.rem_euclid(1.0),
+            WrapMode::Mirror => 1.0 - (t.rem_euclid(2.0) - 1.0).abs(),
+        }
+    }
+}
+
+// An image wrapped onto a surface through (u, v) coordinates. The image is shared so
+// cloning materials doesn't copy the pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TexturePattern {
+    canvas: Arc<Canvas>,
+    wrap: WrapMode,
+}
+
+impl TexturePattern {
+    pub fn new(canvas: Canvas) -> Self {
+        Self {
+            canvas: Arc::new(canvas),
+            wrap: WrapMode::default(),
+        }
+    }
+
+    pub fn with_wrap(self, wrap: WrapMode) -> Self {
+        Self { wrap, ..self }
+    }
+
+    // Nearest pixel to (u, v). v runs up the image, so it's flipped into canvas rows.
+    pub fn pattern_at_uv(&self, u: f64, v: f64) -> Color {
+        let (width, height) = self.canvas.dimensions();
+        let u = self.wrap.wrap(u);
+        let v = 1.0 - self.wrap.wrap(v);
+        let x = (u * (width.max(1) - 1) as f64).round() as usize;
+        let y = (v * (height.max(1) - 1) as f64).round() as usize;
+        self.canvas.pixel_at((x, y)).unwrap_or_else(Color::black)
+    }
+
+    // Without a shape to ask for its mapping the image is wrapped like a sphere
+    pub fn pattern_at(&self, point: &Point) -> Color {
+        let (u, v) = Sphere::uv_at(point);
+        self.pattern_at_uv(u, v)
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::{CheckerPattern, GradientPattern, RingPattern, StripePattern};
+    use super::{
+        CheckerPattern, GradientMode, GradientPattern, MarblePattern, RingPattern, StripePattern,
+        TexturePattern, UvCheckerPattern, WoodPattern, WrapMode,
+    };
 
-    use crate::draw::color::Color;
+    use crate::draw::{canvas::Canvas, color::Color};
     use crate::math::{point::Point, tuple::Tuple};
+    use crate::render::pattern::Pattern;
+
+    fn white() -> Pattern {
+        Pattern::new_solid(Color::white())
+    }
+
+    fn black() -> Pattern {
+        Pattern::new_solid(Color::black())
+    }
 
     #[test]
     fn stripe_pattern_is_constant_in_y() {
-        let pat = StripePattern {
-            a: Color::white(),
-            b: Color::black(),
-        };
+        let pat = StripePattern::new(white(), black());
         assert_eq!(pat.pattern_at(&Point::new(0.0, 0.0, 0.0)), Color::white());
         assert_eq!(pat.pattern_at(&Point::new(0.0, 1.0, 0.0)), Color::white());
         assert_eq!(pat.pattern_at(&Point::new(0.0, 2.0, 0.0)), Color::white());
@@ -257,10 +499,7 @@ mod test {
 
     #[test]
     fn stripe_pattern_is_constant_in_z() {
-        let pat = StripePattern {
-            a: Color::white(),
-            b: Color::black(),
-        };
+        let pat = StripePattern::new(white(), black());
         assert_eq!(pat.pattern_at(&Point::new(0.0, 0.0, 0.0)), Color::white());
         assert_eq!(pat.pattern_at(&Point::new(0.0, 0.0, 1.0)), Color::white());
         assert_eq!(pat.pattern_at(&Point::new(0.0, 0.0, 2.0)), Color::white());
@@ -268,10 +507,7 @@ mod test {
 
     #[test]
     fn stripe_pattern_alternates_in_x() {
-        let pat = StripePattern {
-            a: Color::white(),
-            b: Color::black(),
-        };
+        let pat = StripePattern::new(white(), black());
         assert_eq!(pat.pattern_at(&Point::new(0.0, 0.0, 0.0)), Color::white());
         assert_eq!(pat.pattern_at(&Point::new(0.9, 0.0, 0.0)), Color::white());
         assert_eq!(pat.pattern_at(&Point::new(1.0, 0.0, 0.0)), Color::black());
@@ -282,10 +518,7 @@ mod test {
 
     #[test]
     fn gradient_pattern_linearly_interpolates_between_colors() {
-        let pat = GradientPattern {
-            a: Color::white(),
-            b: Color::black(),
-        };
+        let pat = GradientPattern::new(white(), black());
 
         assert_eq!(pat.pattern_at(&Point::new(0.0, 0.0, 0.0)), Color::white());
         assert_eq!(
@@ -302,12 +535,34 @@ mod test {
         );
     }
 
+    #[test]
+    fn gradient_pattern_modes_outside_of_0_to_1() {
+        let pat = GradientPattern::new(white(), black());
+        let grey = Color::new(0.5, 0.5, 0.5);
+        let before = Point::new(-0.5, 0.0, 0.0);
+        let after = Point::new(1.5, 0.0, 0.0);
+
+        assert_eq!(pat.pattern_at(&before), Color::white());
+        assert_eq!(pat.pattern_at(&after), Color::black());
+
+        let pat = pat.with_mode(GradientMode::Repeat);
+        assert_eq!(pat.pattern_at(&before), grey);
+        assert_eq!(
+            pat.pattern_at(&Point::new(1.25, 0.0, 0.0)),
+            Color::new(0.75, 0.75, 0.75)
+        );
+
+        let pat = pat.with_mode(GradientMode::Mirror);
+        assert_eq!(pat.pattern_at(&before), grey);
+        assert_eq!(
+            pat.pattern_at(&Point::new(1.25, 0.0, 0.0)),
+            Color::new(0.25, 0.25, 0.25)
+        );
+    }
+
     #[test]
     fn ring_pattern_should_extend_in_both_x_and_z() {
-        let pat = RingPattern {
-            a: Color::white(),
-            b: Color::black(),
-        };
+        let pat = RingPattern::new(white(), black());
 
         assert_eq!(pat.pattern_at(&Point::new(0.0, 0.0, 0.0)), Color::white());
         assert_eq!(pat.pattern_at(&Point::new(01.0, 0.0, 0.0)), Color::black());
@@ -320,10 +575,7 @@ mod test {
 
     #[test]
     fn checker_pattern_should_repeat_in_x() {
-        let pat = CheckerPattern {
-            a: Color::white(),
-            b: Color::black(),
-        };
+        let pat = CheckerPattern::new(white(), black());
 
         assert_eq!(pat.pattern_at(&Point::new(0.0, 0.0, 0.0)), Color::white());
         assert_eq!(pat.pattern_at(&Point::new(0.99, 0.0, 0.0)), Color::white());
@@ -332,10 +584,7 @@ mod test {
 
     #[test]
     fn checker_pattern_should_repeat_in_y() {
-        let pat = CheckerPattern {
-            a: Color::white(),
-            b: Color::black(),
-        };
+        let pat = CheckerPattern::new(white(), black());
 
         assert_eq!(pat.pattern_at(&Point::new(0.0, 0.0, 0.0)), Color::white());
         assert_eq!(pat.pattern_at(&Point::new(0.0, 0.99, 0.0)), Color::white());
@@ -344,13 +593,104 @@ mod test {
 
     #[test]
     fn checker_pattern_should_repeat_in_z() {
-        let pat = CheckerPattern {
-            a: Color::white(),
-            b: Color::black(),
-        };
+        let pat = CheckerPattern::new(white(), black());
 
         assert_eq!(pat.pattern_at(&Point::new(0.0, 0.0, 0.0)), Color::white());
         assert_eq!(pat.pattern_at(&Point::new(0.0, 0.0, 0.99)), Color::white());
         assert_eq!(pat.pattern_at(&Point::new(0.0, 0.0, 1.01)), Color::black());
     }
+
+    fn between(color: Color, a: Color, b: Color) -> bool {
+        let (r, g, bl) = color.as_tuple();
+        let (lo, hi) = (a.as_tuple(), b.as_tuple());
+        let within = |v: f64, x: f64, y: f64| v >= x.min(y) - 1e-9 && v <= x.max(y) + 1e-9;
+        within(r, lo.0, hi.0) && within(g, lo.1, hi.1) && within(bl, lo.2, hi.2)
+    }
+
+    #[test]
+    fn marble_and_wood_stay_between_their_colors_and_vary() {
+        let a = Color::new(0.9, 0.85, 0.8);
+        let b = Color::new(0.2, 0.1, 0.05);
+        let marble = MarblePattern::new(a, b, 2.0);
+        let wood = WoodPattern::new(a, b, 0.3);
+
+        let points: Vec<Point> = (0..50)
+            .map(|i| {
+                let i = i as f64;
+                Point::new(i * 0.37, i * 0.11 - 2.0, i * 0.23 + 0.5)
+            })
+            .collect();
+
+        let marble_colors: Vec<Color> = points.iter().map(|p| marble.pattern_at(p)).collect();
+        let wood_colors: Vec<Color> = points.iter().map(|p| wood.pattern_at(p)).collect();
+
+        for colors in [marble_colors, wood_colors] {
+            assert!(colors.iter().all(|c| between(*c, a, b)));
+            assert!(colors.iter().any(|c| *c != colors[0]));
+        }
+    }
+
+    fn test_texture() -> TexturePattern {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.set_pixel((0, 0), &Color::red());
+        canvas.set_pixel((1, 0), &Color::green());
+        canvas.set_pixel((0, 1), &Color::blue());
+        canvas.set_pixel((1, 1), &Color::white());
+        TexturePattern::new(canvas)
+    }
+
+    #[test]
+    fn texture_pattern_samples_the_nearest_pixel() {
+        let pat = test_texture();
+        // The top row of the image is v = 1
+        assert_eq!(pat.pattern_at_uv(0.1, 0.9), Color::red());
+        assert_eq!(pat.pattern_at_uv(0.9, 0.9), Color::green());
+        assert_eq!(pat.pattern_at_uv(0.1, 0.1), Color::blue());
+        assert_eq!(pat.pattern_at_uv(0.9, 0.1), Color::white());
+        assert_eq!(
+            pat.with_wrap(WrapMode::Clamp).pattern_at_uv(-3.0, 7.0),
+            Color::red()
+        );
+    }
+
+    #[test]
+    fn texture_wrap_modes_past_the_right_edge() {
+        // Red, green and blue columns, u = 0.5 lands on the green one
+        let mut canvas = Canvas::new(3, 1);
+        canvas.set_pixel((0, 0), &Color::red());
+        canvas.set_pixel((1, 0), &Color::green());
+        canvas.set_pixel((2, 0), &Color::blue());
+        let pat = TexturePattern::new(canvas);
+
+        assert_eq!(pat.pattern_at_uv(1.5, 0.5), Color::green());
+        let clamped = pat.clone().with_wrap(WrapMode::Clamp);
+        assert_eq!(clamped.pattern_at_uv(1.5, 0.5), Color::blue());
+        let mirrored = pat.clone().with_wrap(WrapMode::Mirror);
+        assert_eq!(mirrored.pattern_at_uv(1.5, 0.5), Color::green());
+
+        // Repeat starts over at red, mirror runs back from blue
+        assert_eq!(pat.pattern_at_uv(1.1, 0.5), Color::red());
+        assert_eq!(mirrored.pattern_at_uv(1.1, 0.5), Color::blue());
+    }
+
+    #[test]
+    fn texture_pattern_wraps_like_a_sphere_by_default() {
+        let pat = test_texture();
+        let half = 2.0_f64.sqrt() / 2.0;
+        assert_eq!(pat.pattern_at(&Point::new(half, half, 0.0)), Color::red());
+        assert_eq!(
+            pat.pattern_at(&Point::new(-half, -half, 0.0)),
+            Color::white()
+        );
+    }
+
+    #[test]
+    fn checker_pattern_in_2d() {
+        let pat = UvCheckerPattern::new(2, 2, Color::black(), Color::white());
+        assert_eq!(pat.pattern_at_uv(0.0, 0.0), Color::black());
+        assert_eq!(pat.pattern_at_uv(0.5, 0.0), Color::white());
+        assert_eq!(pat.pattern_at_uv(0.0, 0.5), Color::white());
+        assert_eq!(pat.pattern_at_uv(0.5, 0.5), Color::black());
+        assert_eq!(pat.pattern_at_uv(1.0, 1.0), Color::black());
+    }
 }
@@ -0,0 +1,86 @@
+use super::{environment::Environment, world::REMAINING};
+
+// Everything that controls how a world is rendered, as opposed to what is in it. The
+// defaults match a freshly created World, so they reproduce plain render output.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderConfig {
+    pub max_reflection_depth: usize,
+    pub max_refraction_depth: usize,
+    pub samples: usize, // Rays per pixel, 1 traces a single ray through the center
+    pub light_sample_count: Option<usize>,
+    pub firefly_clamp: Option<f64>,
+    pub russian_roulette_depth: Option<usize>,
+    pub seed: u64,
+    pub environment: Environment,
+    pub srgb: bool, // sRGB encode the output instead of writing linear bytes
+}
+
+impl RenderConfig {
+    pub fn new() -> Self {
+        Self {
+            max_reflection_depth: REMAINING,
+            max_refraction_depth: REMAINING,
+            samples: 1,
+            light_sample_count: None,
+            firefly_clamp: None,
+            russian_roulette_depth: None,
+            seed: 0,
+            environment: Environment::default(),
+            srgb: false,
+        }
+    }
+
+    pub fn with_max_depth(self, reflection: usize, refraction: usize) -> Self {
+        Self {
+            max_reflection_depth: reflection,
+            max_refraction_depth: refraction,
+            ..self
+        }
+    }
+
+    pub fn with_samples(self, samples: usize) -> Self {
+        Self { samples, ..self }
+    }
+
+    pub fn with_light_sample_count(self, light_sample_count: Option<usize>) -> Self {
+        Self {
+            light_sample_count,
+            ..self
+        }
+    }
+
+    pub fn with_firefly_clamp(self, firefly_clamp: Option<f64>) -> Self {
+        Self {
+            firefly_clamp,
+            ..self
+        }
+    }
+
+    pub fn with_russian_roulette_depth(self, russian_roulette_depth: Option<usize>) -> Self {
+        Self {
+            russian_roulette_depth,
+            ..self
+        }
+    }
+
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+
+    pub fn with_environment(self, environment: Environment) -> Self {
+        Self {
+            environment,
+            ..self
+        }
+    }
+
+    pub fn with_srgb(self, srgb: bool) -> Self {
+        Self { srgb, ..self }
+    }
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
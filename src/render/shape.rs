@@ -1,6 +1,6 @@
 use crate::{
     math::{point::Point, ray::Ray, vector::Vector},
-    render::{intersections::Intersections, object::Object},
+    render::{bounding_box::BoundingBox, intersections::Intersections, object::Object},
 };
 
 use crate::render::shapes::{plane::Plane, sphere::Sphere, test_shape::TestShape};
@@ -8,8 +8,8 @@ use crate::render::shapes::{plane::Plane, sphere::Sphere, test_shape::TestShape}
 use super::{
     intersections::Intersection,
     shapes::{
-        cone::Cone, cube::Cube, cylinder::Cylinder, group::Group, smooth_triangle::SmoothTriangle,
-        triangle::Triangle,
+        cone::Cone, csg::Csg, cube::Cube, cylinder::Cylinder, disk::Disk, group::Group,
+        indexed_mesh::IndexedMesh, smooth_triangle::SmoothTriangle, triangle::Triangle,
     },
 };
 
@@ -24,6 +24,9 @@ pub enum Shape {
     Group(Group),
     Triangle(Triangle),
     SmoothTriangle(SmoothTriangle),
+    IndexedMesh(IndexedMesh),
+    Csg(Csg),
+    Disk(Disk),
 }
 
 impl Shape {
@@ -38,6 +41,28 @@ impl Shape {
             Self::Group(g) => g.normal_at(local_point),
             Self::Triangle(t) => t.normal_at(local_point),
             Self::SmoothTriangle(st) => st.normal_at(local_point, int),
+            Self::IndexedMesh(m) => m.normal_at(local_point, int),
+            Self::Csg(c) => c.normal_at(local_point),
+            Self::Disk(d) => d.normal_at(local_point),
+        }
+    }
+
+    // Texture coordinates for a point in object space. Shapes without a mapping of
+    // their own, and triangles without vertex coordinates, fall back to the spherical one.
+    pub fn uv_at(&self, local_point: &Point) -> (f64, f64) {
+        let vertex_uvs = match self {
+            Self::Triangle(t) => t.uv_at(local_point),
+            Self::SmoothTriangle(st) => st.uv_at(local_point),
+            _ => None,
+        };
+        if let Some(uv) = vertex_uvs {
+            return uv;
+        }
+        match self {
+            Self::Plane(_) | Self::Disk(_) => Plane::uv_at(local_point),
+            Self::Cube(_) => Cube::uv_at(local_point),
+            Self::Cylinder(_) | Self::Cone(_) => Cylinder::uv_at(local_point),
+            _ => Sphere::uv_at(local_point),
         }
     }
 
@@ -57,11 +82,31 @@ impl Shape {
             Self::Group(g) => g.intersect(local_ray, obj, intersections),
             Self::Triangle(t) => t.intersect(local_ray, obj, intersections),
             Self::SmoothTriangle(st) => st.intersect(local_ray, obj, intersections),
+            Self::IndexedMesh(m) => m.intersect(local_ray, obj, intersections),
+            Self::Csg(c) => c.intersect(local_ray, obj, intersections),
+            Self::Disk(d) => d.intersect(local_ray, obj, intersections),
+        }
+    }
+
+    pub fn bounds(&self) -> BoundingBox {
+        match self {
+            Self::TestShape(ts) => ts.bounds(),
+            Self::Sphere(s) => s.bounds(),
+            Self::Plane(_) => Plane::bounds(),
+            Self::Cube(c) => c.bounds(),
+            Self::Cylinder(c) => c.bounds(),
+            Self::Cone(c) => c.bounds(),
+            Self::Group(g) => g.bounds(),
+            Self::Triangle(t) => t.bounds(),
+            Self::SmoothTriangle(st) => st.bounds(),
+            Self::IndexedMesh(m) => m.bounds(),
+            Self::Csg(c) => c.bounds(),
+            Self::Disk(d) => d.bounds(),
         }
     }
 
     pub fn skip_world_to_local(&self) -> bool {
-        matches!(self, Shape::Group(_))
+        matches!(self, Shape::Group(_) | Shape::Csg(_))
     }
 
     pub fn as_triangle(&self) -> Option<Triangle> {
@@ -77,4 +122,11 @@ impl Shape {
             _ => None,
         }
     }
+
+    pub fn as_indexed_mesh(&self) -> Option<&IndexedMesh> {
+        match &self {
+            Self::IndexedMesh(m) => Some(m),
+            _ => None,
+        }
+    }
 }
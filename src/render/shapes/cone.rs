@@ -2,14 +2,15 @@ use std::f64::INFINITY;
 
 use crate::{
     math::{
-        epsilon::{ApproxEq, EPSILON},
+        epsilon::{ApproxEq, EPSILON, GEOMETRY_EPSILON},
         point::Point,
         ray::Ray,
         tuple::Tuple,
         vector::Vector,
     },
     render::{
-        intersections::{Intersection, Intersections},
+        bounding_box::BoundingBox,
+        intersections::{Intersection, Intersections, LocalTs},
         object::Object,
     },
 };
@@ -31,19 +32,25 @@ impl Cone {
     }
 
     pub fn intersect<'a>(&self, ray: &Ray, obj: &'a Object, intersections: &mut Intersections<'a>) {
+        for t in self.local_intersect_ts(ray) {
+            intersections.push(Intersection::new(t, obj));
+        }
+    }
+
+    pub fn local_intersect_ts(&self, ray: &Ray) -> LocalTs {
+        let mut ts = LocalTs::new();
         let a = ray.direction.x().powi(2) - ray.direction.y().powi(2) + ray.direction.z().powi(2);
         let b = 2.0
             * (ray.origin.x() * ray.direction.x() - ray.origin.y() * ray.direction.y()
                 + ray.origin.z() * ray.direction.z());
         let c = ray.origin.x().powi(2) - ray.origin.y().powi(2) + ray.origin.z().powi(2);
         if a.approx_eq(0.0) && !b.approx_eq(0.0) {
-            let t = c / (-2.0 * b);
-            intersections.push(Intersection::new(t, &obj));
+            ts.push(c / (-2.0 * b));
         } else {
             let disc = b.powi(2) - 4.0 * a * c;
 
             if disc < 0.0 {
-                return;
+                return ts;
             }
 
             let double_a = 2.0 * a;
@@ -52,15 +59,16 @@ impl Cone {
 
             let y0 = ray.origin.y() + t0 * ray.direction.y();
             if self.min < y0 && y0 < self.max {
-                intersections.push(Intersection::new(t0, &obj));
+                ts.push(t0);
             }
 
             let y1 = ray.origin.y() + t1 * ray.direction.y();
             if self.min < y1 && y1 < self.max {
-                intersections.push(Intersection::new(t1, &obj));
+                ts.push(t1);
             }
         }
-        self.intersect_caps(ray, obj, intersections);
+        self.intersect_caps(ray, &mut ts);
+        ts
     }
 
     pub fn normal_at(&self, point: &Point) -> Vector {
@@ -83,24 +91,19 @@ impl Cone {
         }
     }
 
-    pub fn intersect_caps<'a>(
-        &self,
-        ray: &Ray,
-        obj: &'a Object,
-        intersections: &mut Intersections<'a>,
-    ) {
-        if !self.closed || ray.direction.y().approx_eq(0.0) {
+    fn intersect_caps(&self, ray: &Ray, ts: &mut LocalTs) {
+        if !self.closed || ray.direction.y().abs() < GEOMETRY_EPSILON {
             return;
         }
 
         let t = (self.min - ray.origin.y()) / ray.direction.y();
         if Self::check_cap(ray, t, self.min) {
-            intersections.push(Intersection::new(t, &obj));
+            ts.push(t);
         }
 
         let t = (self.max - ray.origin.y()) / ray.direction.y();
         if Self::check_cap(ray, t, self.max) {
-            intersections.push(Intersection::new(t, &obj));
+            ts.push(t);
         }
     }
 
@@ -111,6 +114,14 @@ impl Cone {
         (x.powi(2) + z.powi(2)) <= radius.powi(2)
     }
 
+    pub fn bounds(&self) -> BoundingBox {
+        let radius = self.min.abs().max(self.max.abs());
+        BoundingBox::new(
+            Point::new(-radius, self.min, -radius),
+            Point::new(radius, self.max, radius),
+        )
+    }
+
     pub fn with_min(self, min: f64) -> Self {
         Self { min, ..self }
     }
@@ -128,7 +139,13 @@ impl Cone {
 mod test {
     use super::Cone;
     use crate::{
-        math::{epsilon::ApproxEq, point::Point, ray::Ray, tuple::Tuple, vector::Vector},
+        math::{
+            epsilon::{ApproxEq, GEOMETRY_EPSILON},
+            point::Point,
+            ray::Ray,
+            tuple::Tuple,
+            vector::Vector,
+        },
         render::{intersections::Intersections, object::Object},
     };
 
@@ -232,4 +249,16 @@ mod test {
 
         test_runner(test, tests);
     }
+
+    #[test]
+    fn grazing_ray_at_the_geometry_epsilon_boundary_on_the_caps() {
+        let obj = Object::new_test_shape();
+        let c = Cone::new().with_min(-2.0).with_max(-1.0).with_closed(true);
+        for (dy, len) in [(GEOMETRY_EPSILON, 2), (GEOMETRY_EPSILON / 2.0, 0)] {
+            let r = Ray::new(Point::new(0.5, 0.0, 0.0), Vector::new(0.0, -dy, 0.0));
+            let mut ints = Intersections::new();
+            c.intersect(&r, &obj, &mut ints);
+            assert_eq!(ints.len(), len);
+        }
+    }
 }
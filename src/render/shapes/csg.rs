@@ -0,0 +1,301 @@
+use crate::{
+    math::{
+        epsilon::GEOMETRY_EPSILON, point::Point, ray::Ray, transformation::Transformable,
+        tuple::Tuple, vector::Vector,
+    },
+    render::{
+        bounding_box::BoundingBox,
+        intersections::{Intersection, Intersections},
+        object::Object,
+        shape::Shape,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsgOp {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl CsgOp {
+    // Whether a hit on the left (lhit) or right child survives, given whether the ray
+    // is currently inside the left (inl) and right (inr) children
+    pub fn intersection_allowed(&self, lhit: bool, inl: bool, inr: bool) -> bool {
+        match self {
+            Self::Union => (lhit && !inr) || (!lhit && !inl),
+            Self::Intersection => (lhit && inr) || (!lhit && inl),
+            Self::Difference => (lhit && !inr) || (!lhit && inl),
+        }
+    }
+}
+
+// Combines two solids. Like groups, the CSG's transform is baked into both children.
+// A plane child stands for the half-space below it, so a plane can cut a solid in two.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Csg {
+    op: CsgOp,
+    left: Box<Object>,
+    right: Box<Object>,
+}
+
+impl Csg {
+    pub fn new(op: CsgOp, left: Object, right: Object) -> Self {
+        Self {
+            op,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    pub fn divide(self, threshold: usize) -> Self {
+        Self::new(
+            self.op,
+            self.left.divide(threshold),
+            self.right.divide(threshold),
+        )
+    }
+
+    pub fn op(&self) -> CsgOp {
+        self.op
+    }
+
+    pub fn left(&self) -> &Object {
+        &self.left
+    }
+
+    pub fn right(&self) -> &Object {
+        &self.right
+    }
+
+    pub fn intersect<'a>(
+        &'a self,
+        ray: &Ray,
+        _: &'a Object,
+        intersections: &mut Intersections<'a>,
+    ) {
+        let mut left = Intersections::new();
+        self.left.intersect(ray, &mut left);
+        let mut right = Intersections::new();
+        self.right.intersect(ray, &mut right);
+        let filtered = self.filter_intersections_from(
+            &left,
+            &right,
+            starts_inside(&self.left, ray),
+            starts_inside(&self.right, ray),
+        );
+        intersections.merge(filtered);
+        intersections.count_tests(left.tests() + right.tests());
+    }
+
+    // Walks the hits on both children in order, tracking whether the ray is inside
+    // each one, and keeps only the hits on the combined surface
+    pub fn filter_intersections<'a>(
+        &self,
+        left: &Intersections<'a>,
+        right: &Intersections<'a>,
+    ) -> Intersections<'a> {
+        self.filter_intersections_from(left, right, false, false)
+    }
+
+    // Like filter_intersections, for a ray that starts out inside either child
+    fn filter_intersections_from<'a>(
+        &self,
+        left: &Intersections<'a>,
+        right: &Intersections<'a>,
+        mut inl: bool,
+        mut inr: bool,
+    ) -> Intersections<'a> {
+        let mut tagged: Vec<(bool, Intersection<'a>)> = (0..left.len())
+            .map(|i| (true, left[i].clone()))
+            .chain((0..right.len()).map(|i| (false, right[i].clone())))
+            .collect();
+        tagged.sort_by(|(_, a), (_, b)| a.cmp(b));
+
+        let mut kept = vec![];
+        for (lhit, int) in tagged {
+            if self.op.intersection_allowed(lhit, inl, inr) {
+                kept.push(int);
+            }
+            if lhit {
+                inl = !inl;
+            } else {
+                inr = !inr;
+            }
+        }
+        Intersections::new().with_intersections(kept)
+    }
+
+    // Children already have the CSG's transform baked in
+    pub fn bounds(&self) -> BoundingBox {
+        match self.op {
+            CsgOp::Difference => self.left.bounds(),
+            _ => self.left.bounds().union(&self.right.bounds()),
+        }
+    }
+
+    pub fn normal_at(&self, _point: &Point) -> Vector {
+        unreachable!()
+    }
+}
+
+// Whether the ray is inside the child before its first hit. Closed solids are always
+// entered from outside. A plane's half-space has no far side to exit through, so a ray
+// heading up through the plane, or running parallel below it, starts out inside.
+fn starts_inside(child: &Object, ray: &Ray) -> bool {
+    match child.get_shape() {
+        Shape::Plane(_) => {
+            let local = ray.with_transform(child.get_transform_inv());
+            if local.direction.y().abs() < GEOMETRY_EPSILON {
+                local.origin.y() < 0.0
+            } else {
+                local.direction.y() > 0.0
+            }
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::math::{
+        point::Point, ray::Ray, transformation::Transformable, tuple::Tuple, vector::Vector,
+    };
+
+    #[test]
+    fn csg_is_created_with_an_operation_and_two_shapes() {
+        let s1 = Object::new_sphere();
+        let s2 = Object::new_cube();
+        let c = Csg::new(CsgOp::Union, s1.clone(), s2.clone());
+        assert_eq!(c.op(), CsgOp::Union);
+        assert_eq!(c.left(), &s1);
+        assert_eq!(c.right(), &s2);
+    }
+
+    #[test]
+    fn evaluating_the_rule_for_a_csg_operation() {
+        let table = [
+            (
+                CsgOp::Union,
+                [false, true, false, true, false, false, true, true],
+            ),
+            (
+                CsgOp::Intersection,
+                [true, false, true, false, true, true, false, false],
+            ),
+            (
+                CsgOp::Difference,
+                [false, true, false, true, true, true, false, false],
+            ),
+        ];
+        // Rows run through (lhit, inl, inr) from (true, true, true) to (false, false, false)
+        for (op, want) in table {
+            for (row, want) in want.iter().enumerate() {
+                let lhit = row < 4;
+                let inl = row % 4 < 2;
+                let inr = row % 2 == 0;
+                assert_eq!(
+                    op.intersection_allowed(lhit, inl, inr),
+                    *want,
+                    "{:?} lhit {} inl {} inr {}",
+                    op,
+                    lhit,
+                    inl,
+                    inr
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn filtering_a_list_of_intersections() {
+        let s1 = Object::new_sphere();
+        let s2 = Object::new_cube();
+        for (op, x0, x1) in [
+            (CsgOp::Union, 0, 3),
+            (CsgOp::Intersection, 1, 2),
+            (CsgOp::Difference, 0, 1),
+        ] {
+            let c = Csg::new(op, s1.clone(), s2.clone());
+            let left = Intersections::new().with_intersections(vec![
+                Intersection::new(1.0, c.left()),
+                Intersection::new(3.0, c.left()),
+            ]);
+            let right = Intersections::new().with_intersections(vec![
+                Intersection::new(2.0, c.right()),
+                Intersection::new(4.0, c.right()),
+            ]);
+            let all = [1.0, 2.0, 3.0, 4.0];
+
+            let got = c.filter_intersections(&left, &right);
+            assert_eq!(got.len(), 2);
+            assert_eq!(got[0].t(), all[x0]);
+            assert_eq!(got[1].t(), all[x1]);
+        }
+    }
+
+    #[test]
+    fn a_ray_misses_a_csg_object() {
+        let c = Object::new_csg(CsgOp::Union, Object::new_sphere(), Object::new_cube());
+        let r = Ray::new(Point::new(0.0, 2.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let mut ints = Intersections::new();
+        c.intersect(&r, &mut ints);
+        assert!(ints.is_empty());
+    }
+
+    #[test]
+    fn a_ray_hits_a_csg_object() {
+        let s2 = Object::new_sphere().translate(0.0, 0.0, 0.5);
+        let c = Object::new_csg(CsgOp::Union, Object::new_sphere(), s2);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let mut ints = Intersections::new();
+        c.intersect(&r, &mut ints);
+        assert_eq!(ints.len(), 2);
+        assert_eq!(ints[0].t(), 4.0);
+        assert_eq!(ints[1].t(), 6.5);
+    }
+
+    #[test]
+    fn transforming_a_csg_moves_both_children() {
+        let c = Object::new_csg(
+            CsgOp::Difference,
+            Object::new_cube(),
+            Object::new_sphere().scale(0.5, 0.5, 0.5),
+        )
+        .translate(0.0, 0.0, 10.0);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let mut ints = Intersections::new();
+        c.intersect(&r, &mut ints);
+
+        // Front face, the walls of the hollow and the back face
+        let ts: Vec<f64> = ints.iter().map(|int| int.t()).collect();
+        assert_eq!(ts, vec![14.0, 14.5, 15.5, 16.0]);
+    }
+
+    #[test]
+    fn a_plane_cuts_a_solid_as_a_half_space() {
+        // The sphere less everything below y = 0 leaves the upper hemisphere
+        let dome = Object::new_csg(CsgOp::Difference, Object::new_sphere(), Object::new_plane());
+        let ts = |origin: Point, direction: Vector| {
+            let mut ints = Intersections::new();
+            dome.intersect(&Ray::new(origin, direction), &mut ints);
+            ints.iter().map(|int| int.t()).collect::<Vec<f64>>()
+        };
+
+        // From above the ray meets the dome and then the flat cut
+        let down = ts(Point::new(0.0, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        assert_eq!(down, vec![4.0, 5.0]);
+
+        // From below it meets the flat cut first
+        let up = ts(Point::new(0.0, -5.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(up, vec![5.0, 6.0]);
+
+        // Rays through the removed half never touch the plane but still miss
+        let below = ts(Point::new(-5.0, -0.5, 0.0), Vector::new(1.0, 0.0, 0.0));
+        assert!(below.is_empty());
+        let above = ts(Point::new(-5.0, 0.5, 0.0), Vector::new(1.0, 0.0, 0.0));
+        assert_eq!(above.len(), 2);
+    }
+}
@@ -2,14 +2,15 @@ use std::f64::INFINITY;
 
 use crate::{
     math::{
-        epsilon::{ApproxEq, EPSILON},
+        epsilon::{ApproxEq, GEOMETRY_EPSILON},
         point::Point,
         ray::Ray,
         tuple::Tuple,
         vector::Vector,
     },
     render::{
-        intersections::{Intersection, Intersections},
+        bounding_box::BoundingBox,
+        intersections::{Intersection, Intersections, LocalTs},
         object::Object,
     },
 };
@@ -22,6 +23,10 @@ impl Cube {
         Self {}
     }
 
+    pub fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0))
+    }
+
     pub fn normal_at(&self, point: &Point) -> Vector {
         let x = point.x().abs();
         let y = point.y().abs();
@@ -37,7 +42,33 @@ impl Cube {
         }
     }
 
+    // Cube mapping: each face gets the whole texture, picked by the largest coordinate
+    pub fn uv_at(point: &Point) -> (f64, f64) {
+        let (x, y, z) = (point.x(), point.y(), point.z());
+        let max_c = x.abs().max(y.abs()).max(z.abs());
+        let (u, v) = if max_c == x {
+            (1.0 - z, y + 1.0)
+        } else if max_c == -x {
+            (z + 1.0, y + 1.0)
+        } else if max_c == y {
+            (x + 1.0, 1.0 - z)
+        } else if max_c == -y {
+            (x + 1.0, z + 1.0)
+        } else if max_c == z {
+            (x + 1.0, y + 1.0)
+        } else {
+            (1.0 - x, y + 1.0)
+        };
+        (u.rem_euclid(2.0) / 2.0, v.rem_euclid(2.0) / 2.0)
+    }
+
     pub fn intersect<'a>(&self, ray: &Ray, obj: &'a Object, intersections: &mut Intersections<'a>) {
+        for t in self.local_intersect_ts(ray) {
+            intersections.push(Intersection::new(t, obj));
+        }
+    }
+
+    pub fn local_intersect_ts(&self, ray: &Ray) -> LocalTs {
         let (x_min, x_max) = self.check_axis(ray.origin.x(), ray.direction.x());
         let (y_min, y_max) = self.check_axis(ray.origin.y(), ray.direction.y());
         let (z_min, z_max) = self.check_axis(ray.origin.z(), ray.direction.z());
@@ -45,21 +76,21 @@ impl Cube {
         let t_min = x_min.max(y_min).max(z_min);
         let t_max = x_max.min(y_max).min(z_max);
 
-        if t_max < 0.0 {
-            return;
+        let mut ts = LocalTs::new();
+        if t_max < 0.0 || t_min > t_max {
+            return ts;
         }
 
-        if t_min <= t_max {
-            intersections.push(Intersection::new(t_min, obj));
-            intersections.push(Intersection::new(t_max, obj));
-        }
+        ts.push(t_min);
+        ts.push(t_max);
+        ts
     }
 
     fn check_axis(&self, origin: f64, direction: f64) -> (f64, f64) {
         let tmin_numerator = -1.0 - origin;
         let tmax_numerator = 1.0 - origin;
 
-        let (tmin, tmax) = if direction.abs() >= EPSILON {
+        let (tmin, tmax) = if direction.abs() >= GEOMETRY_EPSILON {
             let tmin = tmin_numerator / direction;
             let tmax = tmax_numerator / direction;
             (tmin, tmax)
@@ -82,7 +113,13 @@ mod test {
 
     use super::Cube;
     use crate::{
-        math::{epsilon::ApproxEq, point::Point, ray::Ray, tuple::Tuple, vector::Vector},
+        math::{
+            epsilon::{ApproxEq, GEOMETRY_EPSILON},
+            point::Point,
+            ray::Ray,
+            tuple::Tuple,
+            vector::Vector,
+        },
         render::{intersections::Intersections, object::Object},
     };
 
@@ -141,6 +178,13 @@ mod test {
         assert!(ints_inside[1].t().approx_eq(1.0));
     }
 
+    #[test]
+    fn local_intersect_ts_without_an_object() {
+        let c = Cube::new();
+        let r = Ray::new(Point::new(5.0, 0.5, 0.0), Vector::new(-1.0, 0.0, 0.0));
+        assert_eq!(c.local_intersect_ts(&r).as_slice(), [4.0, 6.0]);
+    }
+
     #[test]
     fn ray_misses_a_cube() {
         let obj = Object::new_test_shape();
@@ -227,4 +271,43 @@ mod test {
         let want = Vector::new(-1.0, 0.0, 0.0);
         assert_eq!(got, want);
     }
+
+    #[test]
+    fn grazing_ray_at_the_geometry_epsilon_boundary() {
+        let obj = Object::new_test_shape();
+        let c = Cube::new();
+        for (dx, len) in [(GEOMETRY_EPSILON, 2), (GEOMETRY_EPSILON / 2.0, 0)] {
+            let r = Ray::new(Point::new(1.5, 0.0, 0.0), Vector::new(-dx, 0.0, 0.0));
+            let mut ints = Intersections::new();
+            c.intersect(&r, &obj, &mut ints);
+            assert_eq!(ints.len(), len);
+        }
+    }
+
+    #[test]
+    fn using_a_cube_mapping_on_each_face() {
+        let tests = [
+            // Front
+            (Point::new(-0.5, 0.5, 1.0), (0.25, 0.75)),
+            (Point::new(0.5, -0.5, 1.0), (0.75, 0.25)),
+            // Back
+            (Point::new(0.5, 0.5, -1.0), (0.25, 0.75)),
+            (Point::new(-0.5, -0.5, -1.0), (0.75, 0.25)),
+            // Left
+            (Point::new(-1.0, 0.5, -0.5), (0.25, 0.75)),
+            (Point::new(-1.0, -0.5, 0.5), (0.75, 0.25)),
+            // Right
+            (Point::new(1.0, 0.5, 0.5), (0.25, 0.75)),
+            (Point::new(1.0, -0.5, -0.5), (0.75, 0.25)),
+            // Up
+            (Point::new(-0.5, 1.0, -0.5), (0.25, 0.75)),
+            (Point::new(0.5, 1.0, 0.5), (0.75, 0.25)),
+            // Down
+            (Point::new(-0.5, -1.0, 0.5), (0.25, 0.75)),
+            (Point::new(0.5, -1.0, -0.5), (0.75, 0.25)),
+        ];
+        for (point, want) in tests {
+            assert_eq!(Cube::uv_at(&point), want, "{:?}", point);
+        }
+    }
 }
@@ -1,15 +1,16 @@
-use std::f64::INFINITY;
+use std::f64::{consts::PI, INFINITY};
 
 use crate::{
     math::{
-        epsilon::{ApproxEq, EPSILON},
+        epsilon::{ApproxEq, EPSILON, GEOMETRY_EPSILON},
         point::Point,
         ray::Ray,
         tuple::Tuple,
         vector::Vector,
     },
     render::{
-        intersections::{Intersection, Intersections},
+        bounding_box::BoundingBox,
+        intersections::{Intersection, Intersections, LocalTs},
         object::Object,
     },
 };
@@ -31,9 +32,16 @@ impl Cylinder {
     }
 
     pub fn intersect<'a>(&self, ray: &Ray, obj: &'a Object, intersections: &mut Intersections<'a>) {
+        for t in self.local_intersect_ts(ray) {
+            intersections.push(Intersection::new(t, obj));
+        }
+    }
+
+    pub fn local_intersect_ts(&self, ray: &Ray) -> LocalTs {
+        let mut ts = LocalTs::new();
         let a = ray.direction.x().powi(2) + ray.direction.z().powi(2);
         if a.approx_eq(0.0) {
-            self.intersect_caps(ray, obj, intersections);
+            self.intersect_caps(ray, &mut ts);
         } else {
             let b = 2.0 * (ray.origin.x() * ray.direction.x() + ray.origin.z() * ray.direction.z());
             let c = ray.origin.x().powi(2) + ray.origin.z().powi(2) - 1.0;
@@ -41,7 +49,7 @@ impl Cylinder {
             let disc = b.powi(2) - 4.0 * a * c;
 
             if disc < 0.0 {
-                return;
+                return ts;
             }
 
             let t0 = (-b - disc.sqrt()) / (2.0 * a);
@@ -51,16 +59,17 @@ impl Cylinder {
 
             let y0 = ray.origin.y() + t0 * ray.direction.y();
             if self.min < y0 && y0 < self.max {
-                intersections.push(Intersection::new(t0, &obj));
+                ts.push(t0);
             }
 
             let y1 = ray.origin.y() + t1 * ray.direction.y();
             if self.min < y1 && y1 < self.max {
-                intersections.push(Intersection::new(t1, &obj));
+                ts.push(t1);
             }
 
-            self.intersect_caps(ray, obj, intersections);
+            self.intersect_caps(ray, &mut ts);
         }
+        ts
     }
 
     pub fn normal_at(&self, point: &Point) -> Vector {
@@ -75,6 +84,20 @@ impl Cylinder {
         }
     }
 
+    // Cylindrical mapping: u runs around the y axis like a sphere, v repeats every unit of y
+    pub fn uv_at(point: &Point) -> (f64, f64) {
+        let theta = point.x().atan2(point.z());
+        let u = (1.0 - (theta / (2.0 * PI) + 0.5)).rem_euclid(1.0);
+        (u, point.y().rem_euclid(1.0))
+    }
+
+    pub fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Point::new(-1.0, self.min, -1.0),
+            Point::new(1.0, self.max, 1.0),
+        )
+    }
+
     pub fn with_min(self, min: f64) -> Self {
         Self { min, ..self }
     }
@@ -94,24 +117,19 @@ impl Cylinder {
         (x.powi(2) + z.powi(2)) <= 1.0
     }
 
-    pub fn intersect_caps<'a>(
-        &self,
-        ray: &Ray,
-        obj: &'a Object,
-        intersections: &mut Intersections<'a>,
-    ) {
-        if !self.closed || ray.direction.y().approx_eq(0.0) {
+    fn intersect_caps(&self, ray: &Ray, ts: &mut LocalTs) {
+        if !self.closed || ray.direction.y().abs() < GEOMETRY_EPSILON {
             return;
         }
 
         let t = (self.min - ray.origin.y()) / ray.direction.y();
         if Self::check_cap(ray, t) {
-            intersections.push(Intersection::new(t, &obj));
+            ts.push(t);
         }
 
         let t = (self.max - ray.origin.y()) / ray.direction.y();
         if Self::check_cap(ray, t) {
-            intersections.push(Intersection::new(t, &obj));
+            ts.push(t);
         }
     }
 }
@@ -122,7 +140,13 @@ mod test {
     use std::f64::INFINITY;
 
     use crate::{
-        math::{epsilon::ApproxEq, point::Point, ray::Ray, tuple::Tuple, vector::Vector},
+        math::{
+            epsilon::{ApproxEq, GEOMETRY_EPSILON},
+            point::Point,
+            ray::Ray,
+            tuple::Tuple,
+            vector::Vector,
+        },
         render::{intersections::Intersections, object::Object},
     };
 
@@ -310,4 +334,41 @@ mod test {
         let got = c.normal_at(&p);
         assert_eq!(got, want);
     }
+
+    #[test]
+    fn grazing_ray_at_the_geometry_epsilon_boundary_on_the_caps() {
+        let obj = Object::new_test_shape();
+        let c = Cylinder::new()
+            .with_min(0.0)
+            .with_max(1.0)
+            .with_closed(true);
+        for (dy, len) in [(GEOMETRY_EPSILON, 2), (GEOMETRY_EPSILON / 2.0, 0)] {
+            let r = Ray::new(Point::new(0.0, 2.0, 0.0), Vector::new(0.0, -dy, 0.0));
+            let mut ints = Intersections::new();
+            c.intersect(&r, &obj, &mut ints);
+            assert_eq!(ints.len(), len);
+        }
+    }
+
+    #[test]
+    fn using_a_cylindrical_mapping_on_a_3d_point() {
+        let half = 2.0_f64.sqrt() / 2.0;
+        let tests = [
+            (Point::new(0.0, 0.0, -1.0), (0.0, 0.0)),
+            (Point::new(0.0, 0.5, -1.0), (0.0, 0.5)),
+            (Point::new(0.0, 1.0, -1.0), (0.0, 0.0)),
+            (Point::new(half, 0.5, -half), (0.125, 0.5)),
+            (Point::new(1.0, 0.5, 0.0), (0.25, 0.5)),
+            (Point::new(half, 0.5, half), (0.375, 0.5)),
+            (Point::new(0.0, -0.25, 1.0), (0.5, 0.75)),
+            (Point::new(-half, 0.5, half), (0.625, 0.5)),
+            (Point::new(-1.0, 1.25, 0.0), (0.75, 0.25)),
+            (Point::new(-half, 0.5, -half), (0.875, 0.5)),
+        ];
+        for (point, (u, v)) in tests {
+            let got = Cylinder::uv_at(&point);
+            assert!(got.0.approx_eq(u), "{:?} u {}", point, got.0);
+            assert!(got.1.approx_eq(v), "{:?} v {}", point, got.1);
+        }
+    }
 }
@@ -0,0 +1,90 @@
+use crate::{
+    math::{epsilon::GEOMETRY_EPSILON, point::Point, ray::Ray, tuple::Tuple, vector::Vector},
+    render::bounding_box::BoundingBox,
+    render::intersections::{Intersection, Intersections},
+    render::object::Object,
+};
+
+// Flat ring in the y = 0 plane around the origin, a plain disk when inner_radius is 0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Disk {
+    pub inner_radius: f64,
+    pub outer_radius: f64,
+}
+
+impl Disk {
+    pub fn new(inner_radius: f64, outer_radius: f64) -> Self {
+        Self {
+            inner_radius,
+            outer_radius,
+        }
+    }
+
+    pub fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Point::new(-self.outer_radius, 0.0, -self.outer_radius),
+            Point::new(self.outer_radius, 0.0, self.outer_radius),
+        )
+    }
+
+    pub fn normal_at(&self, _: &Point) -> Vector {
+        Vector::new(0.0, 1.0, 0.0)
+    }
+
+    pub fn intersect<'a>(&self, ray: &Ray, obj: &'a Object, intersections: &mut Intersections<'a>) {
+        if ray.direction.y().abs() < GEOMETRY_EPSILON {
+            return;
+        }
+
+        let t = -ray.origin.y() / ray.direction.y();
+        let x = ray.origin.x() + t * ray.direction.x();
+        let z = ray.origin.z() + t * ray.direction.z();
+        let dist = x * x + z * z;
+        if dist >= self.inner_radius.powi(2) && dist <= self.outer_radius.powi(2) {
+            intersections.push(Intersection::new(t, obj));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Disk;
+    use crate::math::{point::Point, ray::Ray, tuple::Tuple, vector::Vector};
+    use crate::render::{intersections::Intersections, object::Object};
+
+    // Hit distances against a ring from radius 0.5 to 1
+    fn hits(ray: &Ray) -> Vec<f64> {
+        let obj = Object::new_test_shape();
+        let mut ints = Intersections::new();
+        Disk::new(0.5, 1.0).intersect(ray, &obj, &mut ints);
+        ints.iter().map(|int| int.t()).collect()
+    }
+
+    #[test]
+    fn a_ray_hits_the_ring_of_an_annulus() {
+        let r = Ray::new(Point::new(0.75, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        assert_eq!(hits(&r), vec![1.0]);
+    }
+
+    #[test]
+    fn a_ray_through_the_hole_misses_an_annulus() {
+        let r = Ray::new(Point::new(0.25, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        assert!(hits(&r).is_empty());
+
+        let outside = Ray::new(Point::new(1.5, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        assert!(hits(&outside).is_empty());
+    }
+
+    #[test]
+    fn a_ray_parallel_to_a_disk_misses() {
+        let r = Ray::new(Point::new(-5.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+        assert!(hits(&r).is_empty());
+    }
+
+    #[test]
+    fn disk_normal_points_up() {
+        let d = Disk::new(0.0, 1.0);
+        let n = d.normal_at(&Point::new(0.5, 0.0, 0.5));
+        assert_eq!(n, Vector::new(0.0, 1.0, 0.0));
+    }
+}
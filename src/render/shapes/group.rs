@@ -1,20 +1,39 @@
 use crate::{
     math::{matrix::Matrix, point::Point, ray::Ray, transformation::Transformable, vector::Vector},
-    render::{intersections::Intersections, object::Object, shape::Shape},
+    render::{
+        bounding_box::{BoundingBox, BoundingBoxes},
+        intersections::Intersections,
+        object::Object,
+        shape::Shape,
+    },
 };
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Group {
     children: Vec<Object>,
+    child_bounds: BoundingBoxes, // World space box of each child, children only get tested when their box is hit
+    bounds: BoundingBox,         // World space box around every child
 }
 
 impl Group {
     pub fn new(children: Vec<Object>) -> Self {
-        Self { children }
+        let boxes: Vec<BoundingBox> = children.iter().map(|child| child.bounds()).collect();
+        let bounds = boxes
+            .iter()
+            .fold(BoundingBox::empty(), |acc, child| acc.union(child));
+        Self {
+            children,
+            child_bounds: boxes.into_iter().collect(),
+            bounds,
+        }
     }
 
     pub fn new_empty() -> Self {
-        Self { children: vec![] }
+        Self {
+            children: vec![],
+            child_bounds: BoundingBoxes::new(),
+            bounds: BoundingBox::empty(),
+        }
     }
 
     pub fn intersect<'a>(
@@ -23,11 +42,24 @@ impl Group {
         _: &'a Object,
         intersections: &mut Intersections<'a>,
     ) {
-        for child in &self.children {
-            child.intersect(ray, intersections)
+        // A ray missing the whole group can skip testing each child's box
+        if !self.bounds.intersects(ray) {
+            return;
+        }
+
+        let hits = self.child_bounds.intersects(ray);
+        for (child, hit) in self.children.iter().zip(hits) {
+            if hit {
+                child.intersect(ray, intersections)
+            }
         }
     }
 
+    // Children already have the group's transform baked in
+    pub fn bounds(&self) -> BoundingBox {
+        self.bounds
+    }
+
     pub fn normal_at(&self, _point: &Point) -> Vector {
         unreachable!()
     }
@@ -36,7 +68,65 @@ impl Group {
         &self.children
     }
 
+    // Callers may change the children's materials or normals but not their geometry,
+    // the cached boxes aren't rebuilt
+    pub fn children_mut(&mut self) -> &mut Vec<Object> {
+        &mut self.children
+    }
+
+    // Turns this group into a bounding volume hierarchy. Groups with at least threshold
+    // children move the ones that fit entirely in either half of the group's box into a
+    // sub-group per half, then every child is divided in turn.
+    pub fn divide(self, threshold: usize) -> Self {
+        let children = if threshold <= self.children.len() && self.bounds.is_finite() {
+            self.partition_children()
+        } else {
+            self.children
+        };
+
+        Group::new(
+            children
+                .into_iter()
+                .map(|child| child.divide(threshold))
+                .collect(),
+        )
+    }
+
+    fn partition_children(self) -> Vec<Object> {
+        let count = self.children.len();
+        let (left_box, right_box) = self.bounds.split();
+        let mut left = vec![];
+        let mut right = vec![];
+        let mut rest = vec![];
+        for child in self.children {
+            let bounds = child.bounds();
+            if left_box.contains_box(&bounds) {
+                left.push(child);
+            } else if right_box.contains_box(&bounds) {
+                right.push(child);
+            } else {
+                rest.push(child);
+            }
+        }
+
+        // Flat boxes can fit every child in one half, wrapping them again would never end
+        if left.len() == count || right.len() == count {
+            left.append(&mut right);
+            return left;
+        }
+
+        for half in [left, right] {
+            if !half.is_empty() {
+                rest.push(Object::new_test_shape().with_shape(Shape::Group(Group::new(half))));
+            }
+        }
+        rest
+    }
+
     pub fn add_child(&mut self, child: Object) {
+        let bounds = child.bounds();
+        self.child_bounds.push(&bounds);
+        self.bounds.add_box(&bounds);
         self.children.push(child);
     }
 }
@@ -98,7 +188,7 @@ mod test {
         math::{
             point::Point, ray::Ray, transformation::Transformable, tuple::Tuple, vector::Vector,
         },
-        render::{intersections::Intersections, object::Object},
+        render::{intersections::Intersections, object::Object, shape::Shape},
     };
 
     use super::Group;
@@ -151,4 +241,60 @@ mod test {
         g.intersect(&r, &mut ints);
         assert_eq!(ints.len(), 2);
     }
+
+    #[test]
+    fn rays_missing_the_group_bounds_skip_the_children() {
+        let mut g = Group::new_empty();
+        let obj = Object::new_test_shape();
+        g.add_child(Object::new_test_shape().translate(-10.0, 0.0, 0.0));
+        g.add_child(Object::new_test_shape().translate(10.0, 0.0, 0.0));
+        let saved_rays = |g: &Group| {
+            g.children()
+                .iter()
+                .filter(|child| match child.get_shape() {
+                    Shape::TestShape(ts) => ts.get_saved_ray().is_some(),
+                    _ => false,
+                })
+                .count()
+        };
+
+        let above = Ray::new(Point::new(0.0, 5.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let mut ints = Intersections::new();
+        g.intersect(&above, &obj, &mut ints);
+        assert_eq!(ints.len(), 0);
+        assert_eq!(saved_rays(&g), 0);
+
+        // Through the gap between the children, inside the group's box
+        let between = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        g.intersect(&between, &obj, &mut ints);
+        assert_eq!(ints.len(), 0);
+        assert_eq!(saved_rays(&g), 0);
+    }
+
+    #[test]
+    fn dividing_a_group_nests_children_without_changing_hits() {
+        let spheres = [-10.0, -8.0, 8.0, 10.0]
+            .map(|x| Object::new_sphere().translate(x, 0.0, 0.0))
+            .to_vec();
+        let flat = Object::new_group(spheres);
+        let divided = flat.clone().divide(2);
+
+        // Split once at x = 0, then again inside each half
+        let halves = divided.children().unwrap();
+        assert_eq!(halves.len(), 2);
+        for half in halves {
+            let quarters = half.children().unwrap();
+            assert_eq!(quarters.len(), 2);
+            assert!(quarters.iter().all(|q| q.children().unwrap().len() == 1));
+        }
+
+        let r = Ray::new(Point::new(-20.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+        let mut want = Intersections::new();
+        flat.intersect(&r, &mut want);
+        let mut got = Intersections::new();
+        divided.intersect(&r, &mut got);
+        assert_eq!(got.len(), 8);
+        let ts = |ints: &Intersections| ints.iter().map(|i| i.t()).collect::<Vec<f64>>();
+        assert_eq!(ts(&got), ts(&want));
+    }
 }
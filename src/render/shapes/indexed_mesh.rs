@@ -0,0 +1,159 @@
+use crate::{
+    math::{point::Point, ray::Ray, vector::Vector},
+    render::{
+        bounding_box::BoundingBox,
+        intersections::{Intersection, Intersections},
+        object::Object,
+        shapes::triangle::Triangle,
+    },
+};
+
+// One triangle of a mesh as indices into the mesh's vertex and normal buffers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MeshFace {
+    pub vertices: [usize; 3],
+    pub normals: Option<[usize; 3]>,
+}
+
+impl MeshFace {
+    pub fn new(vertices: [usize; 3]) -> Self {
+        Self {
+            vertices,
+            normals: None,
+        }
+    }
+
+    pub fn with_normals(self, normals: [usize; 3]) -> Self {
+        Self {
+            normals: Some(normals),
+            ..self
+        }
+    }
+}
+
+// Triangles sharing a single vertex and normal buffer, so a mesh costs a few indices per
+// face instead of a whole Object per triangle
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedMesh {
+    vertices: Vec<Point>,
+    normals: Vec<Vector>,
+    faces: Vec<MeshFace>,
+    bounds: BoundingBox,
+}
+
+impl IndexedMesh {
+    pub fn new(vertices: Vec<Point>, normals: Vec<Vector>, faces: Vec<MeshFace>) -> Self {
+        let mut bounds = BoundingBox::empty();
+        for face in &faces {
+            for index in face.vertices {
+                bounds.add_point(&vertices[index]);
+            }
+        }
+        Self {
+            vertices,
+            normals,
+            faces,
+            bounds,
+        }
+    }
+
+    pub fn intersect<'a>(&self, ray: &Ray, obj: &'a Object, intersections: &mut Intersections<'a>) {
+        if !self.bounds.intersects(ray) {
+            return;
+        }
+
+        for (index, face) in self.faces.iter().enumerate() {
+            let (p1, e1, e2) = self.edges(face);
+            if let Some((t, u, v)) = Triangle::intersect_edges(ray, &p1, &e1, &e2, false) {
+                intersections.push(Intersection::new(t, obj).with_u_v(u, v).with_face(index));
+            }
+        }
+    }
+
+    pub fn normal_at(&self, _: &Point, int: &Intersection) -> Vector {
+        let face = &self.faces[int.face()];
+        match face.normals {
+            Some([n1, n2, n3]) => {
+                self.normals[n2] * int.u()
+                    + self.normals[n3] * int.v()
+                    + self.normals[n1] * (1.0 - int.u() - int.v())
+            }
+            None => {
+                let (_, e1, e2) = self.edges(face);
+                e2.cross(&e1).normalize()
+            }
+        }
+    }
+
+    pub fn bounds(&self) -> BoundingBox {
+        self.bounds
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.faces.len()
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    pub fn faces(&self) -> &[MeshFace] {
+        &self.faces
+    }
+
+    fn edges(&self, face: &MeshFace) -> (Point, Vector, Vector) {
+        let [i1, i2, i3] = face.vertices;
+        let p1 = self.vertices[i1];
+        (p1, self.vertices[i2] - p1, self.vertices[i3] - p1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{IndexedMesh, MeshFace};
+    use crate::{
+        math::{epsilon::ApproxEq, point::Point, ray::Ray, tuple::Tuple, vector::Vector},
+        render::{intersections::Intersections, object::Object},
+    };
+
+    fn test_mesh() -> IndexedMesh {
+        IndexedMesh::new(
+            vec![
+                Point::new(-1.0, 1.0, 0.0),
+                Point::new(-1.0, -1.0, 0.0),
+                Point::new(1.0, -1.0, 0.0),
+                Point::new(1.0, 1.0, 0.0),
+            ],
+            vec![],
+            vec![MeshFace::new([0, 1, 2]), MeshFace::new([0, 2, 3])],
+        )
+    }
+
+    #[test]
+    fn a_ray_strikes_each_face_of_a_mesh() {
+        let obj = Object::new_test_shape();
+        let mesh = test_mesh();
+
+        for (x, face) in [(-0.5, 0), (0.5, 1)] {
+            let r = Ray::new(Point::new(x, -0.25, -2.0), Vector::new(0.0, 0.0, 1.0));
+            let mut ints = Intersections::new();
+            mesh.intersect(&r, &obj, &mut ints);
+            assert_eq!(ints.len(), 1);
+            assert!(ints[0].t().approx_eq(2.0));
+            assert_eq!(ints[0].face(), face);
+            assert_eq!(
+                mesh.normal_at(&Point::new(x, -0.25, 0.0), &ints[0]),
+                Vector::new(0.0, 0.0, -1.0)
+            );
+        }
+    }
+
+    #[test]
+    fn a_ray_misses_a_mesh() {
+        let obj = Object::new_test_shape();
+        let r = Ray::new(Point::new(2.0, 0.0, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let mut ints = Intersections::new();
+        test_mesh().intersect(&r, &obj, &mut ints);
+        assert_eq!(ints.len(), 0);
+    }
+}
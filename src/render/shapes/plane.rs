@@ -1,6 +1,7 @@
 use crate::{
-    math::{epsilon::EPSILON, point::Point, ray::Ray, tuple::Tuple, vector::Vector},
-    render::intersections::{Intersection, Intersections},
+    math::{epsilon::GEOMETRY_EPSILON, point::Point, ray::Ray, tuple::Tuple, vector::Vector},
+    render::bounding_box::BoundingBox,
+    render::intersections::{Intersection, Intersections, LocalTs},
     render::object::Object,
 };
 
@@ -12,22 +13,43 @@ impl Plane {
         Plane {}
     }
 
+    pub fn bounds() -> BoundingBox {
+        BoundingBox::new(
+            Point::new(-f64::INFINITY, 0.0, -f64::INFINITY),
+            Point::new(f64::INFINITY, 0.0, f64::INFINITY),
+        )
+    }
+
     pub fn normal_at(_: &Point) -> Vector {
         Vector::new(0.0, 1.0, 0.0)
     }
 
+    // Planar mapping: the texture repeats every unit along x and z
+    pub fn uv_at(point: &Point) -> (f64, f64) {
+        (point.x().rem_euclid(1.0), point.z().rem_euclid(1.0))
+    }
+
     pub fn intersect<'a>(&self, ray: &Ray, obj: &'a Object, intersections: &mut Intersections<'a>) {
-        if ray.direction.y().abs() >= EPSILON {
-            let t = -ray.origin.y() / ray.direction.y();
+        for t in self.local_intersect_ts(ray) {
             intersections.push(Intersection::new(t, obj));
         }
     }
+
+    pub fn local_intersect_ts(&self, ray: &Ray) -> LocalTs {
+        let mut ts = LocalTs::new();
+        if ray.direction.y().abs() >= GEOMETRY_EPSILON {
+            ts.push(-ray.origin.y() / ray.direction.y());
+        }
+        ts
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::Plane;
-    use crate::math::{point::Point, ray::Ray, tuple::Tuple, vector::Vector};
+    use crate::math::{
+        epsilon::GEOMETRY_EPSILON, point::Point, ray::Ray, tuple::Tuple, vector::Vector,
+    };
     use crate::render::{intersections::Intersections, object::Object};
 
     #[test]
@@ -79,4 +101,31 @@ mod test {
         assert_eq!(xs.len(), 1);
         assert_eq!(xs[0].object(), &p);
     }
+
+    #[test]
+    fn plane_grazing_ray_at_the_geometry_epsilon_boundary() {
+        let p = Object::new_plane();
+        for (dy, len) in [(GEOMETRY_EPSILON, 1), (GEOMETRY_EPSILON / 2.0, 0)] {
+            let ray = Ray::new(Point::new(0.0, 1.0, 0.0), Vector::new(0.0, -dy, 0.0));
+            let mut xs = Intersections::new();
+            p.intersect(&ray, &mut xs);
+            assert_eq!(xs.len(), len);
+        }
+    }
+
+    #[test]
+    fn using_a_planar_mapping_on_a_3d_point() {
+        let tests = [
+            (Point::new(0.25, 0.0, 0.5), (0.25, 0.5)),
+            (Point::new(0.25, 0.0, -0.25), (0.25, 0.75)),
+            (Point::new(0.25, 0.5, -0.25), (0.25, 0.75)),
+            (Point::new(1.25, 0.0, 0.5), (0.25, 0.5)),
+            (Point::new(0.25, 0.0, -1.75), (0.25, 0.25)),
+            (Point::new(1.0, 0.0, -1.0), (0.0, 0.0)),
+            (Point::new(0.0, 0.0, 0.0), (0.0, 0.0)),
+        ];
+        for (point, want) in tests {
+            assert_eq!(Plane::uv_at(&point), want);
+        }
+    }
 }
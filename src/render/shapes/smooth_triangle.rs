@@ -1,7 +1,8 @@
 use crate::{
     math::{point::Point, ray::Ray, vector::Vector},
     render::{
-        intersections::{Intersection, Intersections},
+        bounding_box::BoundingBox,
+        intersections::{Intersection, Intersections, LocalTs},
         object::Object,
         shapes::triangle::Triangle,
     },
@@ -25,14 +26,48 @@ impl SmoothTriangle {
         }
     }
 
+    pub fn with_backface_cull(self, backface_cull: bool) -> Self {
+        Self {
+            triangle: self.triangle.with_backface_cull(backface_cull),
+            ..self
+        }
+    }
+
+    pub fn backface_cull(&self) -> bool {
+        self.triangle.backface_cull()
+    }
+
+    pub fn with_uvs(self, uvs: [(f64, f64); 3]) -> Self {
+        Self {
+            triangle: self.triangle.with_uvs(uvs),
+            ..self
+        }
+    }
+
+    pub fn uvs(&self) -> Option<[(f64, f64); 3]> {
+        self.triangle.uvs()
+    }
+
+    pub fn uv_at(&self, point: &Point) -> Option<(f64, f64)> {
+        self.triangle.uv_at(point)
+    }
+
     pub fn intersect<'a>(&self, ray: &Ray, obj: &'a Object, intersections: &mut Intersections<'a>) {
         self.triangle.intersect(ray, obj, intersections)
     }
 
+    pub fn local_intersect_ts(&self, ray: &Ray) -> LocalTs {
+        self.triangle.local_intersect_ts(ray)
+    }
+
     pub fn normal_at(&self, _: &Point, int: &Intersection) -> Vector {
         self.n2 * int.u() + self.n3 * int.v() + self.n1 * (1.0 - int.u() - int.v())
     }
 
+    pub fn bounds(&self) -> BoundingBox {
+        self.triangle.bounds()
+    }
+
     pub fn p1(&self) -> Point {
         self.triangle.p1()
     }
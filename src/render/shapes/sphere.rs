@@ -1,7 +1,10 @@
+use std::f64::consts::PI;
+
 use crate::{
     math::{point::Point, ray::Ray, tuple::Tuple, vector::Vector},
     render::{
-        intersections::{Intersection, Intersections},
+        bounding_box::BoundingBox,
+        intersections::{Intersection, Intersections, LocalTs},
         object::Object,
     },
 };
@@ -13,23 +16,44 @@ impl Sphere {
     pub fn new() -> Self {
         Sphere {}
     }
+    pub fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0))
+    }
+
     pub fn normal_at(&self, local_point: &Point) -> Vector {
         *local_point - Point::new(0.0, 0.0, 0.0)
     }
 
+    // Spherical mapping: u runs around the y axis starting at -z, v from the south pole
+    pub fn uv_at(point: &Point) -> (f64, f64) {
+        let theta = point.x().atan2(point.z());
+        let radius = (point.x().powi(2) + point.y().powi(2) + point.z().powi(2)).sqrt();
+        let phi = (point.y() / radius).acos();
+        let u = (1.0 - (theta / (2.0 * PI) + 0.5)).rem_euclid(1.0);
+        (u, 1.0 - phi / PI)
+    }
+
     pub fn intersect<'a>(&self, ray: &Ray, obj: &'a Object, intersections: &mut Intersections<'a>) {
+        for t in self.local_intersect_ts(ray) {
+            intersections.push(Intersection::new(t, obj));
+        }
+    }
+
+    pub fn local_intersect_ts(&self, ray: &Ray) -> LocalTs {
+        let mut ts = LocalTs::new();
         let sphere_to_ray = ray.origin - Point::new(0.0, 0.0, 0.0);
         let a = ray.direction * ray.direction;
         let b = 2.0 * (ray.direction * sphere_to_ray);
         let c = (sphere_to_ray * sphere_to_ray) - 1.0;
         let discriminant = b.powi(2) - 4.0 * a * c;
 
-        if !(discriminant < 0.0) {
-            let hit1 = (-b - (discriminant.sqrt())) / (2.0 * a);
-            let hit2 = (-b + (discriminant.sqrt())) / (2.0 * a);
-            intersections.push(Intersection::new(hit1, &obj));
-            intersections.push(Intersection::new(hit2, &obj));
+        if discriminant < 0.0 {
+            return ts;
         }
+
+        ts.push((-b - (discriminant.sqrt())) / (2.0 * a));
+        ts.push((-b + (discriminant.sqrt())) / (2.0 * a));
+        ts
     }
 }
 
@@ -215,4 +239,29 @@ mod test {
         assert_eq!(HitComputation::new(&xs, 4, &r).n(), (2.5, 1.5));
         assert_eq!(HitComputation::new(&xs, 5, &r).n(), (1.5, 1.0));
     }
+
+    #[test]
+    fn using_a_spherical_mapping_on_a_3d_point() {
+        let half = 2.0_f64.sqrt() / 2.0;
+        let tests = [
+            (Point::new(0.0, 0.0, -1.0), (0.0, 0.5)),
+            (Point::new(1.0, 0.0, 0.0), (0.25, 0.5)),
+            (Point::new(0.0, 0.0, 1.0), (0.5, 0.5)),
+            (Point::new(-1.0, 0.0, 0.0), (0.75, 0.5)),
+            (Point::new(0.0, 1.0, 0.0), (0.5, 1.0)),
+            (Point::new(0.0, -1.0, 0.0), (0.5, 0.0)),
+            (Point::new(half, half, 0.0), (0.25, 0.75)),
+        ];
+        for (point, (u, v)) in tests {
+            let got = Sphere::uv_at(&point);
+            assert!(got.0.approx_eq(u), "{:?} u {}", point, got.0);
+            assert!(got.1.approx_eq(v), "{:?} v {}", point, got.1);
+        }
+    }
+
+    #[test]
+    fn spherical_u_stays_below_one_on_the_seam() {
+        let (u, _) = Sphere::uv_at(&Point::new(-0.0, 0.0, -1.0));
+        assert_eq!(u, 0.0);
+    }
 }
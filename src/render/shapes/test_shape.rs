@@ -4,6 +4,7 @@ use crate::math::point::Point;
 use crate::math::ray::Ray;
 use crate::math::tuple::Tuple;
 use crate::math::vector::Vector;
+use crate::render::bounding_box::BoundingBox;
 use crate::render::intersections::Intersections;
 use crate::render::object::Object;
 
@@ -22,6 +23,10 @@ impl TestShape {
         *refr = Some(*ray);
     }
 
+    pub fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0))
+    }
+
     pub fn normal_at(&self, _: &Point) -> Vector {
         Vector::new(0.0, 0.0, 0.0)
     }
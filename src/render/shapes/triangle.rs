@@ -1,7 +1,8 @@
 use crate::{
-    math::{epsilon::EPSILON, point::Point, ray::Ray, vector::Vector},
+    math::{epsilon::GEOMETRY_EPSILON, point::Point, ray::Ray, vector::Vector},
     render::{
-        intersections::{Intersection, Intersections},
+        bounding_box::BoundingBox,
+        intersections::{Intersection, Intersections, LocalTs},
         object::Object,
     },
 };
@@ -14,6 +15,8 @@ pub struct Triangle {
     e1: Vector,
     e2: Vector,
     normal: Vector,
+    backface_cull: bool,
+    uvs: Option<[(f64, f64); 3]>, // Texture coordinates at p1, p2 and p3
 }
 
 impl Triangle {
@@ -28,37 +31,115 @@ impl Triangle {
             e1,
             e2,
             normal,
+            backface_cull: false,
+            uvs: None,
         }
     }
 
+    // Ignore hits from rays arriving at the back of the triangle
+    pub fn with_backface_cull(self, backface_cull: bool) -> Self {
+        Self {
+            backface_cull,
+            ..self
+        }
+    }
+
+    pub fn backface_cull(&self) -> bool {
+        self.backface_cull
+    }
+
+    pub fn with_uvs(self, uvs: [(f64, f64); 3]) -> Self {
+        Self {
+            uvs: Some(uvs),
+            ..self
+        }
+    }
+
+    pub fn uvs(&self) -> Option<[(f64, f64); 3]> {
+        self.uvs
+    }
+
+    // Interpolates the vertex texture coordinates at a point on the triangle using its
+    // barycentric coordinates
+    pub fn uv_at(&self, point: &Point) -> Option<(f64, f64)> {
+        let [(u1, v1), (u2, v2), (u3, v3)] = self.uvs?;
+        let to_point = *point - self.p1;
+        let d11 = self.e1 * self.e1;
+        let d12 = self.e1 * self.e2;
+        let d22 = self.e2 * self.e2;
+        let dp1 = to_point * self.e1;
+        let dp2 = to_point * self.e2;
+        let denom = d11 * d22 - d12 * d12;
+        let b2 = (d22 * dp1 - d12 * dp2) / denom;
+        let b3 = (d11 * dp2 - d12 * dp1) / denom;
+        let b1 = 1.0 - b2 - b3;
+        Some((b1 * u1 + b2 * u2 + b3 * u3, b1 * v1 + b2 * v2 + b3 * v3))
+    }
+
     pub fn normal_at(&self, _: &Point) -> Vector {
         self.normal
     }
 
     pub fn intersect<'a>(&self, ray: &Ray, obj: &'a Object, intersections: &mut Intersections<'a>) {
-        let dir_cross_e2 = ray.direction.cross(&self.e2);
-        let det = self.e1 * dir_cross_e2;
-        if det.abs() < EPSILON {
-            return;
+        if let Some((t, u, v)) = self.local_intersect_tuv(ray) {
+            intersections.push(Intersection::new(t, obj).with_u_v(u, v));
+        }
+    }
+
+    pub fn local_intersect_ts(&self, ray: &Ray) -> LocalTs {
+        let mut ts = LocalTs::new();
+        if let Some((t, _, _)) = self.local_intersect_tuv(ray) {
+            ts.push(t);
+        }
+        ts
+    }
+
+    fn local_intersect_tuv(&self, ray: &Ray) -> Option<(f64, f64, f64)> {
+        Self::intersect_edges(ray, &self.p1, &self.e1, &self.e2, self.backface_cull)
+    }
+
+    // Moller-Trumbore test against the triangle at p1 spanned by the edges e1 and e2,
+    // returning t along with the barycentric u and v of the hit
+    pub fn intersect_edges(
+        ray: &Ray,
+        p1: &Point,
+        e1: &Vector,
+        e2: &Vector,
+        backface_cull: bool,
+    ) -> Option<(f64, f64, f64)> {
+        let dir_cross_e2 = ray.direction.cross(e2);
+        // det is the ray direction dotted with the unnormalized face normal, so it is
+        // negative when the ray strikes the front of the triangle
+        let det = *e1 * dir_cross_e2;
+        if det.abs() < GEOMETRY_EPSILON || (backface_cull && det > 0.0) {
+            return None;
         }
 
         // Calculate 'u' value
         let f = 1.0 / det;
-        let p1_to_origin = ray.origin - self.p1;
+        let p1_to_origin = ray.origin - *p1;
         let u = f * (p1_to_origin * dir_cross_e2);
         if u < 0.0 || u > 1.0 {
-            return;
+            return None;
         }
 
         // Calculate 'v' value
-        let origin_cross_e1 = p1_to_origin.cross(&self.e1);
+        let origin_cross_e1 = p1_to_origin.cross(e1);
         let v = f * (ray.direction * origin_cross_e1);
         if v < 0.0 || (u + v) > 1.0 {
-            return;
+            return None;
         }
 
-        let t = f * (self.e2 * origin_cross_e1);
-        intersections.push(Intersection::new(t, obj).with_u_v(u, v));
+        let t = f * (*e2 * origin_cross_e1);
+        Some((t, u, v))
+    }
+
+    pub fn bounds(&self) -> BoundingBox {
+        let mut bounds = BoundingBox::empty();
+        for p in [self.p1, self.p2, self.p3] {
+            bounds.add_point(&p);
+        }
+        bounds
     }
 
     pub fn p1(&self) -> Point {
@@ -78,7 +159,13 @@ impl Triangle {
 mod test {
 
     use crate::{
-        math::{epsilon::ApproxEq, point::Point, ray::Ray, tuple::Tuple, vector::Vector},
+        math::{
+            epsilon::{ApproxEq, GEOMETRY_EPSILON},
+            point::Point,
+            ray::Ray,
+            tuple::Tuple,
+            vector::Vector,
+        },
         render::{intersections::Intersections, object::Object},
     };
 
@@ -166,4 +253,67 @@ mod test {
         assert_eq!(ints.len(), 1);
         assert!(ints[0].t().approx_eq(2.0));
     }
+
+    #[test]
+    fn backface_culling_ignores_hits_from_behind() {
+        let obj = Object::new_test_shape();
+        let (t, _) = test_triangle();
+        let culled = t.clone().with_backface_cull(true);
+        let front = Ray::new(Point::new(0.0, 0.5, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let back = Ray::new(Point::new(0.0, 0.5, 2.0), Vector::new(0.0, 0.0, -1.0));
+
+        for (tri, ray, len) in [
+            (&t, &front, 1),
+            (&t, &back, 1),
+            (&culled, &front, 1),
+            (&culled, &back, 0),
+        ] {
+            let mut ints = Intersections::new();
+            tri.intersect(ray, &obj, &mut ints);
+            assert_eq!(ints.len(), len);
+        }
+    }
+
+    #[test]
+    fn a_ray_strikes_a_large_triangle_at_a_grazing_angle() {
+        let obj = Object::new_test_shape();
+        let t = Triangle::new(
+            Point::new(0.0, 1000.0, 0.0),
+            Point::new(-1000.0, 0.0, 0.0),
+            Point::new(1000.0, 0.0, 0.0),
+        );
+        let r = Ray::new(
+            Point::new(0.0, 10.0, -0.5),
+            Vector::new(0.0, 1.0, 0.001).normalize(),
+        );
+        let mut ints = Intersections::new();
+        t.intersect(&r, &obj, &mut ints);
+        assert_eq!(ints.len(), 1);
+    }
+
+    #[test]
+    fn a_grazing_ray_at_the_geometry_epsilon_boundary() {
+        let obj = Object::new_test_shape();
+        let (t, _) = test_triangle();
+        // det works out to twice the z component for the test triangle
+        for (det, len) in [(GEOMETRY_EPSILON, 1), (GEOMETRY_EPSILON / 2.0, 0)] {
+            let r = Ray::new(Point::new(0.0, 0.5, -1.0), Vector::new(0.0, 0.0, det / 2.0));
+            let mut ints = Intersections::new();
+            t.intersect(&r, &obj, &mut ints);
+            assert_eq!(ints.len(), len);
+        }
+    }
+
+    #[test]
+    fn interpolating_vertex_texture_coordinates() {
+        let (t, (p1, p2, p3)) = test_triangle();
+        assert_eq!(t.uv_at(&p1), None);
+
+        let t = t.with_uvs([(0.5, 1.0), (0.0, 0.0), (1.0, 0.0)]);
+        assert_eq!(t.uv_at(&p1), Some((0.5, 1.0)));
+        assert_eq!(t.uv_at(&p2), Some((0.0, 0.0)));
+        let (u, v) = t.uv_at(&Point::new(0.5, 0.5, 0.0)).unwrap();
+        assert!(u.approx_eq(0.75) && v.approx_eq(0.5));
+        assert_eq!(t.uv_at(&p3), Some((1.0, 0.0)));
+    }
 }
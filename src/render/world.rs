@@ -1,22 +1,93 @@
+use std::{
+    ops::Range,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
 use crate::{
-    draw::{canvas::Canvas, color::Color},
+    draw::{canvas::Canvas, color::Color, frame::Frame, io::obj::ObjFileParser},
+    error::Error,
     math::{
-        epsilon::ApproxEq, point::Point, ray::Ray, transformation::Transformable, tuple::Tuple,
+        epsilon::{ApproxEq, EPSILON},
+        matrix::Matrix,
+        point::Point,
+        ray::Ray,
+        rng,
+        transformation::Transformable,
+        tuple::Tuple,
+        vector::Vector,
     },
     render::{
-        intersections::Intersections, light::Light, lights::point_light::PointLight,
-        material::Materialable, object::Object, pattern::Pattern,
+        bounding_box::BoundingBox,
+        intersections::Intersections,
+        light::Light,
+        lights::{area_light::AreaLight, point_light::PointLight},
+        material::{Material, Materialable},
+        object::Object,
+        pattern::Pattern,
     },
 };
 
-use super::{camera::Camera, intersections::HitComputation};
+use super::{
+    camera::Camera, environment::Environment, intersections::HitComputation,
+    render_config::RenderConfig,
+};
+
+pub(crate) const REMAINING: usize = 5;
+
+// Hard stop for rays kept alive by russian roulette
+const MAX_ROULETTE_BOUNCES: usize = 64;
 
-const REMAINING: usize = 5;
+// Cells along each edge of the area lights baked from emissive objects
+const EMISSIVE_LIGHT_STEPS: usize = 4;
+
+// Recursion budget left for reflected and refracted rays, tracked separately
+// so deep refraction doesn't starve reflection (and vice versa)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Depth {
+    pub reflection: usize,
+    pub refraction: usize,
+    pub bounces: usize, // Secondary rays traced so far along this path
+}
+
+impl Depth {
+    pub fn new(reflection: usize, refraction: usize) -> Self {
+        Self {
+            reflection,
+            refraction,
+            bounces: 0,
+        }
+    }
+}
+
+impl From<usize> for Depth {
+    fn from(remaining: usize) -> Self {
+        Self::new(remaining, remaining)
+    }
+}
 
+// Per pixel breakdown of a render into its shading terms
 #[derive(Debug)]
+pub struct Aovs {
+    pub surface: Canvas,
+    pub reflection: Canvas,
+    pub refraction: Canvas,
+}
+
+#[derive(Debug, Clone)]
 pub struct World {
     objects: Vec<Object>,
     lights: Vec<Light>,
+    max_reflection_depth: usize,
+    max_refraction_depth: usize,
+    light_sample_count: Option<usize>,
+    firefly_clamp: Option<f64>,
+    russian_roulette_depth: Option<usize>,
+    seed: u64,
+    environment: Environment,
+    intersection_counts: Option<Arc<Vec<AtomicUsize>>>,
 }
 
 impl World {
@@ -24,9 +95,106 @@ impl World {
         Self {
             objects: vec![],
             lights: vec![],
+            max_reflection_depth: REMAINING,
+            max_refraction_depth: REMAINING,
+            light_sample_count: None,
+            firefly_clamp: None,
+            russian_roulette_depth: None,
+            seed: 0,
+            environment: Environment::default(),
+            intersection_counts: None,
         }
     }
 
+    pub fn max_reflection_depth(&self) -> usize {
+        self.max_reflection_depth
+    }
+
+    pub fn set_max_reflection_depth(&mut self, depth: usize) {
+        self.max_reflection_depth = depth;
+    }
+
+    pub fn max_refraction_depth(&self) -> usize {
+        self.max_refraction_depth
+    }
+
+    pub fn set_max_refraction_depth(&mut self, depth: usize) {
+        self.max_refraction_depth = depth;
+    }
+
+    pub fn light_sample_count(&self) -> Option<usize> {
+        self.light_sample_count
+    }
+
+    // Limits shading to the given number of most significant lights per hit, trading
+    // accuracy for speed in scenes with many lights. None shades every light.
+    pub fn set_light_sample_count(&mut self, count: Option<usize>) {
+        self.light_sample_count = count;
+    }
+
+    pub fn firefly_clamp(&self) -> Option<f64> {
+        self.firefly_clamp
+    }
+
+    // Caps the luminance of each sample before averaging so a single very bright
+    // sample can't dominate a pixel. None leaves samples untouched.
+    pub fn set_firefly_clamp(&mut self, max_luminance: Option<f64>) {
+        self.firefly_clamp = max_luminance;
+    }
+
+    pub fn average_samples(&self, samples: &[Color]) -> Color {
+        if samples.is_empty() {
+            return Color::black();
+        }
+
+        let sum = samples.iter().fold(Color::black(), |acc, sample| {
+            let luminance = sample.luminance();
+            match self.firefly_clamp {
+                Some(max) if luminance > max => acc + *sample * (max / luminance),
+                _ => acc + *sample,
+            }
+        });
+        sum * (1.0 / samples.len() as f64)
+    }
+
+    pub fn russian_roulette_depth(&self) -> Option<usize> {
+        self.russian_roulette_depth
+    }
+
+    // After this many bounces secondary rays are no longer cut off by the depth limits.
+    // Instead they survive with a probability equal to the surface's reflectance or
+    // transparency and survivors are scaled up to compensate. None keeps the hard limits.
+    pub fn set_russian_roulette_depth(&mut self, depth: Option<usize>) {
+        self.russian_roulette_depth = depth;
+    }
+
+    // Seed for the random choices made while rendering
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    pub fn environment(&self) -> &Environment {
+        &self.environment
+    }
+
+    // Color returned for rays that miss every object
+    pub fn set_environment(&mut self, environment: Environment) {
+        self.environment = environment;
+    }
+
+    // Shorthand for a solid color environment
+    pub fn set_background(&mut self, color: Color) {
+        self.set_environment(Environment::Solid(color));
+    }
+
+    pub fn lights(&self) -> &[Light] {
+        &self.lights
+    }
+
+    pub fn objects(&self) -> &[Object] {
+        &self.objects
+    }
+
     pub fn add_light(&mut self, light: Light) {
         self.lights.push(light);
     }
@@ -35,57 +203,236 @@ impl World {
         self.objects.push(obj);
     }
 
+    // Removes the object at index, shifting later objects down. Out of range indices
+    // leave the world unchanged.
+    pub fn remove_object(&mut self, index: usize) -> Option<Object> {
+        if index < self.objects.len() {
+            Some(self.objects.remove(index))
+        } else {
+            None
+        }
+    }
+
+    pub fn clear_objects(&mut self) {
+        self.objects.clear();
+    }
+
+    // Loads an OBJ model, moves the whole group by transform and paints every
+    // triangle with material before adding it to the scene
+    pub fn add_model(
+        &mut self,
+        path: &str,
+        transform: Matrix,
+        material: Material,
+    ) -> Result<(), Error> {
+        let model = ObjFileParser::new_file(path)?
+            .try_build_with_material(material)?
+            .with_transform(transform);
+        self.add_object(model);
+        Ok(())
+    }
+
+    // Adds an area light over every emissive object so it lights the rest of the scene
+    // too, not just itself. Each call adds another set, so bake once the objects are in
+    // place.
+    pub fn bake_emissive_lights(&mut self) {
+        let panels: Vec<Light> = self
+            .objects
+            .iter()
+            .filter(|obj| obj.get_material().emission != Color::black())
+            .map(emissive_panel)
+            .collect();
+        self.lights.extend(panels);
+    }
+
     pub fn intersect<'a>(
         &self,
         ray: &Ray,
         objects: &'a [Object],
         intersections: &mut Intersections<'a>,
     ) {
-        for obj in objects {
+        for (index, obj) in objects.iter().enumerate() {
+            let before = intersections.tests();
             obj.intersect(ray, intersections);
+            if let Some(count) = self.intersection_counts.as_ref().and_then(|c| c.get(index)) {
+                count.fetch_add(intersections.tests() - before, Ordering::Relaxed);
+            }
         }
     }
 
-    pub fn shade_hit(&self, comp: &HitComputation, remaining: usize) -> Color {
-        self.lights.iter().fold(Color::black(), |acc, light| {
-            let material = comp.object.get_material();
-            let over_point = comp.over_point;
-            let eye_vector = comp.eye;
-            let normal_vector = comp.normal;
-            let in_shadow = self.is_shadowed(&comp.over_point);
-            let surface = light.lighting(
-                comp.object,
-                &material,
-                over_point,
-                eye_vector,
-                normal_vector,
-                in_shadow,
-            );
+    // Tests the ray against a single object, for updating a cached set of hits when
+    // only that object has changed. Out of range indexes add nothing.
+    pub fn intersect_object<'a>(
+        &'a self,
+        ray: &Ray,
+        index: usize,
+        intersections: &mut Intersections<'a>,
+    ) {
+        if let Some(obj) = self.objects.get(index) {
+            obj.intersect(ray, intersections);
+        }
+    }
+
+    // Renders the scene once and returns how many intersection tests each object took,
+    // indexed like the world's objects. Groups count a test for each child a ray reaches.
+    pub fn intersection_histogram(&self, camera: &Camera) -> Vec<usize> {
+        let counts: Arc<Vec<AtomicUsize>> =
+            Arc::new(self.objects.iter().map(|_| AtomicUsize::new(0)).collect());
+        let mut world = self.clone();
+        world.intersection_counts = Some(counts.clone());
+        world.render(camera);
+
+        counts
+            .iter()
+            .map(|count| count.load(Ordering::Relaxed))
+            .collect()
+    }
+
+    // Union of every object's bounds. Unbounded objects like planes are left out so
+    // they don't swallow the rest of the scene.
+    pub fn bounds(&self) -> BoundingBox {
+        self.objects
+            .iter()
+            .map(|obj| obj.bounds())
+            .filter(|bounds| bounds.is_finite())
+            .fold(BoundingBox::empty(), |acc, bounds| acc.union(&bounds))
+    }
+
+    // Indices of the objects whose interior contains the point. Rays are cast both ways
+    // along a fixed, off-axis direction and an object only counts when both rays cross
+    // its surface an odd number of times, which rules out open shapes like planes.
+    pub fn point_inside(&self, point: &Point) -> Vec<usize> {
+        let direction = Vector::new(0.5377, 0.8106, 0.2307).normalize();
+        let crossings = |obj: &Object, direction: Vector| {
+            let mut intersections = Intersections::new();
+            obj.intersect(&Ray::new(*point, direction), &mut intersections);
+            intersections.iter().filter(|int| int.t() > 0.0).count()
+        };
+
+        self.objects
+            .iter()
+            .enumerate()
+            .filter(|(_, obj)| {
+                crossings(obj, direction) % 2 == 1 && crossings(obj, -direction) % 2 == 1
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
 
-            let reflected = self.reflected_color(&comp, remaining);
-            let refracted = self.refracted_color(&comp, remaining);
+    pub fn shade_hit(&self, comp: &HitComputation, remaining: impl Into<Depth>) -> Color {
+        let (surface, reflected, refracted) = self.shade_terms(comp, remaining.into());
+        surface + reflected + refracted
+    }
+
+    // The surface, reflected and refracted contributions to a hit, already weighted by
+    // the fresnel term so they sum to the shaded color
+    fn shade_terms(&self, comp: &HitComputation, remaining: Depth) -> (Color, Color, Color) {
+        let material = comp.material();
+
+        let surface = self.sampled_lights(&comp.over_point).into_iter().fold(
+            Color::black(),
+            |acc, (light, weight)| {
+                // Out of range lights only add their ambient term, no shadow ray needed
+                let intensity = if light.in_range(&comp.over_point) {
+                    light.intensity_at(&comp.over_point, self)
+                } else {
+                    0.0
+                };
+                let lit = light.lighting_with_intensity(
+                    comp.object,
+                    &material,
+                    comp.over_point,
+                    comp.eye,
+                    comp.normal,
+                    intensity,
+                );
+                acc + lit * weight
+            },
+        );
 
-            let is_reflective = comp.object.get_material().reflective > 0.0;
-            let is_transparent = comp.object.get_material().transparency > 0.0;
+        let reflected = self.reflected_color(comp, remaining);
+        let refracted = self.refracted_color(comp, remaining);
 
-            if is_reflective && is_transparent {
-                let reflectance = comp.schlick();
+        let is_reflective = material.reflective > 0.0;
+        let is_transparent = material.transparency > 0.0;
 
-                acc + surface + reflected * reflectance + refracted * (1.0 - reflectance)
+        // Emission doesn't depend on the lights, so it's added once after weighting
+        let (surface, reflected, refracted) = if material.fresnel {
+            // The reflection is already weighted by the Schlick term, what isn't
+            // reflected goes through a transparent surface or is scattered off an opaque one
+            let transmitted = 1.0 - comp.schlick();
+            if is_transparent {
+                (surface, reflected, refracted * transmitted)
             } else {
-                acc + surface + reflected + refracted
+                (surface * transmitted, reflected, refracted)
             }
-        })
+        } else if is_reflective && is_transparent {
+            let reflectance = comp.schlick();
+
+            (
+                surface,
+                reflected * reflectance,
+                refracted * (1.0 - reflectance),
+            )
+        } else {
+            (surface, reflected, refracted)
+        };
+        (surface + material.emission, reflected, refracted)
     }
 
-    pub fn is_shadowed(&self, point: &Point) -> bool {
-        if self.lights.len() == 0 {
-            return false;
-        }
-        let vector = self.lights[0].get_position() - *point;
-        let distance = vector.magnitude();
-        let direction = vector.normalize();
-        let ray = Ray::new(*point, direction);
+    // Picks the lights used to shade a point. With a sample count set, only the lights
+    // with the largest intensity / distance are shaded and their result is scaled up by
+    // the share of the total weight they represent.
+    fn sampled_lights(&self, point: &Point) -> Vec<(&Light, f64)> {
+        let count = match self.light_sample_count {
+            Some(count) if count < self.lights.len() => count,
+            _ => return self.lights.iter().map(|light| (light, 1.0)).collect(),
+        };
+
+        let mut weighted: Vec<(&Light, f64)> = self
+            .lights
+            .iter()
+            .map(|light| {
+                let (r, g, b) = light.get_intensity().as_tuple();
+                // Directional lights don't fall off, weigh them as if one unit away
+                let distance = match light.distance_from(point) {
+                    d if d.is_finite() => d.max(EPSILON),
+                    _ => 1.0,
+                };
+                (light, ((r + g + b) / 3.0) / distance)
+            })
+            .collect();
+        weighted.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+        let total: f64 = weighted.iter().map(|(_, w)| w).sum();
+        weighted.truncate(count);
+        let sampled: f64 = weighted.iter().map(|(_, w)| w).sum();
+        let scale = if sampled > 0.0 { total / sampled } else { 0.0 };
+
+        weighted
+            .into_iter()
+            .map(|(light, _)| (light, scale))
+            .collect()
+    }
+
+    // Whether something blocks the path from the point to this particular light
+    pub fn is_shadowed(&self, point: &Point, light: &Light) -> bool {
+        self.is_occluded(
+            point,
+            &light.direction_from(point),
+            light.distance_from(point),
+        )
+    }
+
+    // Whether something sits between the point and a single point on a light. A surface
+    // the light point lies on, like a baked emissive panel, doesn't block it.
+    pub fn is_shadowed_from(&self, point: &Point, light_point: &Point) -> bool {
+        let vector = *light_point - *point;
+        self.is_occluded(point, &vector.normalize(), vector.magnitude() - EPSILON)
+    }
+
+    fn is_occluded(&self, point: &Point, direction: &Vector, distance: f64) -> bool {
+        let ray = Ray::new(*point, *direction);
         let mut intersections = Intersections::new();
         self.intersect(&ray, &self.objects, &mut intersections);
         if let Some(hit) = intersections.get_hit() {
@@ -94,32 +441,96 @@ impl World {
         false
     }
 
-    pub fn color_at(&self, ray: &Ray, remaining: usize) -> Color {
+    pub fn color_at(&self, ray: &Ray, remaining: impl Into<Depth>) -> Color {
+        self.color_and_coverage_at(ray, remaining).0
+    }
+
+    // The color seen along the ray and its coverage: 1.0 when it hit an object and 0.0
+    // when it fell through to the environment
+    pub fn color_and_coverage_at(&self, ray: &Ray, remaining: impl Into<Depth>) -> (Color, f64) {
+        let (color, hit_t) = self.trace(ray, remaining.into());
+        (color, if hit_t.is_some() { 1.0 } else { 0.0 })
+    }
+
+    // The color seen along the ray and the t of the hit, if there was one
+    fn trace(&self, ray: &Ray, remaining: Depth) -> (Color, Option<f64>) {
         let mut intersections = Intersections::new();
         self.intersect(ray, &self.objects, &mut intersections);
 
         match intersections.get_hit_index() {
             Some(index) => {
                 let comp = HitComputation::new(&intersections, index, ray);
-                self.shade_hit(&comp, remaining)
+                (self.shade_hit(&comp, remaining), Some(comp.t))
             }
-            None => Color::black(),
+            None => (self.environment.color_at(&ray.direction), None),
         }
     }
 
-    pub fn reflected_color(&self, comp: &HitComputation, remaining: usize) -> Color {
-        if remaining == 0 || comp.object.get_material().reflective.approx_eq(0.0) {
+    pub fn reflected_color(&self, comp: &HitComputation, remaining: impl Into<Depth>) -> Color {
+        let remaining = remaining.into();
+        let material = comp.material();
+        let reflective = if material.fresnel {
+            comp.schlick()
+        } else {
+            material.reflective
+        };
+        if reflective.approx_eq(0.0) {
             return Color::black();
         }
+        let weight =
+            match self.continue_path(comp, remaining.reflection, remaining.bounces, reflective) {
+                Some(weight) => weight,
+                None => return Color::black(),
+            };
         let reflect_ray = Ray::new(comp.over_point, comp.reflect);
-        let color = self.color_at(&reflect_ray, remaining - 1);
-        color * comp.object.get_material().reflective
+        let next = Depth {
+            reflection: remaining.reflection.saturating_sub(1),
+            bounces: remaining.bounces + 1,
+            ..remaining
+        };
+        let color = self.color_at(&reflect_ray, next);
+        color * reflective * weight
     }
 
-    pub fn refracted_color(&self, comp: &HitComputation, remaining: usize) -> Color {
-        if remaining == 0 || comp.object.get_material().transparency.approx_eq(0.0) {
+    // Decides whether a secondary ray is traced, returning the weight to apply to it
+    fn continue_path(
+        &self,
+        comp: &HitComputation,
+        remaining: usize,
+        bounces: usize,
+        albedo: f64,
+    ) -> Option<f64> {
+        match self.russian_roulette_depth {
+            Some(depth) if bounces >= depth => {
+                if bounces >= MAX_ROULETTE_BOUNCES {
+                    return None;
+                }
+                let survival = albedo.clamp(0.05, 1.0);
+                let roll = rng::unit_f64(&[
+                    self.seed,
+                    bounces as u64,
+                    comp.point.x().to_bits(),
+                    comp.point.y().to_bits(),
+                    comp.point.z().to_bits(),
+                ]);
+                (roll < survival).then(|| 1.0 / survival)
+            }
+            _ if remaining == 0 => None,
+            _ => Some(1.0),
+        }
+    }
+
+    pub fn refracted_color(&self, comp: &HitComputation, remaining: impl Into<Depth>) -> Color {
+        let remaining = remaining.into();
+        let transparency = comp.material().transparency;
+        if transparency.approx_eq(0.0) {
             return Color::black();
         }
+        let weight =
+            match self.continue_path(comp, remaining.refraction, remaining.bounces, transparency) {
+                Some(weight) => weight,
+                None => return Color::black(),
+            };
 
         let (n1, n2) = comp.n();
         let n_ratio = n1 / n2;
@@ -132,25 +543,356 @@ impl World {
             let cos_t = f64::sqrt(1.0 - sin2_t);
             let direction = comp.normal * (n_ratio * cos_i - cos_t) - comp.eye * n_ratio;
             let refract_ray = Ray::new(comp.under_point, direction);
-            self.color_at(&refract_ray, remaining - 1) * comp.object.get_material().transparency
+            let next = Depth {
+                refraction: remaining.refraction.saturating_sub(1),
+                bounces: remaining.bounces + 1,
+                ..remaining
+            };
+            let (color, hit_t) = self.trace(&refract_ray, next);
+
+            // Entering an absorbing object dims the light by the distance to where the
+            // refracted ray leaves it again
+            let medium = comp
+                .object
+                .get_interior_material()
+                .unwrap_or_else(|| comp.object.get_material());
+            let absorbed = match hit_t {
+                Some(t) if !comp.inside => color * medium.transmittance(t * direction.magnitude()),
+                _ => color,
+            };
+            absorbed * transparency * weight
         }
     }
 
     pub fn render(&self, camera: &Camera) -> Canvas {
+        self.render_threaded(camera, 1)
+    }
+
+    // Splits the rows into one contiguous band per thread, each writing straight into
+    // its own slice of the canvas so the result matches a serial render exactly
+    pub fn render_threaded(&self, camera: &Camera, num_threads: usize) -> Canvas {
         let width = camera.hsize();
         let height = camera.vsize();
         let mut canvas = Canvas::new(width, height);
+        let row_len = width * 3;
+        if row_len == 0 || height == 0 {
+            return canvas;
+        }
 
-        for y in 0..height {
-            for x in 0..width {
-                let ray = camera.ray_for_pixel(x, y);
-                let color = self.color_at(&ray, REMAINING);
-                canvas.set_pixel((x, y), &color);
+        let threads = num_threads.clamp(1, height);
+        let encode = canvas.encoder();
+        if threads == 1 {
+            self.render_rows(camera, 0, 0..width, canvas.as_bytes_mut(), encode);
+            return canvas;
+        }
+
+        let rows_per_band = height.div_ceil(threads);
+        std::thread::scope(|scope| {
+            for (band, bytes) in canvas
+                .as_bytes_mut()
+                .chunks_mut(rows_per_band * row_len)
+                .enumerate()
+            {
+                scope.spawn(move || {
+                    self.render_rows(camera, band * rows_per_band, 0..width, bytes, encode)
+                });
             }
+        });
+
+        canvas
+    }
+
+    // Renders serially like render, calling on_row with (completed_rows, total_rows)
+    // after each scanline finishes
+    pub fn render_with_progress(
+        &self,
+        camera: &Camera,
+        mut on_row: impl FnMut(usize, usize),
+    ) -> Canvas {
+        let width = camera.hsize();
+        let height = camera.vsize();
+        let mut canvas = Canvas::new(width, height);
+        if width == 0 {
+            return canvas;
+        }
+
+        let encode = canvas.encoder();
+        for (y, row) in canvas
+            .as_bytes_mut()
+            .chunks_exact_mut(width * 3)
+            .enumerate()
+        {
+            self.render_rows(camera, y, 0..width, row, encode);
+            on_row(y + 1, height);
+        }
+        canvas
+    }
+
+    // Shades the pixels in columns of whole rows of RGB bytes, starting at first_row
+    fn render_rows(
+        &self,
+        camera: &Camera,
+        first_row: usize,
+        columns: Range<usize>,
+        bytes: &mut [u8],
+        encode: fn(&Color) -> (u8, u8, u8),
+    ) {
+        let depth = Depth::new(self.max_reflection_depth, self.max_refraction_depth);
+        shade_rows(camera, first_row, columns, bytes, encode, |x, y| {
+            self.color_at(&camera.ray_for_pixel(x, y), depth)
+        });
+    }
+
+    // Renders only the pixels in the half-open rectangle [x0, x1) x [y0, y1), clipped to
+    // the camera, leaving the rest of the full size canvas black
+    pub fn render_region(
+        &self,
+        camera: &Camera,
+        x0: usize,
+        y0: usize,
+        x1: usize,
+        y1: usize,
+    ) -> Canvas {
+        let width = camera.hsize();
+        let mut canvas = Canvas::new(width, camera.vsize());
+        let (x1, y1) = (x1.min(width), y1.min(camera.vsize()));
+        if x0 < x1 && y0 < y1 {
+            let encode = canvas.encoder();
+            let rows = &mut canvas.as_bytes_mut()[y0 * width * 3..y1 * width * 3];
+            self.render_rows(camera, y0, x0..x1, rows, encode);
+        }
+        canvas
+    }
+
+    // Quick preview showing each hit's pattern color as is, with no lights, shadows,
+    // reflection or refraction. Rays that miss show the environment.
+    pub fn render_unlit(&self, camera: &Camera) -> Canvas {
+        let mut canvas = Canvas::new(camera.hsize(), camera.vsize());
+        let encode = canvas.encoder();
+        shade_rows(
+            camera,
+            0,
+            0..camera.hsize(),
+            canvas.as_bytes_mut(),
+            encode,
+            |x, y| {
+                let ray = camera.ray_for_pixel(x, y);
+                let mut intersections = Intersections::new();
+                self.intersect(&ray, &self.objects, &mut intersections);
+
+                match intersections.get_hit_index() {
+                    Some(index) => {
+                        let comp = HitComputation::new(&intersections, index, &ray);
+                        comp.material()
+                            .pattern
+                            .pattern_at_object(comp.object, &comp.over_point)
+                    }
+                    None => self.environment.color_at(&ray.direction),
+                }
+            },
+        );
+        canvas
+    }
+
+    // Renders a copy of the world with every setting taken from config. More than one
+    // sample jitters the rays inside each pixel, seeded by the config seed.
+    pub fn render_with_config(&self, camera: &Camera, config: &RenderConfig) -> Canvas {
+        let mut world = self.clone();
+        world.set_max_reflection_depth(config.max_reflection_depth);
+        world.set_max_refraction_depth(config.max_refraction_depth);
+        world.set_light_sample_count(config.light_sample_count);
+        world.set_firefly_clamp(config.firefly_clamp);
+        world.set_russian_roulette_depth(config.russian_roulette_depth);
+        world.set_seed(config.seed);
+        world.set_environment(config.environment);
+
+        let mut canvas = Canvas::new(camera.hsize(), camera.vsize());
+        if config.srgb {
+            canvas = canvas.with_srgb();
+        }
+        if config.samples <= 1 {
+            let encode = canvas.encoder();
+            world.render_rows(camera, 0, 0..camera.hsize(), canvas.as_bytes_mut(), encode);
+            return canvas;
         }
 
+        // Samples are averaged while still linear, the canvas encodes the result
+        let depth = Depth::new(world.max_reflection_depth, world.max_refraction_depth);
+        let encode = canvas.encoder();
+        shade_rows(
+            camera,
+            0,
+            0..camera.hsize(),
+            canvas.as_bytes_mut(),
+            encode,
+            |x, y| {
+                let samples: Vec<Color> = (0..config.samples)
+                    .map(|i| {
+                        let key = [config.seed, x as u64, y as u64, i as u64];
+                        let dx = rng::unit_f64(&[rng::hash(&key), 0]);
+                        let dy = rng::unit_f64(&[rng::hash(&key), 1]);
+                        let ray = camera.ray_for_pixel_offset(x, y, dx, dy);
+                        world.color_at(&ray, depth)
+                    })
+                    .collect();
+                world.average_samples(&samples)
+            },
+        );
         canvas
     }
+
+    // Like render, but the canvas also records which pixels hit an object in its alpha
+    // channel so the image can be composited over a background
+    pub fn render_with_alpha(&self, camera: &Camera) -> Canvas {
+        let width = camera.hsize();
+        let mut canvas = Canvas::new(width, camera.vsize()).with_alpha();
+        let depth = Depth::new(self.max_reflection_depth, self.max_refraction_depth);
+
+        let mut coverage = vec![];
+        let encode = canvas.encoder();
+        shade_rows(
+            camera,
+            0,
+            0..width,
+            canvas.as_bytes_mut(),
+            encode,
+            |x, y| {
+                let (color, covered) =
+                    self.color_and_coverage_at(&camera.ray_for_pixel(x, y), depth);
+                coverage.push(covered);
+                color
+            },
+        );
+        for (i, covered) in coverage.into_iter().enumerate() {
+            canvas.set_alpha((i % width, i / width), covered);
+        }
+        canvas
+    }
+
+    // Renders into an RGBA buffer for GUI embedding
+    pub fn render_frame(&self, camera: &Camera) -> Frame {
+        Frame {
+            width: camera.hsize(),
+            height: camera.vsize(),
+            rgba: self.render_with_alpha(camera).rgba_bytes(),
+        }
+    }
+
+    // Renders the surface, reflected and refracted terms of each camera hit into separate
+    // canvases. Rays that miss write the environment into the surface canvas.
+    pub fn render_aovs(&self, camera: &Camera) -> Aovs {
+        let width = camera.hsize();
+        let height = camera.vsize();
+        let mut aovs = Aovs {
+            surface: Canvas::new(width, height),
+            reflection: Canvas::new(width, height),
+            refraction: Canvas::new(width, height),
+        };
+        let depth = Depth::new(self.max_reflection_depth, self.max_refraction_depth);
+
+        let mut terms = vec![];
+        let encode = aovs.surface.encoder();
+        shade_rows(
+            camera,
+            0,
+            0..width,
+            aovs.surface.as_bytes_mut(),
+            encode,
+            |x, y| {
+                let ray = camera.ray_for_pixel(x, y);
+                let mut intersections = Intersections::new();
+                self.intersect(&ray, &self.objects, &mut intersections);
+
+                let (surface, reflected, refracted) = match intersections.get_hit_index() {
+                    Some(index) => {
+                        let comp = HitComputation::new(&intersections, index, &ray);
+                        self.shade_terms(&comp, depth)
+                    }
+                    None => (
+                        self.environment.color_at(&ray.direction),
+                        Color::black(),
+                        Color::black(),
+                    ),
+                };
+                terms.push((reflected, refracted));
+                surface
+            },
+        );
+        for (i, (reflected, refracted)) in terms.into_iter().enumerate() {
+            let pos = (i % width, i / width);
+            aovs.reflection.set_pixel(pos, &reflected);
+            aovs.refraction.set_pixel(pos, &refracted);
+        }
+        aovs
+    }
+}
+
+// Area light spanning an emissive object's box across its two widest axes, halfway
+// through the thinnest. Axes the shape leaves unbounded, like a plane's, are taken as
+// -1..1 so scaling the plane sizes the panel.
+fn emissive_panel(obj: &Object) -> Light {
+    let bounds = obj.get_shape().bounds();
+    let bounded = |v: f64, fallback: f64| if v.is_finite() { v } else { fallback };
+    let (min, max) = (bounds.min(), bounds.max());
+    let min = [
+        bounded(min.x(), -1.0),
+        bounded(min.y(), -1.0),
+        bounded(min.z(), -1.0),
+    ];
+    let max = [
+        bounded(max.x(), 1.0),
+        bounded(max.y(), 1.0),
+        bounded(max.z(), 1.0),
+    ];
+    let extent = [max[0] - min[0], max[1] - min[1], max[2] - min[2]];
+
+    let thinnest = (0..3)
+        .min_by(|a, b| extent[*a].total_cmp(&extent[*b]))
+        .unwrap_or(1);
+    let (u_axis, v_axis) = match thinnest {
+        0 => (1, 2),
+        1 => (0, 2),
+        _ => (0, 1),
+    };
+    let mut corner = min;
+    corner[thinnest] = (min[thinnest] + max[thinnest]) / 2.0;
+    let edge = |axis: usize| {
+        let mut v = [0.0; 3];
+        v[axis] = extent[axis];
+        Vector::new(v[0], v[1], v[2])
+    };
+
+    // Groups and CSGs already have their transform baked into their bounds
+    let transform = obj.get_transform();
+    Light::Area(AreaLight::new(
+        transform * Point::new(corner[0], corner[1], corner[2]),
+        transform * edge(u_axis),
+        EMISSIVE_LIGHT_STEPS,
+        transform * edge(v_axis),
+        EMISSIVE_LIGHT_STEPS,
+        obj.get_material().emission,
+    ))
+}
+
+// Fills in the pixels in columns of whole rows of RGB bytes, starting at first_row,
+// with the color shade gives for each (x, y), turned into bytes by the canvas's
+// encoder. Every render writes its pixels through here so a tile or a preview can't
+// drift from the full image.
+fn shade_rows(
+    camera: &Camera,
+    first_row: usize,
+    columns: Range<usize>,
+    bytes: &mut [u8],
+    encode: fn(&Color) -> (u8, u8, u8),
+    mut shade: impl FnMut(usize, usize) -> Color,
+) {
+    for (row, pixels) in bytes.chunks_exact_mut(camera.hsize() * 3).enumerate() {
+        let y = first_row + row;
+        for x in columns.clone() {
+            let (r, g, b) = encode(&shade(x, y));
+            pixels[x * 3..x * 3 + 3].copy_from_slice(&[r, g, b]);
+        }
+    }
 }
 
 impl Default for World {
@@ -177,17 +919,25 @@ impl Default for World {
 #[cfg(test)]
 mod test {
 
-    use super::World;
+    use super::{Depth, World};
+    use crate::render::{camera::Camera, environment::Environment, render_config::RenderConfig};
 
     use crate::{
-        draw::color::Color,
+        draw::{canvas::Canvas, color::Color},
         math::{
-            point::Point, ray::Ray, transformation::Transformable, tuple::Tuple, vector::Vector,
+            epsilon::ApproxEq,
+            point::Point,
+            ray::Ray,
+            transformation::{translate, Transformable},
+            tuple::Tuple,
+            vector::Vector,
         },
         render::{
             intersections::{HitComputation, Intersection, Intersections},
             light::Light,
-            lights::point_light::PointLight,
+            lights::{
+                area_light::AreaLight, directional_light::DirectionalLight, point_light::PointLight,
+            },
             material::{Material, Materialable},
             object::Object,
             pattern::Pattern,
@@ -201,6 +951,27 @@ mod test {
         assert_eq!(world.lights.len(), 0);
     }
 
+    #[test]
+    fn removing_an_object_keeps_the_others_in_order() {
+        let mut world = World::new();
+        let light = Light::Point(PointLight::new(Point::new(0.0, 10.0, 0.0), Color::white()));
+        world.add_light(light.clone());
+        let a = Object::new_sphere();
+        let b = Object::new_cube();
+        let c = Object::new_plane();
+        world.add_object(a.clone());
+        world.add_object(b.clone());
+        world.add_object(c.clone());
+
+        assert_eq!(world.remove_object(1), Some(b));
+        assert_eq!(world.objects(), [a, c]);
+        assert_eq!(world.remove_object(2), None);
+
+        world.clear_objects();
+        assert!(world.objects().is_empty());
+        assert_eq!(world.lights(), [light]);
+    }
+
     #[test]
     fn test_default_world() {
         let world = World::default();
@@ -300,28 +1071,56 @@ mod test {
     fn no_shadow_when_nothing_is_collinear_with_point_and_light() {
         let w = World::default();
         let p = Point::new(0.0, 10.0, 0.0);
-        assert_eq!(w.is_shadowed(&p), false);
+        assert_eq!(w.is_shadowed(&p, &w.lights[0]), false);
     }
 
     #[test]
     fn shadow_when_object_is_between_pint_and_light() {
         let w = World::default();
         let p = Point::new(10.0, -10.0, 10.0);
-        assert_eq!(w.is_shadowed(&p), true);
+        assert_eq!(w.is_shadowed(&p, &w.lights[0]), true);
     }
 
     #[test]
     fn no_shadow_when_object_is_behind_light() {
         let w = World::default();
         let p = Point::new(-20.0, 20.0, -20.0);
-        assert_eq!(w.is_shadowed(&p), false);
+        assert_eq!(w.is_shadowed(&p, &w.lights[0]), false);
     }
 
     #[test]
     fn no_shadow_when_object_is_behind_point() {
         let w = World::default();
         let p = Point::new(-2.0, 2.0, -2.0);
-        assert_eq!(w.is_shadowed(&p), false);
+        assert_eq!(w.is_shadowed(&p, &w.lights[0]), false);
+    }
+
+    #[test]
+    fn emission_is_added_once_whatever_the_lights() {
+        let glow = Color::new(0.5, 0.25, 0.0);
+        // No ambient, diffuse or specular light, so only emission shows
+        let sphere = Object::new_sphere().with_material(
+            Material::default()
+                .with_ambient(0.0)
+                .with_diffuse(0.0)
+                .with_specular(0.0)
+                .with_emission(glow),
+        );
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let ints = Intersections::new().with_intersections(vec![Intersection::new(4.0, &sphere)]);
+        let comp = HitComputation::new(&ints, 0, &ray);
+
+        let mut w = World::new();
+        w.add_object(sphere.clone());
+        assert_eq!(w.shade_hit(&comp, 5), glow);
+
+        for x in [-10.0, 10.0] {
+            w.add_light(Light::Point(PointLight::new(
+                Point::new(x, 10.0, -10.0),
+                Color::white(),
+            )));
+        }
+        assert_eq!(w.shade_hit(&comp, 5), glow);
     }
 
     #[test]
@@ -349,6 +1148,34 @@ mod test {
         assert_eq!(color, Color::new(0.1, 0.1, 0.1));
     }
 
+    #[test]
+    fn emissive_surfaces_glow_in_shadow() {
+        let mut w = World::new();
+        w.add_light(Light::Point(PointLight::new(
+            Point::new(0.0, 0.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
+        )));
+        w.add_object(Object::new_sphere());
+        w.add_object(
+            Object::new_sphere()
+                .translate(0.0, 0.0, 10.0)
+                .with_emission(Color::new(0.5, 0.0, 0.0)),
+        );
+
+        let ray = Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0));
+        let intersection = Intersection::new(4.0, &w.objects[1]);
+        let comp = HitComputation::new(
+            &Intersections::new().with_intersections(vec![intersection]),
+            0,
+            &ray,
+        );
+        assert!(w.is_shadowed(&comp.over_point, &w.lights[0]));
+
+        let color = w.shade_hit(&comp, 5);
+        assert!(color.as_tuple().0 >= 0.5);
+        assert_eq!(color, Color::new(0.6, 0.1, 0.1));
+    }
+
     #[test]
     fn reflected_color_for_non_reflective_material() {
         let w = World::default();
@@ -552,6 +1379,58 @@ mod test {
         assert_eq!(got, want);
     }
 
+    #[test]
+    fn fresnel_surfaces_reflect_more_at_grazing_angles() {
+        let mut w = World::new();
+        w.set_background(Color::white());
+        let mirror = Object::new_plane()
+            .with_pattern(Pattern::new_solid(Color::black()))
+            .with_ambient(0.0)
+            .with_refractive_index(1.5)
+            .with_fresnel(true);
+        w.add_object(mirror);
+
+        let head_on = Ray::new(Point::new(0.0, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let grazing = Ray::new(
+            Point::new(0.0, 1.0, 0.0),
+            Vector::new(0.0, -0.1, 1.0).normalize(),
+        );
+        let (head_on, _, _) = w.color_at(&head_on, 5).as_tuple();
+        let (grazing, _, _) = w.color_at(&grazing, 5).as_tuple();
+
+        // Glass reflects about 4% straight on
+        assert!(head_on.approx_eq(0.04));
+        assert!(grazing > 0.5);
+    }
+
+    #[test]
+    fn absorbing_glass_darkens_with_thickness() {
+        let color_through = |depth: f64| {
+            let mut w = World::new();
+            w.set_background(Color::white());
+            w.add_object(
+                Object::new_cube()
+                    .with_ambient(0.0)
+                    .with_diffuse(0.0)
+                    .with_specular(0.0)
+                    .with_transparency(1.0)
+                    .with_refractive_index(1.0)
+                    .with_absorption(Color::new(0.5, 0.5, 0.5))
+                    .scale(1.0, 1.0, depth / 2.0),
+            );
+            let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+            w.color_at(&ray, 5)
+        };
+
+        let thin = color_through(0.5);
+        let thick = color_through(4.0);
+        let (thin_r, _, _) = thin.as_tuple();
+        let (thick_r, _, _) = thick.as_tuple();
+        assert!(thick_r < thin_r);
+        assert_eq!(thin, Color::white() * (-0.25_f64).exp());
+        assert_eq!(thick, Color::white() * (-2.0_f64).exp());
+    }
+
     #[test]
     fn shade_hit_with_transparent_material() {
         let root_2 = f64::sqrt(2.0);
@@ -575,7 +1454,8 @@ mod test {
             Intersections::new().with_intersections(vec![Intersection::new(root_2, &floor)]);
         let comp = HitComputation::new(&&intersections, 0, &ray);
         let got = w.shade_hit(&comp, 5);
-        let want = Color::new(0.93642, 0.68642, 0.68642);
+        // The book's 0.93642, 0.68642, 0.68642 includes the floor's full diffuse term
+        let want = Color::new(0.64321, 0.39321, 0.39321);
         assert_eq!(got, want);
     }
 
@@ -603,7 +1483,723 @@ mod test {
             Intersections::new().with_intersections(vec![Intersection::new(root_2, &floor)]);
         let comp = HitComputation::new(&&intersections, 0, &ray);
         let got = w.shade_hit(&comp, 5);
-        let want = Color::new(0.93391, 0.69643, 0.69243);
+        // The book's 0.93391, 0.69643, 0.69243 includes the floor's full diffuse term
+        let want = Color::new(0.64070, 0.40322, 0.39922);
         assert_eq!(got, want);
     }
+
+    #[test]
+    fn reflection_survives_when_refraction_depth_is_exhausted() {
+        let root_2_2 = f64::sqrt(2.0) / 2.0;
+        let mut w = World::new();
+        w.add_light(Light::Point(PointLight::new(
+            Point::new(0.0, 10.0, -10.0),
+            Color::white(),
+        )));
+        w.add_object(
+            Object::new_plane()
+                .with_ambient(0.0)
+                .with_diffuse(0.0)
+                .with_specular(0.0)
+                .with_transparency(1.0)
+                .with_refractive_index(1.0),
+        );
+        w.add_object(
+            Object::new_plane()
+                .with_pattern(Pattern::new_solid(Color::black()))
+                .with_ambient(0.0)
+                .with_diffuse(0.0)
+                .with_specular(0.0)
+                .with_reflective(1.0)
+                .translate(0.0, -2.0, 0.0),
+        );
+        w.add_object(
+            Object::new_sphere()
+                .with_pattern(Pattern::new_solid(Color::red()))
+                .scale(0.5, 0.5, 0.5)
+                .translate(0.0, -1.0, 1.0),
+        );
+
+        let ray = Ray::new(
+            Point::new(0.0, 1.0, -3.0),
+            Vector::new(0.0, -root_2_2, root_2_2),
+        );
+
+        let no_reflection = w.color_at(&ray, Depth::new(0, 1));
+        assert_eq!(no_reflection, Color::black());
+
+        let separate = w.color_at(&ray, Depth::new(5, 1));
+        assert!(separate.as_tuple().0 > 0.0);
+        assert!(separate.as_tuple().1.abs() < 1e-9);
+    }
+
+    #[test]
+    fn sampling_a_subset_of_lights_scales_the_surface_color() {
+        let mut w = World::new();
+        let key = Light::Point(PointLight::new(
+            Point::new(-10.0, 10.0, -10.0),
+            Color::white(),
+        ));
+        let fill = Light::Point(PointLight::new(
+            Point::new(10.0, 10.0, -10.0),
+            Color::new(0.5, 0.5, 0.5),
+        ));
+        w.add_light(key.clone());
+        w.add_light(fill);
+        w.add_object(Object::new_sphere());
+
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let exact = w.color_at(&ray, 5);
+
+        w.set_light_sample_count(Some(2));
+        assert_eq!(w.light_sample_count(), Some(2));
+        assert_eq!(w.color_at(&ray, 5), exact);
+
+        w.set_light_sample_count(Some(1));
+        let obj = &w.objects[0];
+        let intersections =
+            Intersections::new().with_intersections(vec![Intersection::new(4.0, obj)]);
+        let comp = HitComputation::new(&intersections, 0, &ray);
+        let key_only = key.lighting(
+            obj,
+            &obj.get_material(),
+            comp.over_point,
+            comp.eye,
+            comp.normal,
+            false,
+        );
+        assert_eq!(w.color_at(&ray, 5), key_only * 1.5);
+    }
+
+    #[test]
+    fn cloned_world_can_be_changed_independently() {
+        let original = World::default();
+        let mut variant = original.clone();
+        variant.lights[0] = Light::Point(PointLight::new(
+            Point::new(0.0, 0.25, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+
+        assert_ne!(original.lights[0], variant.lights[0]);
+        assert_eq!(
+            original.lights[0].get_position(),
+            Some(Point::new(-10.0, 10.0, -10.0))
+        );
+
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let want = original.color_at(&ray, 5);
+        let got = std::thread::spawn(move || original.color_at(&ray, 5))
+            .join()
+            .unwrap();
+        assert_eq!(got, want);
+    }
+
+    #[test]
+    fn firefly_clamp_limits_bright_samples_before_averaging() {
+        let mut w = World::new();
+        let samples = [
+            Color::new(0.5, 0.5, 0.5),
+            Color::new(0.4, 0.4, 0.4),
+            Color::new(50.0, 50.0, 50.0),
+            Color::new(0.6, 0.6, 0.6),
+        ];
+
+        assert_eq!(w.firefly_clamp(), None);
+        let unclamped = w.average_samples(&samples);
+        assert_eq!(unclamped, Color::new(12.875, 12.875, 12.875));
+
+        w.set_firefly_clamp(Some(1.0));
+        let clamped = w.average_samples(&samples);
+        assert_eq!(clamped, Color::new(0.625, 0.625, 0.625));
+        assert!(clamped.luminance() < unclamped.luminance());
+    }
+
+    #[test]
+    fn default_world_intersection_test_count() {
+        // Counts shape tests rather than timing them, so it's the same on every machine.
+        // Each primary ray should test each of the default world's two spheres once.
+        let world = World::default();
+        let camera = Camera::new(32, 32, std::f64::consts::PI / 9.0).view_transform(
+            &Point::new(0.0, 0.0, -5.0),
+            &Point::new(0.0, 0.0, 0.0),
+            &Vector::new(0.0, 1.0, 0.0),
+        );
+
+        let mut tests = 0;
+        for y in 0..32 {
+            for x in 0..32 {
+                let mut ints = Intersections::new();
+                world.intersect(&camera.ray_for_pixel(x, y), &world.objects, &mut ints);
+                tests += ints.tests();
+            }
+        }
+        assert_eq!(tests, 32 * 32 * world.objects.len());
+    }
+
+    #[test]
+    fn finding_the_objects_containing_a_point() {
+        let mut w = World::default();
+        w.add_object(Object::new_plane().translate(0.0, -1.0, 0.0));
+
+        assert_eq!(w.point_inside(&Point::new(0.0, 0.0, 0.0)), vec![0, 1]);
+        assert_eq!(w.point_inside(&Point::new(0.0, 0.0, 0.75)), vec![0]);
+        assert!(w.point_inside(&Point::new(0.0, 0.0, -5.0)).is_empty());
+    }
+
+    #[test]
+    fn inside_hits_use_the_interior_material() {
+        let mut w = World::new();
+        w.add_light(Light::Point(PointLight::new(
+            Point::new(-10.0, 10.0, -10.0),
+            Color::white(),
+        )));
+        w.add_object(
+            Object::new_cube().with_interior_material(
+                Material::default()
+                    .with_pattern(Pattern::new_solid(Color::red()))
+                    .with_ambient(1.0)
+                    .with_diffuse(0.0)
+                    .with_specular(0.0),
+            ),
+        );
+
+        let inside = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(w.color_at(&inside, 5), Color::red());
+
+        let outside = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let (r, g, b) = w.color_at(&outside, 5).as_tuple();
+        assert!(r > 0.0 && r == g && g == b);
+    }
+
+    #[test]
+    fn intersecting_a_single_object_of_the_world() {
+        let world = World::default();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        let mut xs = Intersections::new();
+        world.intersect_object(&r, 1, &mut xs);
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t(), 4.5);
+        assert_eq!(xs[1].t(), 5.5);
+        assert!(xs.iter().all(|int| int.object() == &world.objects[1]));
+
+        let mut xs = Intersections::new();
+        world.intersect_object(&r, 2, &mut xs);
+        assert_eq!(xs.len(), 0);
+    }
+
+    #[test]
+    fn russian_roulette_converges_to_the_deep_recursion_result() {
+        let mut w = World::new();
+        w.add_light(Light::Point(PointLight::new(
+            Point::new(0.0, 0.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        )));
+        w.add_object(
+            Object::new_plane()
+                .with_reflective(0.5)
+                .translate(0.0, -1.0, 0.0),
+        );
+        w.add_object(
+            Object::new_plane()
+                .with_reflective(0.5)
+                .translate(0.0, 1.0, 0.0),
+        );
+        let ray = Ray::new(
+            Point::new(0.0, 0.0, 0.0),
+            Vector::new(0.3, 1.0, 0.2).normalize(),
+        );
+
+        let reference = w.color_at(&ray, Depth::new(64, 64));
+        let truncated = w.color_at(&ray, Depth::new(1, 1));
+
+        w.set_russian_roulette_depth(Some(1));
+        let samples = 2000;
+        let mut total = Color::black();
+        for seed in 0..samples {
+            w.set_seed(seed);
+            total = total + w.color_at(&ray, Depth::new(1, 1));
+        }
+        let average = total * (1.0 / samples as f64);
+
+        let error = (average.luminance() - reference.luminance()).abs();
+        assert!(error < 0.02 * reference.luminance());
+        assert!(error < (truncated.luminance() - reference.luminance()).abs());
+    }
+
+    #[test]
+    fn missed_rays_return_the_environment_color() {
+        let mut w = World::default();
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(w.color_at(&ray, 5), Color::black());
+
+        w.set_environment(Environment::Solid(Color::blue()));
+        assert_eq!(w.color_at(&ray, 5), Color::blue());
+
+        w.set_background(Color::red());
+        assert_eq!(w.color_at(&ray, 5), Color::red());
+    }
+
+    #[test]
+    fn intersection_histogram_counts_tests_per_object() {
+        let mut w = World::new();
+        w.add_light(Light::Point(PointLight::new(
+            Point::new(-10.0, 10.0, -10.0),
+            Color::white(),
+        )));
+        let pair = || {
+            Object::new_group(vec![
+                Object::new_sphere().translate(-1.0, 0.0, 0.0),
+                Object::new_sphere().translate(1.0, 0.0, 0.0),
+            ])
+        };
+        w.add_object(Object::new_sphere());
+        w.add_object(pair());
+        w.add_object(pair().translate(0.0, 100.0, 0.0));
+
+        let camera = Camera::new(11, 11, std::f64::consts::PI / 3.0).view_transform(
+            &Point::new(0.0, 0.0, -5.0),
+            &Point::new(0.0, 0.0, 0.0),
+            &Vector::new(0.0, 1.0, 0.0),
+        );
+        // Shadow rays are counted too, so take the lights away to count only camera rays
+        let mut unlit = w.clone();
+        unlit.lights.clear();
+        let histogram = unlit.intersection_histogram(&camera);
+
+        // Every ray tests each top level object once. Only rays reaching the group in
+        // view go on to test its children.
+        assert_eq!(histogram.len(), 3);
+        assert_eq!(histogram[0], 11 * 11);
+        assert!(histogram[1] > 11 * 11);
+        assert_eq!(histogram[2], 11 * 11);
+
+        let lit = w.intersection_histogram(&camera);
+        assert!(lit[1] > histogram[1]);
+    }
+
+    #[test]
+    fn refraction_aov_only_covers_the_transparent_object() {
+        let mut w = World::new();
+        w.add_light(Light::Point(PointLight::new(
+            Point::new(-10.0, 10.0, -10.0),
+            Color::white(),
+        )));
+        w.add_object(
+            Object::new_plane()
+                .with_reflective(1.0)
+                .translate(0.0, -1.0, 0.0),
+        );
+        w.add_object(
+            Object::new_sphere()
+                .with_pattern(Pattern::new_solid(Color::black()))
+                .with_diffuse(0.0)
+                .with_transparency(1.0)
+                .with_refractive_index(1.5)
+                .translate(0.0, 0.5, 0.0),
+        );
+        w.add_object(
+            Object::new_plane()
+                .with_pattern(Pattern::new_solid(Color::white()))
+                .with_ambient(1.0)
+                .rotate_x(std::f64::consts::PI / 2.0)
+                .translate(0.0, 0.0, 10.0),
+        );
+
+        let camera = Camera::new(11, 11, std::f64::consts::PI / 3.0).view_transform(
+            &Point::new(0.0, 0.0, -5.0),
+            &Point::new(0.0, 0.0, 0.0),
+            &Vector::new(0.0, 1.0, 0.0),
+        );
+        let aovs = w.render_aovs(&camera);
+        let is_black = |canvas: &Canvas, pos| canvas.pixel_at(pos).unwrap() == Color::black();
+
+        // Center pixel looks through the glass sphere
+        assert!(!is_black(&aovs.refraction, (5, 4)));
+        // Bottom corner hits the mirror floor, top corner only sees the backdrop
+        assert!(is_black(&aovs.refraction, (0, 10)));
+        assert!(!is_black(&aovs.reflection, (0, 10)));
+        assert!(is_black(&aovs.refraction, (0, 0)));
+    }
+
+    #[test]
+    fn alpha_render_marks_hits_opaque_and_misses_transparent() {
+        let w = World::default();
+        let camera = Camera::new(11, 11, std::f64::consts::PI / 2.0).view_transform(
+            &Point::new(0.0, 0.0, -5.0),
+            &Point::new(0.0, 0.0, 0.0),
+            &Vector::new(0.0, 1.0, 0.0),
+        );
+        let canvas = w.render_with_alpha(&camera);
+        let rgba = canvas.rgba_bytes();
+        let alpha = |x: usize, y: usize| rgba[((y * 11) + x) * 4 + 3];
+
+        assert_eq!(alpha(5, 5), 255);
+        assert_eq!(alpha(0, 0), 0);
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn alpha_renders_save_as_rgba_pngs() {
+        let w = World::default();
+        let camera = Camera::new(11, 11, std::f64::consts::PI / 2.0).view_transform(
+            &Point::new(0.0, 0.0, -5.0),
+            &Point::new(0.0, 0.0, 0.0),
+            &Vector::new(0.0, 1.0, 0.0),
+        );
+        let canvas = w.render_with_alpha(&camera);
+        let dir = std::env::temp_dir();
+        let dir = dir.to_str().unwrap();
+        canvas.save_png(dir, "alpha_render_png_test").unwrap();
+
+        let path = [dir, "/alpha_render_png_test.png"].concat();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let mut reader = png::Decoder::new(bytes.as_slice()).read_info().unwrap();
+        assert_eq!(reader.info().color_type, png::ColorType::Rgba);
+        let mut decoded = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut decoded).unwrap();
+
+        let alpha = |x: usize, y: usize| decoded[((y * 11) + x) * 4 + 3];
+        assert_eq!(alpha(0, 0), 0);
+        assert_eq!(alpha(5, 5), 255);
+        assert_eq!(decoded, canvas.rgba_bytes());
+    }
+
+    #[test]
+    fn surfaces_beyond_a_lights_range_only_get_ambient() {
+        let mut w = World::new();
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::white());
+        w.add_object(Object::new_sphere());
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+
+        w.add_light(Light::Point(light.with_range(20.0)));
+        let lit = w.color_at(&ray, 5);
+
+        let mut w = World::new();
+        w.add_object(Object::new_sphere());
+        w.add_light(Light::Point(light.with_range(5.0)));
+        let ambient = w.color_at(&ray, 5);
+
+        assert_eq!(ambient, Color::new(0.1, 0.1, 0.1));
+        assert!(lit.as_tuple().0 > ambient.as_tuple().0);
+    }
+
+    #[test]
+    fn rendered_frame_matches_the_camera_size() {
+        let w = World::default();
+        let camera = Camera::new(16, 9, std::f64::consts::PI / 2.0).view_transform(
+            &Point::new(0.0, 0.0, -5.0),
+            &Point::new(0.0, 0.0, 0.0),
+            &Vector::new(0.0, 1.0, 0.0),
+        );
+        let frame = w.render_frame(&camera);
+
+        assert_eq!((frame.width, frame.height), (16, 9));
+        assert_eq!(frame.rgba.len(), 16 * 9 * 4);
+        assert_eq!(frame.rgba, w.render_with_alpha(&camera).rgba_bytes());
+    }
+
+    #[test]
+    fn adding_two_copies_of_a_model_at_different_offsets() {
+        let path = std::env::temp_dir().join("world_add_model_test.obj");
+        std::fs::write(&path, "v 0 1 0\nv -1 0 0\nv 1 0 0\nf 1 2 3\n").unwrap();
+
+        let mut world = World::new();
+        let material = Material::default().with_ambient(0.5);
+        for x in [-20.0, 20.0] {
+            world
+                .add_model(
+                    path.to_str().unwrap(),
+                    translate(x, 0.0, 0.0),
+                    material.clone(),
+                )
+                .unwrap();
+        }
+
+        assert_eq!(world.objects.len(), 2);
+        assert!(world.objects.iter().all(|o| o.children().is_some()));
+
+        let left = world.objects[0].bounds();
+        let right = world.objects[1].bounds();
+        assert_eq!(right.center() - left.center(), Vector::new(40.0, 0.0, 0.0));
+        assert_eq!(right.max() - right.min(), left.max() - left.min());
+
+        let mut leaf = &world.objects[1];
+        while let Some(children) = leaf.children() {
+            leaf = &children[0];
+        }
+        assert_eq!(leaf.get_material(), material);
+    }
+
+    #[test]
+    fn default_render_config_reproduces_render() {
+        let world = World::default();
+        let camera = Camera::new(11, 11, std::f64::consts::PI / 3.0).view_transform(
+            &Point::new(0.0, 0.0, -5.0),
+            &Point::new(0.0, 0.0, 0.0),
+            &Vector::new(0.0, 1.0, 0.0),
+        );
+
+        let want = world.render(&camera);
+        let got = world.render_with_config(&camera, &RenderConfig::default());
+        for y in 0..11 {
+            for x in 0..11 {
+                assert_eq!(got.pixel_at((x, y)), want.pixel_at((x, y)));
+            }
+        }
+
+        let config = RenderConfig::default().with_samples(4).with_seed(7);
+        let a = world.render_with_config(&camera, &config);
+        let b = world.render_with_config(&camera, &config);
+        assert_eq!(a.pixel_at((5, 5)), b.pixel_at((5, 5)));
+    }
+
+    #[test]
+    fn unlit_render_shows_stripe_colors_regardless_of_light() {
+        let sphere = Object::new_sphere()
+            .with_pattern(Pattern::new_stripe(Color::red(), Color::blue()).scale(0.5, 1.0, 1.0));
+        let camera = Camera::new(21, 21, std::f64::consts::PI / 3.0).view_transform(
+            &Point::new(0.0, 0.0, -5.0),
+            &Point::new(0.0, 0.0, 0.0),
+            &Vector::new(0.0, 1.0, 0.0),
+        );
+
+        let mut renders = vec![];
+        for light_position in [Point::new(-10.0, 10.0, -10.0), Point::new(10.0, 0.0, 10.0)] {
+            let mut world = World::new();
+            world.add_object(sphere.clone());
+            world.add_light(Light::Point(PointLight::new(
+                light_position,
+                Color::white(),
+            )));
+            renders.push(world.render_unlit(&camera));
+        }
+
+        for y in 0..21 {
+            for x in 0..21 {
+                let color = renders[0].pixel_at((x, y)).unwrap();
+                assert!(color == Color::red() || color == Color::blue() || color == Color::black());
+                assert_eq!(renders[1].pixel_at((x, y)), Some(color));
+            }
+        }
+        assert_eq!(renders[0].pixel_at((10, 10)), Some(Color::red()));
+    }
+
+    #[test]
+    fn samples_are_averaged_in_linear_space_before_srgb_encoding() {
+        let world = World::new();
+        let edge = world.average_samples(&[Color::black(), Color::white()]);
+        let mut canvas = Canvas::new(1, 1).with_srgb();
+        canvas.set_pixel((0, 0), &edge);
+
+        // Averaging the encoded bytes instead would write 128
+        let dir = std::env::temp_dir();
+        let dir = dir.to_str().unwrap();
+        canvas.save(dir, "srgb_average_test").unwrap();
+        let path = [dir, "/srgb_average_test.ppm"].concat();
+        let ppm = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(ppm, "P3\n1 1\n255\n188 188 188\n");
+    }
+
+    #[test]
+    fn srgb_renders_encode_the_averaged_samples() {
+        let world = World::new();
+        let camera = Camera::new(2, 2, std::f64::consts::PI / 2.0);
+        let config = RenderConfig::new()
+            .with_samples(4)
+            .with_environment(Environment::Solid(Color::new(0.5, 0.5, 0.5)));
+
+        let linear = world.render_with_config(&camera, &config);
+        assert!(linear.as_bytes().iter().all(|byte| *byte == 127));
+
+        let encoded = world.render_with_config(&camera, &config.with_srgb(true));
+        assert!(encoded.is_srgb());
+        assert!(encoded.as_bytes().iter().all(|byte| *byte == 188));
+    }
+
+    #[test]
+    fn each_light_casts_its_own_shadow() {
+        let mut w = World::new();
+        let left = Light::Point(PointLight::new(
+            Point::new(-10.0, 0.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        let right = Light::Point(PointLight::new(
+            Point::new(10.0, 0.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        w.add_light(left.clone());
+        w.add_light(right.clone());
+        w.add_object(Object::new_sphere());
+
+        // Just left of the sphere, facing the left light with the sphere blocking the right
+        let p = Point::new(-2.0, 0.0, 0.0);
+        assert!(!w.is_shadowed(&p, &left));
+        assert!(w.is_shadowed(&p, &right));
+
+        let r = Ray::new(Point::new(-5.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+        let i = Intersection::new(4.0, &w.objects[0]);
+        let comp = HitComputation::from_single(&i, &r);
+        let both = w.shade_hit(&comp, 1);
+
+        let mut only_left = World::new();
+        only_left.add_light(left);
+        only_left.add_object(Object::new_sphere());
+        let i = Intersection::new(4.0, &only_left.objects[0]);
+        let comp = HitComputation::from_single(&i, &r);
+        let lit = only_left.shade_hit(&comp, 1);
+
+        // The right light is blocked, so it only adds its ambient term
+        let ambient = Color::new(1.0, 1.0, 1.0) * Material::default().ambient;
+        assert_eq!(both, lit + ambient);
+    }
+
+    #[test]
+    fn directional_light_shadows_ignore_distance() {
+        let mut w = World::new();
+        let sun = Light::Directional(DirectionalLight::new(
+            Vector::new(0.0, -1.0, 0.0),
+            Color::white(),
+        ));
+        w.add_light(sun.clone());
+        w.add_object(Object::new_sphere().translate(0.0, 1000.0, 0.0));
+
+        assert!(w.is_shadowed(&Point::new(0.0, 0.0, 0.0), &sun));
+        assert!(!w.is_shadowed(&Point::new(5.0, 0.0, 0.0), &sun));
+    }
+
+    #[test]
+    fn threaded_render_matches_the_serial_render() {
+        let w = World::default();
+        let camera = Camera::new(21, 13, std::f64::consts::PI / 2.0).view_transform(
+            &Point::new(0.0, 0.0, -5.0),
+            &Point::new(0.0, 0.0, 0.0),
+            &Vector::new(0.0, 1.0, 0.0),
+        );
+
+        let serial = w.render(&camera);
+        for threads in [2, 4, 13, 64] {
+            let threaded = w.render_threaded(&camera, threads);
+            assert_eq!(threaded.as_bytes(), serial.as_bytes());
+        }
+    }
+
+    #[test]
+    fn rendering_a_region_matches_the_full_render_inside_it() {
+        let w = World::default();
+        let camera = Camera::new(11, 11, std::f64::consts::PI / 2.0).view_transform(
+            &Point::new(0.0, 0.0, -5.0),
+            &Point::new(0.0, 0.0, 0.0),
+            &Vector::new(0.0, 1.0, 0.0),
+        );
+
+        let full = w.render(&camera);
+        let region = w.render_region(&camera, 3, 4, 8, 20);
+        assert_eq!(region.dimensions(), (11, 11));
+        for y in 0..11 {
+            for x in 0..11 {
+                let want = if (3..8).contains(&x) && y >= 4 {
+                    full.pixel_at((x, y))
+                } else {
+                    Some(Color::black())
+                };
+                assert_eq!(region.pixel_at((x, y)), want, "pixel ({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn rendering_with_progress_reports_every_row_in_order() {
+        let w = World::default();
+        let camera = Camera::new(9, 7, std::f64::consts::PI / 2.0).view_transform(
+            &Point::new(0.0, 0.0, -5.0),
+            &Point::new(0.0, 0.0, 0.0),
+            &Vector::new(0.0, 1.0, 0.0),
+        );
+
+        let mut reports = vec![];
+        let image = w.render_with_progress(&camera, |done, total| reports.push((done, total)));
+        let want: Vec<(usize, usize)> = (1..=7).map(|done| (done, 7)).collect();
+        assert_eq!(reports, want);
+        assert_eq!(image.as_bytes(), w.render(&camera).as_bytes());
+    }
+
+    #[test]
+    fn baked_emissive_planes_light_nearby_objects() {
+        let mut w = World::new();
+        w.add_object(Object::new_sphere());
+        w.add_object(
+            Object::new_plane()
+                .scale(2.0, 1.0, 2.0)
+                .translate(0.0, 5.0, 0.0)
+                .with_emission(Color::white()),
+        );
+        // Looking down at the top of the sphere from under the panel
+        let ray = Ray::new(Point::new(0.0, 3.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        assert_eq!(w.color_at(&ray, 5), Color::black());
+
+        w.bake_emissive_lights();
+        assert_eq!(w.lights().len(), 1);
+        match &w.lights()[0] {
+            Light::Area(panel) => {
+                assert_eq!(panel.position, Point::new(0.0, 5.0, 0.0));
+                assert_eq!(panel.intensity, Color::white());
+            }
+            other => panic!("expected an area light, got {:?}", other),
+        }
+
+        // Straight under the panel the sphere picks up most of its diffuse
+        let (r, g, b) = w.color_at(&ray, 5).as_tuple();
+        assert!(r > 0.5);
+        assert_eq!((g, b), (r, r));
+    }
+
+    #[test]
+    fn area_lights_cast_a_penumbra_between_lit_and_shadowed_floor() {
+        // The scene from the soft_shadows example at a lower resolution
+        let light = AreaLight::new(
+            Point::new(-3.0, 4.0, -2.0),
+            Vector::new(2.0, 0.0, 0.0),
+            4,
+            Vector::new(0.0, 2.0, 0.0),
+            4,
+            Color::white(),
+        )
+        .with_jitter(7);
+        let mut floor_only = World::new();
+        floor_only.add_light(Light::Area(light));
+        floor_only.add_object(Object::new_plane().with_specular(0.0));
+        let mut w = floor_only.clone();
+        w.add_object(
+            Object::new_sphere()
+                .translate(0.0, 1.0, 0.0)
+                .with_pattern(Pattern::new_solid(Color::new(1.0, 0.2, 0.2))),
+        );
+        let camera = Camera::new(40, 20, std::f64::consts::PI / 3.0).view_transform(
+            &Point::new(0.0, 3.0, -6.0),
+            &Point::new(0.5, 0.5, 0.0),
+            &Vector::new(0.0, 1.0, 0.0),
+        );
+        let image = w.render(&camera);
+        let unshadowed = floor_only.render(&camera);
+
+        // Each floor pixel along a row through the shadow, as the share of the light
+        // that reaches it: 0.0 in the umbra and 1.0 where nothing is in the way
+        let ambient = 0.1;
+        let lit: Vec<f64> = (0..40)
+            .map(|x| (image.pixel_at((x, 12)), unshadowed.pixel_at((x, 12))))
+            .filter_map(|(seen, full)| {
+                let (r, g, _) = seen?.as_tuple();
+                let full = full?.as_tuple().1;
+                // Skip the red sphere itself
+                ((r - g).abs() < 0.05).then(|| (g - ambient) / (full - ambient))
+            })
+            .collect();
+
+        assert!(lit.iter().any(|share| *share > 0.99));
+        assert!(lit.iter().any(|share| *share < 0.01));
+        assert!(lit.iter().any(|share| *share > 0.1 && *share < 0.9));
+    }
 }